@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use premint_airdrop::instruction::deserialize_instruction_data;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = deserialize_instruction_data(data);
+});
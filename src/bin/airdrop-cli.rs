@@ -0,0 +1,517 @@
+//! Command line tool for operating an airdrop
+
+use std::{process::exit, str::FromStr};
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use num_traits::FromPrimitive;
+use premint_airdrop::{
+    client::{self, find_airdrop_config, find_airdrop_user_data, find_mint_authority},
+    error::AirdropError,
+    instruction::InitializeAirdropArgs,
+    state::{RevenueSplit, MAX_REVENUE_SPLITS},
+};
+use solana_client::{client_error::ClientError, rpc_client::RpcClient};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+type CliResult = Result<(), String>;
+
+fn main() {
+    let matches = App::new("airdrop-cli")
+        .about("Operate premint airdrop")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .global(true)
+                .takes_value(true)
+                .default_value("http://localhost:8899")
+                .help("RPC URL"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .long("keypair")
+                .global(true)
+                .takes_value(true)
+                .help("Fee payer and signer keypair [default: ~/.config/solana/id.json]"),
+        )
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Initialize airdrop owned by keypair")
+                .arg(arg("name", "Airdrop name, up to 16 bytes").required(true))
+                .arg(arg("amount", "Airdrop supply").required(true))
+                .arg(arg("price", "Price in lamports").required(true))
+                .arg(
+                    arg("base-uri", "Base URI of revealed metadata, up to 200 bytes")
+                        .required(true),
+                )
+                .arg(arg("symbol", "Token symbol").required(true))
+                .arg(arg("revenue-wallet", "Wallet receiving revenues").required(true))
+                .arg(
+                    arg("admin", "Admin account, update authority of minted tokens").required(true),
+                )
+                .arg(arg("placeholder-uri", "Metadata URI until reveal"))
+                .arg(
+                    arg(
+                        "max-mints-per-tx",
+                        "Maximum mints in one transaction, 0 for unlimited",
+                    )
+                    .default_value("0"),
+                )
+                .arg(
+                    arg(
+                        "fairness-bps",
+                        "Caps mints per transaction to this share of remaining supply",
+                    )
+                    .default_value("0"),
+                )
+                .arg(
+                    arg("trait-count", "Number of traits derived for every mint")
+                        .default_value("0"),
+                )
+                .arg(arg("cooldown", "Seconds between user mints").default_value("21600"))
+                .arg(
+                    arg("referral-bps", "Referrer cut of price in basis points").default_value("0"),
+                )
+                .arg(
+                    arg(
+                        "bot-tax",
+                        "Lamports charged instead of failing ineligible mints",
+                    )
+                    .default_value("0"),
+                )
+                .arg(
+                    arg("decimals", "Decimals of every minted token, 0 for NFTs")
+                        .default_value("0"),
+                )
+                .arg(
+                    arg("quantity", "Base units of token every mint gives, 1 for NFTs")
+                        .default_value("1"),
+                )
+                .arg(
+                    arg(
+                        "registration-fee",
+                        "Lamports paid to revenue wallet on user registration",
+                    )
+                    .default_value("0"),
+                )
+                .arg(
+                    arg(
+                        "min-payer-balance",
+                        "Lamports payer must hold to mint, 0 to disable",
+                    )
+                    .default_value("0"),
+                )
+                .arg(
+                    arg(
+                        "escrow-timeout",
+                        "Seconds before unclaimed escrow can be swept, 0 to never sweep",
+                    )
+                    .default_value("0"),
+                )
+                .arg(flag("auto-lock", "Make revealed tokens immutable"))
+                .arg(flag(
+                    "charge-payer",
+                    "Charge price from payer instead of user",
+                ))
+                .arg(flag("raffle", "Only raffle winners can mint"))
+                .arg(flag(
+                    "open-mint",
+                    "Mint without registration and per user limits",
+                ))
+                .arg(flag(
+                    "deny-cpi-mint",
+                    "Only allow mints from top level instructions",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Print airdrop state")
+                .arg(
+                    Arg::with_name("authority")
+                        .required(true)
+                        .index(1)
+                        .help("Airdrop authority address"),
+                )
+                .arg(
+                    Arg::with_name("name")
+                        .required(true)
+                        .index(2)
+                        .help("Airdrop name"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("register")
+                .about("Create user data account")
+                .arg(pubkey_arg("airdrop"))
+                .arg(arg("user", "User wallet [default: keypair]")),
+        )
+        .subcommand(
+            SubCommand::with_name("mint")
+                .about("Mint one token to keypair")
+                .arg(pubkey_arg("airdrop"))
+                .arg(arg("authority", "Airdrop authority keypair path").required(true))
+                .arg(arg("referrer", "Referrer wallet")),
+        )
+        .subcommand(
+            SubCommand::with_name("reveal")
+                .about("Reveal collection. Keypair must be airdrop authority")
+                .arg(pubkey_arg("airdrop")),
+        )
+        .subcommand(
+            SubCommand::with_name("increase-supply")
+                .about("Increase airdrop supply. Keypair must be airdrop authority")
+                .arg(pubkey_arg("airdrop"))
+                .arg(
+                    Arg::with_name("additional")
+                        .required(true)
+                        .index(2)
+                        .help("Tokens to add"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("update-window")
+                .about("Set mint window. Keypair must be airdrop authority")
+                .arg(pubkey_arg("airdrop"))
+                .arg(arg("start", "Unix timestamp mints open at, 0 for now").required(true))
+                .arg(arg("end", "Unix timestamp mints close at, 0 for no end").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("sweep-mint-authority")
+                .about("Move mint authority lamports to revenue wallet. Keypair must be airdrop authority")
+                .arg(pubkey_arg("airdrop")),
+        )
+        .get_matches();
+
+    let rpc = RpcClient::new_with_commitment(
+        matches.value_of("url").unwrap().to_string(),
+        CommitmentConfig::confirmed(),
+    );
+
+    let result = match matches.subcommand() {
+        ("init", Some(m)) => init(&rpc, m),
+        ("show", Some(m)) => show(&rpc, m),
+        ("register", Some(m)) => register(&rpc, m),
+        ("mint", Some(m)) => mint(&rpc, m),
+        ("reveal", Some(m)) => reveal(&rpc, m),
+        ("increase-supply", Some(m)) => increase_supply(&rpc, m),
+        ("update-window", Some(m)) => update_window(&rpc, m),
+        ("sweep-mint-authority", Some(m)) => sweep_mint_authority(&rpc, m),
+        _ => unreachable!(),
+    };
+
+    if let Err(error) = result {
+        eprintln!("Error: {}", error);
+        exit(1);
+    }
+}
+
+fn arg<'a>(name: &'a str, help: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name(name).long(name).takes_value(true).help(help)
+}
+
+fn flag<'a>(name: &'a str, help: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name(name).long(name).help(help)
+}
+
+fn pubkey_arg(name: &str) -> Arg<'_, '_> {
+    Arg::with_name(name)
+        .required(true)
+        .index(1)
+        .help("Airdrop config address")
+}
+
+fn keypair(matches: &ArgMatches) -> Result<Keypair, String> {
+    let path = match matches.value_of("keypair") {
+        Some(path) => path.to_string(),
+        None => {
+            let home = std::env::var("HOME").map_err(|e| e.to_string())?;
+            format!("{}/.config/solana/id.json", home)
+        }
+    };
+
+    read_keypair_file(&path).map_err(|e| format!("Can not read keypair {}: {}", path, e))
+}
+
+fn pubkey(matches: &ArgMatches, name: &str) -> Result<Pubkey, String> {
+    let value = matches.value_of(name).unwrap();
+    Pubkey::from_str(value).map_err(|e| format!("Invalid {} {}: {}", name, value, e))
+}
+
+fn number<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<T, String> {
+    let value = matches.value_of(name).unwrap();
+    value
+        .parse()
+        .map_err(|_| format!("Invalid {} {}", name, value))
+}
+
+/// Zero padded string, as program stores strings
+fn padded<const N: usize>(matches: &ArgMatches, name: &str) -> Result<[u8; N], String> {
+    let mut dst = [0u8; N];
+    let src = matches.value_of(name).unwrap_or_default().as_bytes();
+
+    if src.len() > N {
+        return Err(format!("{} must be at most {} bytes", name, N));
+    }
+
+    dst[..src.len()].copy_from_slice(src);
+    Ok(dst)
+}
+
+fn unpadded(src: &[u8]) -> String {
+    let end = src.iter().position(|&c| c == 0).unwrap_or(src.len());
+    String::from_utf8_lossy(&src[..end]).to_string()
+}
+
+/// Maps custom program error codes back to airdrop error names
+fn describe(error: ClientError) -> String {
+    if let Some(TransactionError::InstructionError(index, InstructionError::Custom(code))) =
+        error.get_transaction_error()
+    {
+        if let Some(airdrop_error) = AirdropError::from_u32(code) {
+            return format!(
+                "Instruction {} failed with {:?}: {}",
+                index, airdrop_error, airdrop_error
+            );
+        }
+    }
+
+    error.to_string()
+}
+
+fn send(
+    rpc: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Keypair,
+    signers: &[&Keypair],
+) -> CliResult {
+    let blockhash = rpc.get_latest_blockhash().map_err(describe)?;
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+
+    let signature = rpc
+        .send_and_confirm_transaction(&transaction)
+        .map_err(describe)?;
+    println!("Signature: {}", signature);
+
+    Ok(())
+}
+
+fn init(rpc: &RpcClient, matches: &ArgMatches) -> CliResult {
+    let authority = keypair(matches)?;
+    let args = InitializeAirdropArgs {
+        airdrop_amount: number(matches, "amount")?,
+        base_uri: matches.value_of("base-uri").unwrap().as_bytes().to_vec(),
+        symbol: padded(matches, "symbol")?,
+        price: number(matches, "price")?,
+        placeholder_uri: padded(matches, "placeholder-uri")?,
+        auto_lock: matches.is_present("auto-lock"),
+        revenue_splits: [RevenueSplit::default(); MAX_REVENUE_SPLITS],
+        charge_payer: matches.is_present("charge-payer"),
+        max_mints_per_tx: number(matches, "max-mints-per-tx")?,
+        name: padded(matches, "name")?,
+        trait_count: number(matches, "trait-count")?,
+        mint_cooldown_secs: number(matches, "cooldown")?,
+        referral_bps: number(matches, "referral-bps")?,
+        bot_tax_lamports: number(matches, "bot-tax")?,
+        expected_decimals: number(matches, "decimals")?,
+        escrow_timeout_secs: number(matches, "escrow-timeout")?,
+        mint_quantity: number(matches, "quantity")?,
+        registration_fee: number(matches, "registration-fee")?,
+        fairness_bps: number(matches, "fairness-bps")?,
+        raffle: matches.is_present("raffle"),
+        require_registration: !matches.is_present("open-mint"),
+        deny_cpi_mint: matches.is_present("deny-cpi-mint"),
+        min_payer_balance: number(matches, "min-payer-balance")?,
+    };
+    let (airdrop, _) = find_airdrop_config(&authority.pubkey(), &args.name);
+
+    let instruction = client::initialize_airdrop(
+        &authority.pubkey(),
+        &pubkey(matches, "revenue-wallet")?,
+        &pubkey(matches, "admin")?,
+        &authority.pubkey(),
+        &args,
+    );
+
+    send(rpc, &[instruction], &authority, &[])?;
+    println!("Airdrop: {}", airdrop);
+
+    Ok(())
+}
+
+fn show(rpc: &RpcClient, matches: &ArgMatches) -> CliResult {
+    let (airdrop, config) = client::get_named_airdrop_config(
+        rpc,
+        &pubkey(matches, "authority")?,
+        &padded(matches, "name")?,
+    )
+    .map_err(|e| e.to_string())?;
+
+    println!("Airdrop:          {}", airdrop);
+    println!("Name:             {}", unpadded(&config.name));
+    println!("Authority:        {}", config.airdrop_authority);
+    println!("Mint authority:   {}", find_mint_authority(&airdrop).0);
+    println!("Admin:            {}", config.admin_account);
+    println!("Revenue wallet:   {}", config.revenues_wallet);
+    for split in config.revenue_splits.iter().filter(|s| s.is_used()) {
+        println!(
+            "Revenue split:    {} {} bps",
+            split.wallet, split.basis_points
+        );
+    }
+    println!("Symbol:           {}", unpadded(&config.symbol));
+    println!(
+        "Base URI:         {}",
+        String::from_utf8_lossy(config.base_uri())
+    );
+    println!("Placeholder URI:  {}", unpadded(&config.placeholder_uri));
+    println!("Revealed:         {}", config.revealed);
+    println!("Auto lock:        {}", config.auto_lock);
+    println!("Price:            {} lamports", config.price);
+    println!("Charge payer:     {}", config.charge_payer);
+    println!("Minted:           {}", config.airdrop_index);
+    println!("Supply:           {}", config.airdrop_amount);
+    println!(
+        "Remaining supply: {}",
+        config.airdrop_amount.saturating_sub(config.airdrop_index)
+    );
+    println!("Users:            {}", config.airdrop_users);
+    println!("Registered total: {}", config.registered_count);
+    println!("Max mints per tx: {}", config.max_mints_per_tx);
+    println!("Trait count:      {}", config.trait_count);
+    println!("Cooldown:         {} s", config.mint_cooldown_secs);
+    println!("Referral:         {} bps", config.referral_bps);
+    println!("Bot tax:          {} lamports", config.bot_tax_lamports);
+    println!("Window start:     {}", config.start_ts);
+    println!("Window end:       {}", config.end_ts);
+    println!("Stages:           {}", config.stage_count);
+    println!("Decimals:         {}", config.expected_decimals);
+    println!("Escrow timeout:   {} s", config.escrow_timeout_secs);
+    println!("Mint quantity:    {}", config.mint_quantity);
+    println!("Registration fee: {} lamports", config.registration_fee);
+    println!("Min payer funds:  {} lamports", config.min_payer_balance);
+    println!("Fairness:         {} bps", config.fairness_bps);
+    println!("Active:           {}", config.is_active);
+    println!("Gifted:           {}", config.gifted_count);
+    println!("Revenue:          {} lamports", config.total_revenue);
+    if config.finalized {
+        println!(
+            "Finalized:        minted {}, gifted {}, registered {}, revenue {} lamports",
+            config.final_minted, config.final_gifted, config.final_registered, config.final_revenue
+        );
+    }
+    if config.price_usd_cents > 0 {
+        println!(
+            "USD price:        {}.{:02} (feed {}, max age {} slots)",
+            config.price_usd_cents / 100,
+            config.price_usd_cents % 100,
+            config.pyth_price_account,
+            config.max_price_age_slots
+        );
+    }
+    println!("Registration:     {}", config.require_registration);
+    println!("Deny CPI mint:    {}", config.deny_cpi_mint);
+    println!("Raffle:           {}", config.raffle);
+    println!("Raffle winners:   {}", config.winners_count);
+
+    Ok(())
+}
+
+fn register(rpc: &RpcClient, matches: &ArgMatches) -> CliResult {
+    let payer = keypair(matches)?;
+    let airdrop = pubkey(matches, "airdrop")?;
+    let user = match matches.value_of("user") {
+        Some(_) => pubkey(matches, "user")?,
+        None => payer.pubkey(),
+    };
+
+    let config = client::get_airdrop_config(rpc, &airdrop).map_err(|e| e.to_string())?;
+    let instruction = client::initialize_airdrop_user(&airdrop, &config, &user, &payer.pubkey());
+    send(rpc, &[instruction], &payer, &[])?;
+    println!("User data: {}", find_airdrop_user_data(&airdrop, &user).0);
+
+    Ok(())
+}
+
+fn mint(rpc: &RpcClient, matches: &ArgMatches) -> CliResult {
+    let user = keypair(matches)?;
+    let authority_path = matches.value_of("authority").unwrap();
+    let authority = read_keypair_file(authority_path)
+        .map_err(|e| format!("Can not read keypair {}: {}", authority_path, e))?;
+    let airdrop = pubkey(matches, "airdrop")?;
+    let config = client::get_airdrop_config(rpc, &airdrop).map_err(|e| e.to_string())?;
+    let mint = Keypair::new();
+    let referrer = match matches.value_of("referrer") {
+        Some(_) => Some(pubkey(matches, "referrer")?),
+        None => None,
+    };
+
+    let instruction = client::mint_one(
+        &airdrop,
+        &config,
+        &user.pubkey(),
+        &mint.pubkey(),
+        &user.pubkey(),
+        referrer.as_ref(),
+    );
+
+    send(rpc, &[instruction], &user, &[&mint, &authority])?;
+    println!("Mint: {}", mint.pubkey());
+
+    Ok(())
+}
+
+fn reveal(rpc: &RpcClient, matches: &ArgMatches) -> CliResult {
+    let authority = keypair(matches)?;
+    let airdrop = pubkey(matches, "airdrop")?;
+
+    let instruction = client::reveal(&airdrop, &authority.pubkey());
+    send(rpc, &[instruction], &authority, &[])
+}
+
+fn increase_supply(rpc: &RpcClient, matches: &ArgMatches) -> CliResult {
+    let authority = keypair(matches)?;
+    let airdrop = pubkey(matches, "airdrop")?;
+
+    let instruction = client::increase_supply(
+        &airdrop,
+        &authority.pubkey(),
+        number(matches, "additional")?,
+    );
+    send(rpc, &[instruction], &authority, &[])
+}
+
+fn update_window(rpc: &RpcClient, matches: &ArgMatches) -> CliResult {
+    let authority = keypair(matches)?;
+    let airdrop = pubkey(matches, "airdrop")?;
+
+    let instruction = client::update_window(
+        &airdrop,
+        &authority.pubkey(),
+        number(matches, "start")?,
+        number(matches, "end")?,
+    );
+    send(rpc, &[instruction], &authority, &[])
+}
+
+fn sweep_mint_authority(rpc: &RpcClient, matches: &ArgMatches) -> CliResult {
+    let authority = keypair(matches)?;
+    let airdrop = pubkey(matches, "airdrop")?;
+    let config = client::get_airdrop_config(rpc, &airdrop).map_err(|e| e.to_string())?;
+
+    let instruction = client::sweep_mint_authority(&airdrop, &config, &authority.pubkey());
+    send(rpc, &[instruction], &authority, &[])
+}
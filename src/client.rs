@@ -0,0 +1,1102 @@
+//! Off-chain helpers: PDA finders, instruction builders and account decoders
+
+use solana_client::{client_error::ClientError, rpc_client::RpcClient};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use thiserror::Error;
+
+pub use crate::pda::{
+    find_airdrop_config, find_airdrop_user_data, find_escrow, find_master_edition, find_metadata,
+    find_mint_authority, find_stage, find_vault,
+};
+use crate::{
+    error::AirdropError,
+    instruction::{AddStageArgs, InitializeAirdropArgs},
+    state::{
+        pack_revenue_splits, AirdropConfig, AirdropUserData, MAX_REVENUE_SPLITS,
+        PLATFORM_FEE_LAMPORTS, PLATFORM_WALLET, PROTOCOL_FEE_BPS, PROTOCOL_TREASURY,
+        REVENUE_SPLITS_LEN,
+    },
+};
+
+#[derive(Error, Debug)]
+pub enum AirdropClientError {
+    #[error(transparent)]
+    Rpc(Box<ClientError>),
+
+    #[error(transparent)]
+    Program(#[from] ProgramError),
+}
+
+impl From<ClientError> for AirdropClientError {
+    fn from(error: ClientError) -> Self {
+        AirdropClientError::Rpc(Box::new(error))
+    }
+}
+
+impl AirdropConfig {
+    /// Decodes airdrop config from RPC account data
+    pub fn try_deserialize(data: &[u8]) -> Result<AirdropConfig, ProgramError> {
+        let config = AirdropConfig::unpack_unchecked(data)?;
+
+        if !config.initialized {
+            return Err(AirdropError::Uninitialized.into());
+        }
+
+        Ok(config)
+    }
+}
+
+impl AirdropUserData {
+    /// Decodes user data from RPC account data
+    pub fn try_deserialize(data: &[u8]) -> Result<AirdropUserData, ProgramError> {
+        let user_data = AirdropUserData::unpack_unchecked(data)?;
+
+        if !user_data.initialized {
+            return Err(AirdropError::Uninitialized.into());
+        }
+
+        Ok(user_data)
+    }
+}
+
+pub fn get_airdrop_config(
+    rpc: &RpcClient,
+    airdrop: &Pubkey,
+) -> Result<AirdropConfig, AirdropClientError> {
+    let data = rpc.get_account_data(airdrop)?;
+    Ok(AirdropConfig::try_deserialize(&data)?)
+}
+
+/// Fetches airdrop config derived from authority and name
+pub fn get_named_airdrop_config(
+    rpc: &RpcClient,
+    airdrop_authority: &Pubkey,
+    name: &[u8; 16],
+) -> Result<(Pubkey, AirdropConfig), AirdropClientError> {
+    let (airdrop, _) = find_airdrop_config(airdrop_authority, name);
+    Ok((airdrop, get_airdrop_config(rpc, &airdrop)?))
+}
+
+pub fn get_airdrop_user_data(
+    rpc: &RpcClient,
+    airdrop: &Pubkey,
+    user: &Pubkey,
+) -> Result<AirdropUserData, AirdropClientError> {
+    let (user_data, _) = find_airdrop_user_data(airdrop, user);
+    let data = rpc.get_account_data(&user_data)?;
+    Ok(AirdropUserData::try_deserialize(&data)?)
+}
+
+/// User data addresses and bumps of given wallets, in the same order
+pub fn derive_user_data_pdas(airdrop: &Pubkey, users: &[Pubkey]) -> Vec<(Pubkey, u8)> {
+    users
+        .iter()
+        .map(|user| find_airdrop_user_data(airdrop, user))
+        .collect()
+}
+
+pub fn pack_initialize_airdrop_args(args: &InitializeAirdropArgs) -> Vec<u8> {
+    let mut revenue_splits = [0u8; REVENUE_SPLITS_LEN];
+    pack_revenue_splits(&args.revenue_splits, &mut revenue_splits);
+
+    let mut data = vec![10];
+    data.extend_from_slice(&args.airdrop_amount.to_le_bytes());
+    data.extend_from_slice(&args.symbol);
+    data.extend_from_slice(&args.price.to_le_bytes());
+    data.extend_from_slice(&args.placeholder_uri);
+    data.push(args.auto_lock as u8);
+    data.extend_from_slice(&revenue_splits);
+    data.push(args.charge_payer as u8);
+    data.push(args.max_mints_per_tx);
+    data.extend_from_slice(&args.name);
+    data.push(args.trait_count);
+    data.extend_from_slice(&args.mint_cooldown_secs.to_le_bytes());
+    data.extend_from_slice(&args.referral_bps.to_le_bytes());
+    data.extend_from_slice(&args.bot_tax_lamports.to_le_bytes());
+    data.push(args.expected_decimals);
+    data.extend_from_slice(&args.escrow_timeout_secs.to_le_bytes());
+    data.extend_from_slice(&args.mint_quantity.to_le_bytes());
+    data.extend_from_slice(&args.registration_fee.to_le_bytes());
+    data.extend_from_slice(&args.fairness_bps.to_le_bytes());
+    data.push(args.raffle as u8);
+    data.push(args.require_registration as u8);
+    data.push(args.deny_cpi_mint as u8);
+    data.extend_from_slice(&args.min_payer_balance.to_le_bytes());
+    data.push(args.base_uri.len() as u8);
+    data.extend_from_slice(&args.base_uri);
+    data
+}
+
+/// Airdrop config derived from authority and `args.name` is created by the program.
+/// Airdrop authority must sign the transaction
+pub fn initialize_airdrop(
+    airdrop_authority: &Pubkey,
+    revenues_wallet: &Pubkey,
+    admin: &Pubkey,
+    fee_payer: &Pubkey,
+    args: &InitializeAirdropArgs,
+) -> Instruction {
+    let (airdrop, _) = find_airdrop_config(airdrop_authority, &args.name);
+    let (mint_authority, _) = find_mint_authority(&airdrop);
+
+    let mut accounts = vec![
+        AccountMeta::new(airdrop, false),
+        AccountMeta::new_readonly(*airdrop_authority, true),
+        AccountMeta::new(mint_authority, false),
+        AccountMeta::new_readonly(*revenues_wallet, false),
+        AccountMeta::new_readonly(*admin, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(*fee_payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    if PLATFORM_FEE_LAMPORTS != 0 {
+        accounts.push(AccountMeta::new(PLATFORM_WALLET, false));
+    }
+
+    Instruction::new_with_bytes(crate::id(), &pack_initialize_airdrop_args(args), accounts)
+}
+
+/// Revenue wallet is passed when airdrop charges registration fee
+pub fn initialize_airdrop_user(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    user: &Pubkey,
+    fee_payer: &Pubkey,
+) -> Instruction {
+    initialize_airdrop_user_with_flag(airdrop, config, user, fee_payer, false)
+}
+
+/// Succeeds without changes if user is already registered,
+/// so registration can share a transaction with mint
+pub fn initialize_airdrop_user_idempotent(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    user: &Pubkey,
+    fee_payer: &Pubkey,
+) -> Instruction {
+    initialize_airdrop_user_with_flag(airdrop, config, user, fee_payer, true)
+}
+
+fn initialize_airdrop_user_with_flag(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    user: &Pubkey,
+    fee_payer: &Pubkey,
+    idempotent: bool,
+) -> Instruction {
+    let (user_data, _) = find_airdrop_user_data(airdrop, user);
+
+    let mut accounts = vec![
+        AccountMeta::new(user_data, false),
+        AccountMeta::new_readonly(*user, false),
+        AccountMeta::new(*airdrop, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(*fee_payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    if config.registration_fee > 0 {
+        accounts.push(AccountMeta::new(config.revenues_wallet, false));
+    }
+
+    Instruction::new_with_bytes(crate::id(), &[2, idempotent as u8], accounts)
+}
+
+/// Accounts are taken from airdrop config. User signs unless airdrop charges payer.
+/// Referrer is ignored when airdrop has no referral cut
+pub fn mint_one(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    user: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    referrer: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = mint_one_accounts(
+        airdrop,
+        config,
+        user,
+        user,
+        mint,
+        payer,
+        pays_revenue(config),
+    );
+
+    push_price_account(&mut accounts, config);
+
+    if let (Some(referrer), true) = (referrer, config.referral_bps > 0) {
+        accounts.push(AccountMeta::new(*referrer, false));
+    }
+
+    Instruction::new_with_bytes(crate::id(), &[3], accounts)
+}
+
+/// Mints token into recipient wallet, counted against user and paid as configured.
+/// Recipient must be a wallet on ed25519 curve
+pub fn mint_one_gift(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    user: &Pubkey,
+    recipient: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    referrer: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = mint_one_accounts(
+        airdrop,
+        config,
+        user,
+        recipient,
+        mint,
+        payer,
+        pays_revenue(config),
+    );
+    accounts.push(AccountMeta::new_readonly(*recipient, false));
+
+    push_price_account(&mut accounts, config);
+
+    if let (Some(referrer), true) = (referrer, config.referral_bps > 0) {
+        accounts.push(AccountMeta::new(*referrer, false));
+    }
+
+    Instruction::new_with_bytes(crate::id(), &[3, 1], accounts)
+}
+
+/// Mints token into escrow of the next airdrop index, claimable by claimant.
+/// Config must be fresh, as escrow address is derived from its `airdrop_index`
+pub fn mint_to_escrow(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    user: &Pubkey,
+    claimant: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    referrer: Option<&Pubkey>,
+) -> Instruction {
+    let (escrow, _) = find_escrow(airdrop, config.airdrop_index);
+    let mut accounts = mint_one_accounts(
+        airdrop,
+        config,
+        user,
+        &escrow,
+        mint,
+        payer,
+        pays_revenue(config),
+    );
+    accounts.push(AccountMeta::new(escrow, false));
+
+    push_price_account(&mut accounts, config);
+
+    if let (Some(referrer), true) = (referrer, config.referral_bps > 0) {
+        accounts.push(AccountMeta::new(*referrer, false));
+    }
+
+    let mut data = vec![15];
+    data.extend_from_slice(claimant.as_ref());
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Mints in active stage of airdrop with stages. Proof is empty when stage has no allowlist
+#[allow(clippy::too_many_arguments)]
+pub fn mint_one_in_stage(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    user: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    referrer: Option<&Pubkey>,
+    stage_index: u8,
+    proof: &[[u8; 32]],
+) -> Instruction {
+    let mut accounts = mint_one_accounts(airdrop, config, user, user, mint, payer, true);
+    let (stage, _) = find_stage(airdrop, stage_index);
+    accounts.push(AccountMeta::new(stage, false));
+
+    push_price_account(&mut accounts, config);
+
+    if let (Some(referrer), true) = (referrer, config.referral_bps > 0) {
+        accounts.push(AccountMeta::new(*referrer, false));
+    }
+
+    let mut data = vec![3];
+    for node in proof {
+        data.extend_from_slice(node);
+    }
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Free mint transfers nothing to revenue wallets, unless it charges bot tax
+fn pays_revenue(config: &AirdropConfig) -> bool {
+    config.price > 0 || config.price_usd_cents > 0 || config.bot_tax_lamports > 0
+}
+
+/// Pyth price account follows stage when airdrop is priced in USD
+fn push_price_account(accounts: &mut Vec<AccountMeta>, config: &AirdropConfig) {
+    if config.price_usd_cents > 0 {
+        accounts.push(AccountMeta::new_readonly(config.pyth_price_account, false));
+    }
+}
+
+/// Number of MintOne accounts before revenue split wallets
+const MINT_ONE_FIXED_ACCOUNTS: usize = 20;
+
+/// Protocol treasury follows revenue split wallets in builds taking protocol fee
+fn protocol_accounts() -> usize {
+    (PROTOCOL_FEE_BPS != 0) as usize
+}
+
+/// Checks MintOne account metas before sending, as a misplaced account only fails on chain.
+/// Flags that depend on airdrop config, like writeable revenue wallet, are not checked.
+///
+/// Canonical order:
+/// 0. `[writeable]`. Airdrop account
+/// 1. `[writeable]`. User data account, or `[]` program id when airdrop does not require registration
+/// 2. `[signer, writeable]`. Mint
+/// 3. `[]`. User. `[signer, writeable]` when user pays the price
+/// 4. `[writeable]`. Token account of receiver
+/// 5. `[writeable]`. Metadata account
+/// 6. `[]`. Mint authority
+/// 7. `[]`. System program
+/// 8. `[]`. Clock sysvar
+/// 9. `[]`. Rent sysvar
+/// 10. `[]`. Token program
+/// 11. `[]`. Associated token program
+/// 12. `[]`. Metaplex token metadata program
+/// 13. `[signer, writeable]`. Payer
+/// 14. `[signer]`. Airdrop authority
+/// 15. `[]`. Admin account
+/// 16. `[]`. Revenue wallet
+/// 17. `[]`. Instructions sysvar
+/// 18. `[]`. Slot hashes sysvar
+/// 19. `[writeable]`. Master edition account
+/// 20. Revenue split wallets, then protocol treasury in builds taking protocol fee,
+///     then optional gift recipient, stage, Pyth price account and referrer
+pub fn validate_mint_accounts(metas: &[AccountMeta]) -> Result<(), &'static str> {
+    if metas.len() < MINT_ONE_FIXED_ACCOUNTS {
+        return Err("Not enough accounts");
+    }
+
+    if metas.len() > MINT_ONE_FIXED_ACCOUNTS + MAX_REVENUE_SPLITS + 4 + protocol_accounts() {
+        return Err("Too many accounts");
+    }
+
+    for (index, name) in [
+        (0, "Airdrop account must be writeable"),
+        (4, "Token account must be writeable"),
+        (5, "Metadata account must be writeable"),
+        (19, "Master edition account must be writeable"),
+    ] {
+        if !metas[index].is_writable {
+            return Err(name);
+        }
+    }
+
+    for (index, name) in [
+        (2, "Mint must be signer and writeable"),
+        (13, "Payer must be signer and writeable"),
+    ] {
+        if !metas[index].is_signer || !metas[index].is_writable {
+            return Err(name);
+        }
+    }
+
+    if !metas[1].is_writable && metas[1].pubkey != crate::id() {
+        return Err("User data account must be writeable");
+    }
+
+    if metas[3].is_signer && !metas[3].is_writable {
+        return Err("Signing user must be writeable");
+    }
+
+    if !metas[14].is_signer {
+        return Err("Airdrop authority must be signer");
+    }
+
+    for (index, id, name) in [
+        (7, system_program::id(), "Account 7 must be system program"),
+        (8, sysvar::clock::id(), "Account 8 must be clock sysvar"),
+        (9, sysvar::rent::id(), "Account 9 must be rent sysvar"),
+        (10, spl_token::id(), "Account 10 must be token program"),
+        (
+            11,
+            spl_associated_token_account::id(),
+            "Account 11 must be associated token program",
+        ),
+        (
+            12,
+            metaplex_token_metadata::id(),
+            "Account 12 must be token metadata program",
+        ),
+        (
+            17,
+            sysvar::instructions::id(),
+            "Account 17 must be instructions sysvar",
+        ),
+        (
+            18,
+            sysvar::slot_hashes::id(),
+            "Account 18 must be slot hashes sysvar",
+        ),
+    ] {
+        if metas[index].pubkey != id {
+            return Err(name);
+        }
+    }
+
+    Ok(())
+}
+
+/// Accounts up to revenue split wallets and protocol treasury. Token account is derived for `token_owner`
+fn mint_one_accounts(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    user: &Pubkey,
+    token_owner: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    pays_revenue: bool,
+) -> Vec<AccountMeta> {
+    let (mint_authority, _) = find_mint_authority(airdrop);
+    let user_token_account =
+        spl_associated_token_account::get_associated_token_address(token_owner, mint);
+    let user_signs = !config.charge_payer;
+
+    let mut accounts = vec![
+        AccountMeta::new(*airdrop, false),
+        match config.require_registration {
+            true => AccountMeta::new(find_airdrop_user_data(airdrop, user).0, false),
+            false => AccountMeta::new_readonly(crate::id(), false),
+        },
+        AccountMeta::new(*mint, true),
+        match user_signs {
+            true => AccountMeta::new(*user, true),
+            false => AccountMeta::new_readonly(*user, false),
+        },
+        AccountMeta::new(user_token_account, false),
+        AccountMeta::new(find_metadata(mint), false),
+        AccountMeta::new(mint_authority, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(metaplex_token_metadata::id(), false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(config.airdrop_authority, true),
+        AccountMeta::new_readonly(config.admin_account, false),
+        match pays_revenue {
+            true => AccountMeta::new(config.revenues_wallet, false),
+            false => AccountMeta::new_readonly(config.revenues_wallet, false),
+        },
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        AccountMeta::new(find_master_edition(mint), false),
+    ];
+
+    for split in config.revenue_splits.iter().filter(|s| s.is_used()) {
+        accounts.push(match pays_revenue {
+            true => AccountMeta::new(split.wallet, false),
+            false => AccountMeta::new_readonly(split.wallet, false),
+        });
+    }
+
+    if PROTOCOL_FEE_BPS != 0 {
+        accounts.push(match pays_revenue {
+            true => AccountMeta::new(PROTOCOL_TREASURY, false),
+            false => AccountMeta::new_readonly(PROTOCOL_TREASURY, false),
+        });
+    }
+
+    accounts
+}
+
+pub fn reveal(airdrop: &Pubkey, airdrop_authority: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[4],
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+        ],
+    )
+}
+
+pub fn can_mint(airdrop: &Pubkey, user: &Pubkey) -> Instruction {
+    let (user_data, _) = find_airdrop_user_data(airdrop, user);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[5],
+        vec![
+            AccountMeta::new_readonly(*airdrop, false),
+            AccountMeta::new_readonly(user_data, false),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+pub fn increase_supply(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    additional: u64,
+) -> Instruction {
+    let mut data = vec![6];
+    data.extend_from_slice(&additional.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &data,
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+        ],
+    )
+}
+
+/// Resolves the same price MintOne would charge. Stage index is required for airdrop
+/// with stages, proof is empty when stage has no allowlist
+pub fn preview_mint(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    user: &Pubkey,
+    mint: &Pubkey,
+    stage_index: Option<u8>,
+    proof: &[[u8; 32]],
+) -> Instruction {
+    let (user_data, _) = find_airdrop_user_data(airdrop, user);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*airdrop, false),
+        AccountMeta::new_readonly(user_data, false),
+        AccountMeta::new_readonly(*user, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(
+            spl_associated_token_account::get_associated_token_address(user, mint),
+            false,
+        ),
+    ];
+
+    if let Some(stage_index) = stage_index {
+        let (stage, _) = find_stage(airdrop, stage_index);
+        accounts.push(AccountMeta::new_readonly(stage, false));
+    }
+
+    push_price_account(&mut accounts, config);
+
+    let mut data = vec![7];
+    for node in proof {
+        data.extend_from_slice(node);
+    }
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+pub fn batch_init_users(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    fee_payer: &Pubkey,
+    users: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*airdrop, false),
+        AccountMeta::new_readonly(*airdrop_authority, true),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(*fee_payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    for user in users {
+        let (user_data, _) = find_airdrop_user_data(airdrop, user);
+        accounts.push(AccountMeta::new(user_data, false));
+        accounts.push(AccountMeta::new_readonly(*user, false));
+    }
+
+    Instruction::new_with_bytes(crate::id(), &[8], accounts)
+}
+
+pub fn update_window(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    start_ts: i64,
+    end_ts: i64,
+) -> Instruction {
+    let mut data = vec![9];
+    data.extend_from_slice(&start_ts.to_le_bytes());
+    data.extend_from_slice(&end_ts.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &data,
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+        ],
+    )
+}
+
+pub fn update_revenue_wallet(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    revenue_wallet: &Pubkey,
+) -> Instruction {
+    let mut data = vec![21];
+    data.extend_from_slice(revenue_wallet.as_ref());
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &data,
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+        ],
+    )
+}
+
+/// Prices mints in USD cents using Pyth SOL/USD price account. Zero cents turns it off
+pub fn set_usd_price(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    price_usd_cents: u64,
+    pyth_price_account: &Pubkey,
+    max_price_age_slots: u64,
+) -> Instruction {
+    let mut data = vec![25];
+    data.extend_from_slice(&price_usd_cents.to_le_bytes());
+    data.extend_from_slice(pyth_price_account.as_ref());
+    data.extend_from_slice(&max_price_age_slots.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &data,
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+        ],
+    )
+}
+
+/// Blocks or unblocks user. Fee payer covers user data rent when wallet is not registered yet
+pub fn set_user_blocked(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    user: &Pubkey,
+    fee_payer: &Pubkey,
+    blocked: bool,
+) -> Instruction {
+    let (user_data, _) = find_airdrop_user_data(airdrop, user);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[27, blocked as u8],
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+            AccountMeta::new(user_data, false),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new(*fee_payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Freezes ended or sold out airdrop. Lock also drops airdrop authority
+pub fn finalize(airdrop: &Pubkey, airdrop_authority: &Pubkey, lock: bool) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[26, lock as u8],
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Ends airdrop for good
+pub fn deactivate(airdrop: &Pubkey, airdrop_authority: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[24],
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+        ],
+    )
+}
+
+pub fn draw_raffle(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    winners_count: u64,
+) -> Instruction {
+    let mut data = vec![22];
+    data.extend_from_slice(&winners_count.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &data,
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+            AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        ],
+    )
+}
+
+/// Returns rent of raffle loser's user data to the user
+pub fn close_user_data(airdrop: &Pubkey, user: &Pubkey) -> Instruction {
+    let (user_data, _) = find_airdrop_user_data(airdrop, user);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[23],
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new(user_data, false),
+        ],
+    )
+}
+
+/// Returns rent of user data to the user, who has not minted yet
+pub fn deregister(airdrop: &Pubkey, user: &Pubkey) -> Instruction {
+    let (user_data, _) = find_airdrop_user_data(airdrop, user);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[28],
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new(user_data, false),
+        ],
+    )
+}
+
+/// Rewrites cached mint authority bump and bumps of passed users' data
+/// `minted` is a token minted by the airdrop, required once airdrop has minted
+pub fn recompute_bumps(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    minted: Option<&Pubkey>,
+    users: &[Pubkey],
+) -> Instruction {
+    let (mint_authority, _) = find_mint_authority(airdrop);
+
+    let mut accounts = vec![
+        AccountMeta::new(*airdrop, false),
+        AccountMeta::new_readonly(*airdrop_authority, true),
+        AccountMeta::new_readonly(mint_authority, false),
+    ];
+
+    if let Some(minted) = minted {
+        accounts.push(AccountMeta::new_readonly(find_metadata(minted), false));
+    }
+
+    for user in users {
+        let (user_data, _) = find_airdrop_user_data(airdrop, user);
+        accounts.push(AccountMeta::new(user_data, false));
+    }
+
+    Instruction::new_with_bytes(crate::id(), &[29], accounts)
+}
+
+/// Moves lamports held by mint authority to revenue wallet stored in config
+pub fn sweep_mint_authority(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    airdrop_authority: &Pubkey,
+) -> Instruction {
+    let (mint_authority, _) = find_mint_authority(airdrop);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[11],
+        vec![
+            AccountMeta::new_readonly(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+            AccountMeta::new(mint_authority, false),
+            AccountMeta::new(config.revenues_wallet, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Stage windows must not overlap, so every existing stage of airdrop is passed in
+pub fn add_stage(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    fee_payer: &Pubkey,
+    existing_stages: &[Pubkey],
+    args: &AddStageArgs,
+) -> Instruction {
+    let (stage, _) = find_stage(airdrop, args.stage_index);
+
+    let mut data = vec![12, args.stage_index];
+    data.extend_from_slice(&args.price.to_le_bytes());
+    data.extend_from_slice(&args.supply_cap.to_le_bytes());
+    data.extend_from_slice(&args.start_ts.to_le_bytes());
+    data.extend_from_slice(&args.end_ts.to_le_bytes());
+    data.extend_from_slice(&args.merkle_root);
+    data.extend_from_slice(&args.per_user_limit.to_le_bytes());
+
+    let mut accounts = vec![
+        AccountMeta::new(*airdrop, false),
+        AccountMeta::new_readonly(*airdrop_authority, true),
+        AccountMeta::new(stage, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(*fee_payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    for existing in existing_stages {
+        accounts.push(AccountMeta::new_readonly(*existing, false));
+    }
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+pub fn remove_stage(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    stage_index: u8,
+    receiver: &Pubkey,
+) -> Instruction {
+    let (stage, _) = find_stage(airdrop, stage_index);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[13],
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+            AccountMeta::new(stage, false),
+            AccountMeta::new(*receiver, false),
+        ],
+    )
+}
+
+/// Closes user data account once mint window ended long enough ago. Rent goes to authority
+pub fn sweep_user_account(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    user: &Pubkey,
+) -> Instruction {
+    let (user_data, _) = find_airdrop_user_data(airdrop, user);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[14],
+        vec![
+            AccountMeta::new(*airdrop, false),
+            AccountMeta::new(*airdrop_authority, true),
+            AccountMeta::new(user_data, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Transfers escrowed token to claimant. Escrow rent goes back to payer of the mint
+pub fn claim_from_escrow(
+    airdrop: &Pubkey,
+    purchase_index: u64,
+    claimant: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (escrow, _) = find_escrow(airdrop, purchase_index);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[16],
+        vec![
+            AccountMeta::new_readonly(*airdrop, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(&escrow, mint),
+                false,
+            ),
+            AccountMeta::new(*claimant, true),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(claimant, mint),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payer, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+    )
+}
+
+/// Moves unclaimed token past escrow timeout to destination token account
+pub fn sweep_escrow(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    purchase_index: u64,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (escrow, _) = find_escrow(airdrop, purchase_index);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[17],
+        vec![
+            AccountMeta::new_readonly(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(&escrow, mint),
+                false,
+            ),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new(*payer, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+/// Queues NFT held in authority's associated token account for distribution
+pub fn deposit_preminted(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    mint: &Pubkey,
+    fee_payer: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_vault(airdrop);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[18],
+        vec![
+            AccountMeta::new_readonly(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(&vault, mint),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(airdrop_authority, mint),
+                false,
+            ),
+            AccountMeta::new(*fee_payer, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+    )
+}
+
+/// Claims next queued NFT. `mint` must be the one at vault cursor.
+/// Stage is required for airdrop with stages, proof is empty when stage has no allowlist
+pub fn claim_preminted(
+    airdrop: &Pubkey,
+    config: &AirdropConfig,
+    user: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    stage: Option<(u8, &[[u8; 32]])>,
+) -> Instruction {
+    let (user_data, _) = find_airdrop_user_data(airdrop, user);
+    let (vault, _) = find_vault(airdrop);
+    let user_signs = !config.charge_payer;
+
+    let mut accounts = vec![
+        AccountMeta::new(*airdrop, false),
+        AccountMeta::new(user_data, false),
+        match user_signs {
+            true => AccountMeta::new(*user, true),
+            false => AccountMeta::new_readonly(*user, false),
+        },
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(user, mint),
+            false,
+        ),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(&vault, mint),
+            false,
+        ),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(config.airdrop_authority, true),
+        AccountMeta::new(config.revenues_wallet, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+    ];
+
+    for split in config.revenue_splits.iter().filter(|s| s.is_used()) {
+        accounts.push(AccountMeta::new(split.wallet, false));
+    }
+
+    let mut data = vec![19];
+
+    if let Some((stage_index, proof)) = stage {
+        let (stage, _) = find_stage(airdrop, stage_index);
+        accounts.push(AccountMeta::new(stage, false));
+
+        for node in proof {
+            data.extend_from_slice(node);
+        }
+    }
+
+    push_price_account(&mut accounts, config);
+
+    Instruction::new_with_bytes(crate::id(), &data, accounts)
+}
+
+/// Returns queued NFT to destination token account after mint window ended
+pub fn withdraw_unclaimed(
+    airdrop: &Pubkey,
+    airdrop_authority: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    rent_receiver: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_vault(airdrop);
+
+    Instruction::new_with_bytes(
+        crate::id(),
+        &[20],
+        vec![
+            AccountMeta::new_readonly(*airdrop, false),
+            AccountMeta::new_readonly(*airdrop_authority, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(&vault, mint),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new(*rent_receiver, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
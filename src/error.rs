@@ -1,3 +1,6 @@
+// Derived impls reference the deprecated PdaCheckFailed variant
+#![allow(deprecated)]
+
 use num_derive::FromPrimitive;
 use solana_program::{
     decode_error::DecodeError,
@@ -20,6 +23,8 @@ pub enum AirdropError {
     #[error("Acoount is not writeable")]
     WriteableRequired,
 
+    /// Superseded by account specific variants, kept to preserve error codes
+    #[deprecated]
     #[error("Acoount is not properly derived")]
     PdaCheckFailed,
 
@@ -34,6 +39,171 @@ pub enum AirdropError {
 
     #[error("Out of supply")]
     OutOfSupply,
+
+    #[error("Airdrop is already revealed")]
+    AlreadyRevealed,
+
+    #[error("Metadata prefix or symbol is empty")]
+    InvalidMetadataParams,
+
+    #[error("Revenue splits must sum up to 10000 basis points")]
+    InvalidSplit,
+
+    #[error("User must sign to pay the price")]
+    UserSignerRequired,
+
+    #[error("Revenue wallet must be a regular wallet")]
+    InvalidRevenueWallet,
+
+    #[error("Too many mint instructions in transaction")]
+    TooManyMintsInTransaction,
+
+    #[error("Arithmetic overflow")]
+    Overflow,
+
+    #[error("User account belongs to previous airdrop")]
+    StaleUserAccount,
+
+    #[error("Minted token has unexpected authorities or supply")]
+    InvalidMint,
+
+    #[error("Airdrop amount must be greater than zero")]
+    InvalidSupply,
+
+    #[error("Referrer must be a regular wallet other than payer and user")]
+    InvalidReferrer,
+
+    #[error("Program account does not match expected program id")]
+    InvalidProgramAccount,
+
+    #[error("Price is above sanity ceiling")]
+    InvalidPrice,
+
+    #[error("Mint authority is not properly derived")]
+    InvalidMintAuthorityPda,
+
+    #[error("User data is not properly derived")]
+    InvalidUserDataPda,
+
+    #[error("Token account is not user's associated token account")]
+    InvalidTokenAccountPda,
+
+    #[error("Metadata account is not properly derived")]
+    InvalidMetadataPda,
+
+    #[error("Mint window end is not after start")]
+    InvalidWindow,
+
+    #[error("Airdrop is outside of mint window")]
+    MintWindowClosed,
+
+    #[error("Symbol must be ASCII alphanumeric and NUL padded")]
+    InvalidSymbol,
+
+    #[error("Metadata prefix must be UTF-8 and NUL padded")]
+    InvalidMetadataPrefix,
+
+    #[error("Same account is passed in several slots")]
+    DuplicateAccount,
+
+    #[error("Metadata URI is longer than 200 bytes")]
+    UriTooLong,
+
+    #[error("Stage account is not properly derived")]
+    InvalidStagePda,
+
+    #[error("Stage window overlaps another stage")]
+    StageOverlap,
+
+    #[error("Airdrop has already started minting")]
+    AirdropLive,
+
+    #[error("Stage is outside of its mint window")]
+    StageNotActive,
+
+    #[error("Stage has minted tokens")]
+    StageInUse,
+
+    #[error("User is not on stage allowlist")]
+    NotAllowlisted,
+
+    #[error("User reached stage mint limit")]
+    StageLimitReached,
+
+    #[error("Fee payer can not afford rent of created accounts")]
+    InsufficientFunds,
+
+    #[error("Price is below sanity floor. Use zero for free mint")]
+    SuspiciousPrice,
+
+    #[error("Airdrop mint window has not ended long enough ago")]
+    AirdropNotEnded,
+
+    #[error("Gift recipient must be a wallet on ed25519 curve")]
+    InvalidRecipient,
+
+    #[error("Escrow account is not properly derived")]
+    InvalidEscrowPda,
+
+    #[error("Signer is not the escrow claimant")]
+    NotEscrowClaimant,
+
+    #[error("Escrow is not expired yet")]
+    EscrowNotExpired,
+
+    #[error("Vault account is not properly derived")]
+    InvalidVaultPda,
+
+    #[error("Every deposited NFT is already claimed")]
+    VaultEmpty,
+
+    #[error("Mint is not queued in vault")]
+    MintNotQueued,
+
+    #[error("Airdrop is not in raffle mode")]
+    RaffleDisabled,
+
+    #[error("Raffle is already drawn")]
+    RaffleDrawn,
+
+    #[error("Raffle is not drawn yet")]
+    RaffleNotDrawn,
+
+    #[error("User is not a raffle winner")]
+    NotRaffleWinner,
+
+    #[error("Raffle winner can not close user data")]
+    RaffleWinner,
+
+    #[error("Airdrop is deactivated for good")]
+    AirdropEnded,
+
+    #[error("Oracle price is stale or invalid")]
+    StaleOracle,
+
+    #[error("Airdrop is finalized")]
+    AirdropFinalized,
+
+    #[error("User is blocked by airdrop authority")]
+    UserBlocked,
+
+    #[error("Master edition account is not properly derived")]
+    InvalidMasterEditionPda,
+
+    #[error("User has already minted")]
+    UserHasMinted,
+
+    #[error("Account data is too small")]
+    InvalidAccountData,
+
+    #[error("Mint must be top level instruction")]
+    CpiNotAllowed,
+
+    #[error("Payer balance is below required minimum")]
+    InsufficientStake,
+
+    #[error("Payer can not afford rent of minted accounts and price it pays")]
+    PayerCannotAffordRent,
 }
 
 impl PrintProgramError for AirdropError {
@@ -0,0 +1,73 @@
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    log::sol_log_data,
+    program_error::ProgramError,
+    program_pack::{Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// Logged by MintOne with `sol_log_data`, so indexers decode it from `Program data:` log lines.
+/// Data is 8 byte discriminator followed by packed event, as Anchor events are laid out
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MintEvent {
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub index: u64,
+    pub price: u64,
+}
+
+impl MintEvent {
+    /// First 8 bytes of `sha256("event:MintEvent")`
+    pub const DISCRIMINATOR: [u8; 8] = [197, 144, 146, 149, 66, 164, 95, 16];
+
+    pub fn emit(&self) {
+        sol_log_data(&[&self.to_log_data()]);
+    }
+
+    pub fn to_log_data(&self) -> Vec<u8> {
+        let mut data = vec![0u8; 8 + MintEvent::LEN];
+        data[..8].copy_from_slice(&Self::DISCRIMINATOR);
+        self.pack_into_slice(&mut data[8..]);
+        data
+    }
+
+    pub fn from_log_data(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < 8 || data[..8] != Self::DISCRIMINATOR {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Self::unpack_from_slice(&data[8..])
+    }
+}
+
+impl Sealed for MintEvent {}
+
+impl Pack for MintEvent {
+    const LEN: usize = 32 + 32 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, MintEvent::LEN];
+        let (user, mint, index, price) = mut_array_refs![dst, 32, 32, 8, 8];
+
+        user.copy_from_slice(&self.user.to_bytes());
+        mint.copy_from_slice(&self.mint.to_bytes());
+        index.copy_from_slice(&self.index.to_le_bytes());
+        price.copy_from_slice(&self.price.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != MintEvent::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src = array_ref![src, 0, MintEvent::LEN];
+        let (user_src, mint_src, index_src, price_src) = array_refs![src, 32, 32, 8, 8];
+
+        Ok(MintEvent {
+            user: Pubkey::new_from_array(*user_src),
+            mint: Pubkey::new_from_array(*mint_src),
+            index: u64::from_le_bytes(*index_src),
+            price: u64::from_le_bytes(*price_src),
+        })
+    }
+}
@@ -1,32 +1,170 @@
 use std::convert::TryInto;
 
-use arrayref::array_refs;
-use solana_program::program_error::ProgramError;
+use arrayref::{array_ref, array_refs};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
-use crate::error::AirdropError;
+use crate::{
+    error::AirdropError,
+    state::{unpack_revenue_splits, RevenueSplit, MAX_REVENUE_SPLITS, REVENUE_SPLITS_LEN},
+};
 
+/// Instruction id 10 serializes fields in declaration order, base URI last. Integers are little endian,
+/// bools are a single 0 or 1 byte and fixed size strings are NUL padded.
+/// `tests/fixtures/initialize_airdrop.hex` pins the exact layout for hand-written clients
 pub struct InitializeAirdropArgs {
     pub airdrop_amount: u64,
-    pub metadata_prefix: [u8; 32],
+    pub base_uri: Vec<u8>,
     pub symbol: [u8; 8],
     pub price: u64,
+    pub placeholder_uri: [u8; 32],
+    pub auto_lock: bool,
+    pub revenue_splits: [RevenueSplit; MAX_REVENUE_SPLITS],
+    pub charge_payer: bool,
+    pub max_mints_per_tx: u8,
+    pub name: [u8; 16],
+    pub trait_count: u8,
+    pub mint_cooldown_secs: u64,
+    pub referral_bps: u16,
+    pub bot_tax_lamports: u64,
+    pub expected_decimals: u8,
+    pub escrow_timeout_secs: u64,
+    pub mint_quantity: u64,
+    pub registration_fee: u64,
+    pub fairness_bps: u16,
+    pub raffle: bool,
+    pub require_registration: bool,
+    pub deny_cpi_mint: bool,
+    pub min_payer_balance: u64,
 }
 
-pub struct InitializeAirdropUserDataArgs {}
+/// Optional `idempotent` flag is the only body byte. Idempotent registration
+/// of already registered user succeeds without changes
+pub struct InitializeAirdropUserDataArgs {
+    pub idempotent: bool,
+}
+
+/// Body is stage allowlist merkle proof, 32 bytes per node, empty without allowlist,
+/// followed by optional flags byte where 1 marks a gift to another wallet.
+/// Omitting the flags byte keeps body of older clients valid
+pub struct MintOneArgs {
+    pub proof: Vec<[u8; 32]>,
+    pub gift: bool,
+}
+
+pub struct RevealArgs {}
+
+pub struct CanMintArgs {}
+
+pub struct IncreaseSupplyArgs {
+    pub additional: u64,
+}
+
+/// Body is stage allowlist merkle proof, 32 bytes per node, empty without allowlist
+pub struct PreviewMintArgs {
+    pub proof: Vec<[u8; 32]>,
+}
+
+pub struct BatchInitUsersArgs {}
+
+pub struct SweepMintAuthorityArgs {}
+
+pub struct AddStageArgs {
+    pub stage_index: u8,
+    pub price: u64,
+    pub supply_cap: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub merkle_root: [u8; 32],
+    pub per_user_limit: u64,
+}
+
+pub struct RemoveStageArgs {}
+
+pub struct SweepUserAccountArgs {}
+
+/// Body is claimant wallet followed by stage allowlist merkle proof, 32 bytes per node
+pub struct MintToEscrowArgs {
+    pub claimant: Pubkey,
+    pub proof: Vec<[u8; 32]>,
+}
+
+pub struct ClaimFromEscrowArgs {}
+
+pub struct SweepEscrowArgs {}
+
+pub struct DepositPremintedArgs {}
+
+pub struct WithdrawUnclaimedArgs {}
+
+pub struct UpdateRevenueWalletArgs {
+    pub revenue_wallet: Pubkey,
+}
+
+pub struct DrawRaffleArgs {
+    pub winners_count: u64,
+}
+
+pub struct CloseUserDataArgs {}
+
+pub struct DeactivateArgs {}
+
+/// Optional `lock` flag is the only body byte. Locked airdrop drops its authority
+pub struct FinalizeArgs {
+    pub lock: bool,
+}
+
+pub struct SetUserBlockedArgs {
+    pub blocked: bool,
+}
+
+pub struct DeregisterArgs {}
+
+pub struct RecomputeBumpsArgs {}
+
+/// Zero USD price turns USD pricing off
+pub struct SetUsdPriceArgs {
+    pub price_usd_cents: u64,
+    pub pyth_price_account: Pubkey,
+    pub max_price_age_slots: u64,
+}
+
+/// Body is stage allowlist merkle proof, 32 bytes per node, empty without allowlist
+pub struct ClaimPremintedArgs {
+    pub proof: Vec<[u8; 32]>,
+}
 
-pub struct MintOneArgs {}
+pub struct UpdateWindowArgs {
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
 
+/// Airdrop authority is only ever required to sign. It never pays fees and is only
+/// written to when receiving swept rent, so it can be a PDA of another program (e.g. governance treasury)
+/// signing through `invoke_signed`. Such programs depend on this crate with
+/// `no-entrypoint` feature and pass accounts in the order documented on each variant
+#[allow(clippy::large_enum_variant)]
 pub enum AirdropInstruction {
+    ///
+    /// Instruction id 10 carries base URI of up to 200 bytes as length prefixed string
+    /// after the other args. Id 1 is the legacy layout with base URI as NUL padded
+    /// 32 byte array after airdrop amount. It is frozen at `bot_tax_lamports`,
+    /// fields added later take their defaults
     ///
     /// Accounts required:
-    /// 0. `[writeable]`. Airdrop account. Used to store all of the airdrop data
-    /// 1. `[]`. Airdrop authority. Account that will have the authority to airdrop nfts
+    /// 0. `[writeable]`. Airdrop account. Used to store all of the airdrop data.
+    ///    Created by program if derived from airdrop authority and name, otherwise must be
+    ///    created and assigned to program beforehand
+    /// 1. `[]`. Airdrop authority. Account that will have the authority to airdrop nfts.
+    ///    Must sign when airdrop account is derived from it, so nobody else takes the name
     /// 2. `[writeable]`. Mint authority. It will be used to mint airdropped nfts
-    /// 3. `[]`. Revenues wallet. Wallet where all revenues are paid out
+    /// 3. `[]`. Revenues wallet. Wallet where all revenues are paid out. Must be a system owned
+    ///    account on ed25519 curve, distinct from airdrop, mint authority and fee payer
     /// 4. `[]`. Admin account. Account that will be verified creator of minted tokens.
     /// 5. `[]`. Rent sysvar
-    /// 6. `[signer]`. Fee payer. Wallet that will pay for creating mint authority
+    /// 6. `[signer]`. Fee payer. Wallet that will pay for creating mint authority and airdrop account
     /// 7. `[]`. System program
+    /// 8. `[writeable]`. Platform wallet. Required only when build charges platform fee,
+    ///    which fee payer pays
     InitializeAirdrop(InitializeAirdropArgs),
 
     ///
@@ -35,17 +173,33 @@ pub enum AirdropInstruction {
     /// 1. `[]`. User. Wallet that will use the account
     /// 2. `[writeable]`. Airdrop. Airdrop that data account will be associated with
     /// 3. `[]`. Rent sysvar
-    /// 4. `[signer]`. Fee payer. Wallet that is paying fee for creating an account
+    /// 4. `[signer]`. Fee payer. Wallet that is paying fee for creating an account.
+    ///    Must be writeable and also pays registration fee when airdrop charges one
     /// 5. `[]`. System program
+    /// 6. `[writeable]`. Revenue wallet. Required only when airdrop charges registration fee
+    ///
+    /// Idempotent variant does nothing if user data already exists for this airdrop and user.
+    /// User data left over from a previous airdrop at the same address is re-initialized
     InitializeAirdropUser(InitializeAirdropUserDataArgs),
 
+    ///
+    /// Sets `MintReturnData` (minted index, mint, price paid) as return data.
+    /// With bot tax configured, mint outside of mint window or active stage, user in timeout,
+    /// off stage allowlist, over stage limit or over per transaction limit
+    /// pays the tax to revenue wallet and nothing is minted.
+    /// Airdrop with `trait_count` names every token `<symbol> #<index> T<trait>`.
+    /// Airdrop with `deny_cpi_mint` rejects mints invoked by other programs.
+    /// Airdrop with `min_payer_balance` rejects payers holding less lamports before the mint.
     ///
     /// Accounts required:
     /// 0. `[writeable]`. Airdrop account
-    /// 1. `[writeable]`. User data account
+    /// 1. `[writeable]`. User data account. Airdrop that does not require registration
+    ///    takes program id as placeholder, mint is not counted against user then.
+    ///    Missing or stale user data is registered and paid by payer, so fresh wallet mints without registration
     /// 2. `[signer, writeable]`. SPL mint account. Represents a token in solana blockchain
-    /// 3. `[]`. Recipient. Wallet that owns user data account and will recieve a token
-    /// 4. `[writeable]`. Recipient token account.
+    /// 3. `[]`. User. Wallet that owns user data account and will recieve a token unless it is a gift.
+    ///    Must be `[signer, writeable]` when airdrop doesn't charge payer, as it pays the price then
+    /// 4. `[writeable]`. Associated token account of token receiver.
     /// 5. `[writeable]`. Token metadata account. Holds NFT metadata
     /// 6. `[]`. Mint authority
     /// 7. `[]`. System program
@@ -54,42 +208,757 @@ pub enum AirdropInstruction {
     /// 10. `[]`. Token program
     /// 11. `[]`. Associated token program
     /// 12. `[]`. Metaplex token metadata program
-    /// 13. `[signer, writeable]`. Payer. Wallet that pays for NFT.
+    /// 13. `[signer, writeable]`. Payer. Wallet that pays for NFT accounts, and for the price when
+    ///     airdrop charges payer. May differ from recipient, mint is still counted against
+    ///     recipient's user data
     /// 14. `[signer]`. Airdrop authority. Authority owner must verify that user is eglible for airdrop
     /// 15. `[]`. Admin account
-    /// 16. `[writeable]`. Revenue wallet
+    /// 16. `[writeable]`. Revenue wallet. May be read-only when mint is free and there is no bot tax
+    /// 17. `[]`. Instructions sysvar
+    /// 18. `[]`. Slot hashes sysvar
+    /// 19. `[writeable]`. Master edition account. Created with zero max supply when airdrop mints
+    ///     a single token without decimals
+    /// 20. `[writeable]`. Revenue split wallets. One account for each configured split, in config order.
+    ///     May be read-only when mint is free
+    /// 21. `[writeable]`. Protocol treasury, right after revenue split wallets. Required only by
+    ///     builds taking protocol fee. May be read-only when mint is free
+    /// 22. `[]`. Gift recipient, right after accounts above. Required only with gift flag.
+    ///     Receives the token, while the mint is counted against user
+    /// 23. `[writeable]`. Active stage, right after accounts above.
+    ///     Required only when airdrop has stages. Its price and limits apply instead of airdrop price
+    /// 24. `[]`. Pyth price account, right after active stage or accounts above.
+    ///     Required only when airdrop is priced in USD
+    /// 25. `[writeable]`. Optional referrer, last of accounts above.
+    ///     Receives referral cut of the price when airdrop has one
     MintOne(MintOneArgs),
+
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority. Only it can reveal the collection
+    Reveal(RevealArgs),
+
+    ///
+    /// Runs the same eligibility checks as MintOne without minting anything.
+    /// Safe to simulate, no signatures required
+    ///
+    /// Accounts required:
+    /// 0. `[]`. Airdrop account
+    /// 1. `[]`. User data account. Any placeholder when airdrop does not require registration
+    /// 2. `[]`. User. Wallet that owns user data account
+    /// 3. `[]`. Clock sysvar
+    CanMint(CanMintArgs),
+
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    IncreaseSupply(IncreaseSupplyArgs),
+
+    ///
+    /// Runs the same eligibility checks as MintOne and sets `MintPreview`
+    /// (price, rent for created accounts, remaining supply and allowance) as return data.
+    /// Price comes from active stage or USD price the same way MintOne resolves it.
+    /// Safe to simulate, no signatures required
+    ///
+    /// Accounts required:
+    /// 0. `[]`. Airdrop account
+    /// 1. `[]`. User data account. Any placeholder when airdrop does not require registration
+    /// 2. `[]`. User. Wallet that owns user data account
+    /// 3. `[]`. Clock sysvar
+    /// 4. `[]`. Rent sysvar
+    /// 5. `[]`. Mint. Address of token that would be minted, need not exist yet
+    /// 6. `[]`. User token account. Associated token account of user for mint,
+    ///    its rent is counted only when it doesn't exist yet
+    /// 7. `[]`. Stage. Required only if airdrop has stages, must be the currently active one
+    /// 8. `[]`. Pyth SOL/USD price account. Required only when USD price is set
+    PreviewMint(PreviewMintArgs),
+
+    ///
+    /// Creates user data accounts for many recipients at once, paid by the fee payer.
+    /// Already existing user data accounts are skipped
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    /// 2. `[]`. Rent sysvar
+    /// 3. `[signer, writeable]`. Fee payer. Wallet that is paying fee for creating accounts
+    /// 4. `[]`. System program
+    /// 5. `[writeable]`. User data account, followed by `[]` recipient wallet. Repeated for every recipient
+    BatchInitUsers(BatchInitUsersArgs),
+
+    ///
+    /// Sets unix timestamps mints are allowed between. Zero `start_ts` allows mints right away,
+    /// zero `end_ts` keeps airdrop open with no end
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    UpdateWindow(UpdateWindowArgs),
+
+    ///
+    /// Moves all lamports held by mint authority to revenue wallet
+    ///
+    /// Accounts required:
+    /// 0. `[]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    /// 2. `[writeable]`. Mint authority
+    /// 3. `[writeable]`. Revenue wallet stored in airdrop config
+    /// 4. `[]`. System program
+    SweepMintAuthority(SweepMintAuthorityArgs),
+
+    ///
+    /// Creates stage account derived from airdrop and stage index. Only possible before
+    /// the first mint. Stage windows must not overlap, so every existing stage is passed in
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    /// 2. `[writeable]`. Stage account
+    /// 3. `[]`. Rent sysvar
+    /// 4. `[signer, writeable]`. Fee payer. Wallet that is paying fee for creating stage account
+    /// 5. `[]`. System program
+    /// 6. `[]`. Every existing stage of airdrop
+    AddStage(AddStageArgs),
+
+    ///
+    /// Closes stage nothing was minted in and returns its rent
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    /// 2. `[writeable]`. Stage account
+    /// 3. `[writeable]`. Receiver of stage account rent
+    RemoveStage(RemoveStageArgs),
+
+    ///
+    /// Closes user data account and returns its rent to airdrop authority.
+    /// Only possible once mint window has ended at least `USER_SWEEP_GRACE_SECS` ago
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer, writeable]`. Airdrop authority. Receives user data account rent
+    /// 2. `[writeable]`. User data account
+    /// 3. `[]`. Clock sysvar
+    SweepUserAccount(SweepUserAccountArgs),
+
+    ///
+    /// Same as MintOne, but the token is minted into associated token account of escrow
+    /// derived from airdrop and minted index. Escrow records claimant, who takes the token
+    /// with ClaimFromEscrow. Payer funds escrow account and gets its rent back on close
+    ///
+    /// Accounts required:
+    /// 0-19. Same as MintOne, with account 4 being associated token account of escrow
+    /// 20. `[writeable]`. Escrow account, in place of gift recipient
+    /// 21-22. Same as MintOne
+    MintToEscrow(MintToEscrowArgs),
+
+    ///
+    /// Transfers escrowed token to claimant and closes escrow accounts.
+    /// Rent goes back to payer of the mint
+    ///
+    /// Accounts required:
+    /// 0. `[]`. Airdrop account
+    /// 1. `[writeable]`. Escrow account
+    /// 2. `[writeable]`. Escrow token account. Associated token account of escrow
+    /// 3. `[signer, writeable]`. Claimant. Pays for its token account when it does not exist yet
+    /// 4. `[writeable]`. Claimant token account. Associated token account of claimant
+    /// 5. `[]`. Escrowed mint
+    /// 6. `[writeable]`. Payer of the mint stored in escrow. Receives escrow rent
+    /// 7. `[]`. Rent sysvar
+    /// 8. `[]`. System program
+    /// 9. `[]`. Token program
+    /// 10. `[]`. Associated token program
+    ClaimFromEscrow(ClaimFromEscrowArgs),
+
+    ///
+    /// Moves unclaimed token to authority chosen token account and closes escrow accounts.
+    /// Only possible `escrow_timeout_secs` after escrow was created. Zero timeout disables sweeping
+    ///
+    /// Accounts required:
+    /// 0. `[]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    /// 2. `[writeable]`. Escrow account
+    /// 3. `[writeable]`. Escrow token account. Associated token account of escrow
+    /// 4. `[writeable]`. Destination token account of escrowed mint
+    /// 5. `[writeable]`. Payer of the mint stored in escrow. Receives escrow rent
+    /// 6. `[]`. Clock sysvar
+    /// 7. `[]`. Token program
+    SweepEscrow(SweepEscrowArgs),
+
+    ///
+    /// Moves NFT minted elsewhere into vault token account and queues its mint for
+    /// ClaimPreminted. Vault is created on first deposit and grows with every deposit
+    ///
+    /// Accounts required:
+    /// 0. `[]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority. Owner of source token account
+    /// 2. `[writeable]`. Vault account derived from airdrop
+    /// 3. `[writeable]`. Vault token account. Associated token account of vault
+    /// 4. `[]`. Deposited mint. Single token with no decimals
+    /// 5. `[writeable]`. Source token account holding the NFT
+    /// 6. `[signer, writeable]`. Fee payer. Pays for vault growth and vault token account
+    /// 7. `[]`. Rent sysvar
+    /// 8. `[]`. System program
+    /// 9. `[]`. Token program
+    /// 10. `[]`. Associated token program
+    DepositPreminted(DepositPremintedArgs),
+
+    ///
+    /// Transfers the next queued NFT from vault to user with the same price, supply,
+    /// window, stage and user limit checks as MintOne. Claims count against airdrop supply.
+    /// Emptied vault token account is closed and its rent goes to airdrop authority,
+    /// which deposited the NFT.
+    /// Sets `MintReturnData` as return data
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[writeable]`. User data account
+    /// 2. `[]`. User. Wallet that owns user data account and receives the NFT.
+    ///    Must be `[signer, writeable]` when airdrop doesn't charge payer, as it pays the price then
+    /// 3. `[writeable]`. User token account. Associated token account of user
+    /// 4. `[writeable]`. Vault account
+    /// 5. `[writeable]`. Vault token account of the next queued mint
+    /// 6. `[]`. Next queued mint
+    /// 7. `[signer, writeable]`. Payer. Pays for user token account, and for the price
+    ///    when airdrop charges payer
+    /// 8. `[signer, writeable]`. Airdrop authority. Receives rent of vault token account
+    /// 9. `[writeable]`. Revenue wallet. May be read-only when claim is free
+    /// 10. `[]`. Clock sysvar
+    /// 11. `[]`. Rent sysvar
+    /// 12. `[]`. Instructions sysvar
+    /// 13. `[]`. System program
+    /// 14. `[]`. Token program
+    /// 15. `[]`. Associated token program
+    /// 16. `[writeable]`. Revenue split wallets. One account for each configured split, in config order
+    /// 17. `[writeable]`. Active stage, right after revenue split wallets. Required only when airdrop has stages
+    /// 18. `[]`. Pyth price account, last of accounts above. Required only when airdrop is priced in USD
+    ClaimPreminted(ClaimPremintedArgs),
+
+    ///
+    /// Returns a still queued NFT to authority once mint window has ended.
+    /// Mint is swap removed from the queue and vault shrinks. Emptied vault token account
+    /// is closed. Its rent and the freed vault rent go to rent receiver.
+    /// Drain the vault by calling it once per queued mint
+    ///
+    /// Accounts required:
+    /// 0. `[]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    /// 2. `[writeable]`. Vault account
+    /// 3. `[writeable]`. Vault token account of withdrawn mint
+    /// 4. `[]`. Withdrawn mint. Must be queued and not claimed yet
+    /// 5. `[writeable]`. Destination token account
+    /// 6. `[writeable]`. Rent receiver
+    /// 7. `[]`. Clock sysvar
+    /// 8. `[]`. Rent sysvar
+    /// 9. `[]`. Token program
+    WithdrawUnclaimed(WithdrawUnclaimedArgs),
+
+    ///
+    /// Points price, fees and sweeps to new revenue wallet. Already collected lamports stay
+    /// in the old one. Revenue split wallets are not changed
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    UpdateRevenueWallet(UpdateRevenueWalletArgs),
+
+    ///
+    /// Picks raffle winners from a recent slot hash and closes registration.
+    /// Winner is a user whose registration index hashed with the seed,
+    /// modulo registered users, is below winners count.
+    /// Slot hash is known to the leader producing the block, so the leader can
+    /// grind the draw. Verifiable randomness is not supported yet
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    /// 2. `[]`. Slot hashes sysvar
+    DrawRaffle(DrawRaffleArgs),
+
+    ///
+    /// Closes user data of raffle loser and returns its rent to user
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer, writeable]`. User wallet
+    /// 2. `[writeable]`. User data account
+    CloseUserData(CloseUserDataArgs),
+
+    ///
+    /// Ends airdrop for good. Unlike closing the mint window with UpdateWindow,
+    /// deactivated airdrop can't be resumed
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    Deactivate(DeactivateArgs),
+
+    ///
+    /// Prices mints in USD cents, converted to lamports with Pyth SOL/USD price on every mint.
+    /// USD price replaces lamport prices of airdrop and stages. MintOne and ClaimPreminted
+    /// then take Pyth price account right before optional referrer
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    SetUsdPrice(SetUsdPriceArgs),
+
+    ///
+    /// Freezes airdrop once supply is exhausted or mint window ended, and records final
+    /// minted, gifted, registered and revenue totals. Finalized airdrop can't mint, register
+    /// or change its settings. Reveal and sweeps still work, unless airdrop is locked
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    /// 2. `[]`. Clock sysvar
+    Finalize(FinalizeArgs),
+
+    ///
+    /// Blocks user from minting and claiming, or unblocks. User data is created when wallet
+    /// is not registered yet, so wallets can be blocked before they register
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    /// 2. `[writeable]`. User data account
+    /// 3. `[]`. User wallet
+    /// 4. `[]`. Rent sysvar
+    /// 5. `[signer, writeable]`. Fee payer. Pays for user data when it is created
+    /// 6. `[]`. System program
+    SetUserBlocked(SetUserBlockedArgs),
+
+    ///
+    /// Closes user data of a wallet that has not minted yet and returns its rent to user.
+    /// Wallet may register again afterwards
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer, writeable]`. User wallet
+    /// 2. `[writeable]`. User data account
+    Deregister(DeregisterArgs),
+
+    ///
+    /// Re-derives mint authority and user data bumps and rewrites the cached ones.
+    /// Migration escape hatch for configs holding stale bumps
+    ///
+    /// Accounts required:
+    /// 0. `[writeable]`. Airdrop account
+    /// 1. `[signer]`. Airdrop authority
+    /// 2. `[]`. Mint authority. Must be the re-derived PDA
+    /// 3. `[]`. Metadata of a token minted by the airdrop, verified by the re-derived mint authority.
+    ///    Only passed once airdrop has minted
+    /// 4. `[writeable]`. User data accounts of the airdrop, any number of them
+    RecomputeBumps(RecomputeBumpsArgs),
 }
 
+/// Fixed size part of initialize airdrop args that follows airdrop amount and base URI
+const INITIALIZE_AIRDROP_FIELDS_LEN: usize = 124 + REVENUE_SPLITS_LEN;
+
+/// Fixed size part of legacy initialize airdrop args, frozen at `bot_tax_lamports`.
+/// Fields added later are not part of id 1 and get their defaults
+const LEGACY_INITIALIZE_AIRDROP_FIELDS_LEN: usize = 86 + REVENUE_SPLITS_LEN;
+
+/// Legacy layout with base URI as NUL padded 32 byte array
+fn parse_initialize_airdrop_fixed_args(body: &[u8]) -> Result<InitializeAirdropArgs, ProgramError> {
+    let body_sized: &[u8; 40 + LEGACY_INITIALIZE_AIRDROP_FIELDS_LEN] = body
+        .try_into()
+        .or(Err(AirdropError::BadInstructionArgument))?;
+
+    let (airdrop_amount_array, metadata_prefix_array, legacy_fields_array) =
+        array_refs!(body_sized, 8, 32, LEGACY_INITIALIZE_AIRDROP_FIELDS_LEN);
+
+    let base_uri_end = metadata_prefix_array
+        .iter()
+        .rposition(|&c| c != 0)
+        .map_or(0, |i| i + 1);
+
+    let mut fields_array = [0; INITIALIZE_AIRDROP_FIELDS_LEN];
+    fields_array[..LEGACY_INITIALIZE_AIRDROP_FIELDS_LEN].copy_from_slice(legacy_fields_array);
+
+    let args = parse_initialize_airdrop_fields(
+        airdrop_amount_array,
+        metadata_prefix_array[..base_uri_end].to_vec(),
+        &fields_array,
+    )?;
+
+    Ok(InitializeAirdropArgs {
+        expected_decimals: 0,
+        escrow_timeout_secs: 0,
+        mint_quantity: 1,
+        registration_fee: 0,
+        fairness_bps: 0,
+        raffle: false,
+        require_registration: true,
+        deny_cpi_mint: false,
+        min_payer_balance: 0,
+        ..args
+    })
+}
+
+/// Layout with base URI as length prefixed string at the tail
 fn parse_initialize_airdrop_args(body: &[u8]) -> Result<InitializeAirdropArgs, ProgramError> {
-    let body_sized: &[u8; 56] = body
+    if body.len() < 8 + INITIALIZE_AIRDROP_FIELDS_LEN + 1 {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    let (fixed, base_uri) = body.split_at(8 + INITIALIZE_AIRDROP_FIELDS_LEN + 1);
+    let fixed: &[u8; 8 + INITIALIZE_AIRDROP_FIELDS_LEN + 1] = fixed
         .try_into()
         .or(Err(AirdropError::BadInstructionArgument))?;
 
-    let (airdrop_amount_array, metadata_prefix_array, symbol_array, price_array) =
-        array_refs!(body_sized, 8, 32, 8, 8);
+    let (airdrop_amount_array, fields_array, base_uri_len_array) =
+        array_refs!(fixed, 8, INITIALIZE_AIRDROP_FIELDS_LEN, 1);
+
+    if base_uri.len() != base_uri_len_array[0] as usize {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    parse_initialize_airdrop_fields(airdrop_amount_array, base_uri.to_vec(), fields_array)
+}
+
+fn parse_initialize_airdrop_fields(
+    airdrop_amount_array: &[u8; 8],
+    base_uri: Vec<u8>,
+    fields_array: &[u8; INITIALIZE_AIRDROP_FIELDS_LEN],
+) -> Result<InitializeAirdropArgs, ProgramError> {
+    let (
+        symbol_array,
+        price_array,
+        placeholder_uri_array,
+        auto_lock_array,
+        revenue_splits_array,
+        charge_payer_array,
+        max_mints_per_tx_array,
+        name_array,
+        trait_count_array,
+        mint_cooldown_secs_array,
+        referral_bps_array,
+        bot_tax_lamports_array,
+        expected_decimals_array,
+        escrow_timeout_secs_array,
+        mint_quantity_array,
+        registration_fee_array,
+        fairness_bps_array,
+        raffle_array,
+        require_registration_array,
+        deny_cpi_mint_array,
+        min_payer_balance_array,
+    ) = array_refs!(
+        fields_array,
+        8,
+        8,
+        32,
+        1,
+        REVENUE_SPLITS_LEN,
+        1,
+        1,
+        16,
+        1,
+        8,
+        2,
+        8,
+        1,
+        8,
+        8,
+        8,
+        2,
+        1,
+        1,
+        1,
+        8
+    );
 
     let airdrop_amount = u64::from_le_bytes(*airdrop_amount_array);
-    let metadata_prefix = *metadata_prefix_array;
     let symbol = *symbol_array;
     let price = u64::from_le_bytes(*price_array);
+    let placeholder_uri = *placeholder_uri_array;
+    let auto_lock = match auto_lock_array {
+        [0] => false,
+        [1] => true,
+        _ => return Err(AirdropError::BadInstructionArgument.into()),
+    };
+    let revenue_splits = unpack_revenue_splits(revenue_splits_array);
+    let charge_payer = match charge_payer_array {
+        [0] => false,
+        [1] => true,
+        _ => return Err(AirdropError::BadInstructionArgument.into()),
+    };
+    let max_mints_per_tx = max_mints_per_tx_array[0];
+    let name = *name_array;
+    let trait_count = trait_count_array[0];
+    let mint_cooldown_secs = u64::from_le_bytes(*mint_cooldown_secs_array);
+    let referral_bps = u16::from_le_bytes(*referral_bps_array);
+    let bot_tax_lamports = u64::from_le_bytes(*bot_tax_lamports_array);
+    let expected_decimals = expected_decimals_array[0];
+    let escrow_timeout_secs = u64::from_le_bytes(*escrow_timeout_secs_array);
+    let mint_quantity = u64::from_le_bytes(*mint_quantity_array);
+    let registration_fee = u64::from_le_bytes(*registration_fee_array);
+    let fairness_bps = u16::from_le_bytes(*fairness_bps_array);
+    let raffle = match raffle_array {
+        [0] => false,
+        [1] => true,
+        _ => return Err(AirdropError::BadInstructionArgument.into()),
+    };
+    let require_registration = match require_registration_array {
+        [0] => false,
+        [1] => true,
+        _ => return Err(AirdropError::BadInstructionArgument.into()),
+    };
+    let deny_cpi_mint = match deny_cpi_mint_array {
+        [0] => false,
+        [1] => true,
+        _ => return Err(AirdropError::BadInstructionArgument.into()),
+    };
+    let min_payer_balance = u64::from_le_bytes(*min_payer_balance_array);
 
     Ok(InitializeAirdropArgs {
         airdrop_amount,
-        metadata_prefix,
+        base_uri,
         symbol,
         price,
+        placeholder_uri,
+        auto_lock,
+        revenue_splits,
+        charge_payer,
+        max_mints_per_tx,
+        name,
+        trait_count,
+        mint_cooldown_secs,
+        referral_bps,
+        bot_tax_lamports,
+        expected_decimals,
+        escrow_timeout_secs,
+        mint_quantity,
+        registration_fee,
+        fairness_bps,
+        raffle,
+        require_registration,
+        deny_cpi_mint,
+        min_payer_balance,
     })
 }
 
 fn parse_initialize_airdrop_user_args(
-    _body: &[u8],
+    body: &[u8],
 ) -> Result<InitializeAirdropUserDataArgs, ProgramError> {
-    Ok(InitializeAirdropUserDataArgs {})
+    let idempotent = match body {
+        [] | [0] => false,
+        [1] => true,
+        _ => return Err(AirdropError::BadInstructionArgument.into()),
+    };
+
+    Ok(InitializeAirdropUserDataArgs { idempotent })
+}
+
+fn parse_mint_one_args(body: &[u8]) -> Result<MintOneArgs, ProgramError> {
+    let nodes = body.chunks_exact(32);
+
+    let gift = match nodes.remainder() {
+        [] | [0] => false,
+        [1] => true,
+        _ => return Err(AirdropError::BadInstructionArgument.into()),
+    };
+
+    let proof = nodes.map(|node| *array_ref![node, 0, 32]).collect();
+
+    Ok(MintOneArgs { proof, gift })
+}
+
+fn parse_reveal_args(_body: &[u8]) -> Result<RevealArgs, ProgramError> {
+    Ok(RevealArgs {})
+}
+
+fn parse_can_mint_args(_body: &[u8]) -> Result<CanMintArgs, ProgramError> {
+    Ok(CanMintArgs {})
+}
+
+fn parse_increase_supply_args(body: &[u8]) -> Result<IncreaseSupplyArgs, ProgramError> {
+    let body_sized: &[u8; 8] = body
+        .try_into()
+        .or(Err(AirdropError::BadInstructionArgument))?;
+
+    let additional = u64::from_le_bytes(*body_sized);
+
+    Ok(IncreaseSupplyArgs { additional })
+}
+
+fn parse_preview_mint_args(body: &[u8]) -> Result<PreviewMintArgs, ProgramError> {
+    let nodes = body.chunks_exact(32);
+
+    if !nodes.remainder().is_empty() {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    Ok(PreviewMintArgs {
+        proof: nodes.map(|node| *array_ref![node, 0, 32]).collect(),
+    })
+}
+
+fn parse_batch_init_users_args(_body: &[u8]) -> Result<BatchInitUsersArgs, ProgramError> {
+    Ok(BatchInitUsersArgs {})
+}
+
+fn parse_update_window_args(body: &[u8]) -> Result<UpdateWindowArgs, ProgramError> {
+    let body_sized: &[u8; 16] = body
+        .try_into()
+        .or(Err(AirdropError::BadInstructionArgument))?;
+
+    let (start_ts, end_ts) = array_refs![body_sized, 8, 8];
+
+    Ok(UpdateWindowArgs {
+        start_ts: i64::from_le_bytes(*start_ts),
+        end_ts: i64::from_le_bytes(*end_ts),
+    })
+}
+
+fn parse_sweep_mint_authority_args(_body: &[u8]) -> Result<SweepMintAuthorityArgs, ProgramError> {
+    Ok(SweepMintAuthorityArgs {})
+}
+
+fn parse_add_stage_args(body: &[u8]) -> Result<AddStageArgs, ProgramError> {
+    let body_sized: &[u8; 73] = body
+        .try_into()
+        .or(Err(AirdropError::BadInstructionArgument))?;
+
+    let (stage_index, price, supply_cap, start_ts, end_ts, merkle_root, per_user_limit) =
+        array_refs![body_sized, 1, 8, 8, 8, 8, 32, 8];
+
+    Ok(AddStageArgs {
+        stage_index: stage_index[0],
+        price: u64::from_le_bytes(*price),
+        supply_cap: u64::from_le_bytes(*supply_cap),
+        start_ts: i64::from_le_bytes(*start_ts),
+        end_ts: i64::from_le_bytes(*end_ts),
+        merkle_root: *merkle_root,
+        per_user_limit: u64::from_le_bytes(*per_user_limit),
+    })
+}
+
+fn parse_remove_stage_args(_body: &[u8]) -> Result<RemoveStageArgs, ProgramError> {
+    Ok(RemoveStageArgs {})
+}
+
+fn parse_sweep_user_account_args(_body: &[u8]) -> Result<SweepUserAccountArgs, ProgramError> {
+    Ok(SweepUserAccountArgs {})
+}
+
+fn parse_mint_to_escrow_args(body: &[u8]) -> Result<MintToEscrowArgs, ProgramError> {
+    if body.len() < 32 {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    let (claimant, proof) = body.split_at(32);
+    let nodes = proof.chunks_exact(32);
+
+    if !nodes.remainder().is_empty() {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    Ok(MintToEscrowArgs {
+        claimant: Pubkey::new_from_array(*array_ref![claimant, 0, 32]),
+        proof: nodes.map(|node| *array_ref![node, 0, 32]).collect(),
+    })
+}
+
+fn parse_claim_from_escrow_args(_body: &[u8]) -> Result<ClaimFromEscrowArgs, ProgramError> {
+    Ok(ClaimFromEscrowArgs {})
+}
+
+fn parse_sweep_escrow_args(_body: &[u8]) -> Result<SweepEscrowArgs, ProgramError> {
+    Ok(SweepEscrowArgs {})
+}
+
+fn parse_deposit_preminted_args(_body: &[u8]) -> Result<DepositPremintedArgs, ProgramError> {
+    Ok(DepositPremintedArgs {})
 }
 
-fn parse_mint_one_args(_body: &[u8]) -> Result<MintOneArgs, ProgramError> {
-    Ok(MintOneArgs {})
+fn parse_withdraw_unclaimed_args(_body: &[u8]) -> Result<WithdrawUnclaimedArgs, ProgramError> {
+    Ok(WithdrawUnclaimedArgs {})
+}
+
+fn parse_update_revenue_wallet_args(body: &[u8]) -> Result<UpdateRevenueWalletArgs, ProgramError> {
+    let body_sized: &[u8; 32] = body
+        .try_into()
+        .or(Err(AirdropError::BadInstructionArgument))?;
+
+    Ok(UpdateRevenueWalletArgs {
+        revenue_wallet: Pubkey::new_from_array(*body_sized),
+    })
+}
+
+fn parse_draw_raffle_args(body: &[u8]) -> Result<DrawRaffleArgs, ProgramError> {
+    let body_sized: &[u8; 8] = body
+        .try_into()
+        .or(Err(AirdropError::BadInstructionArgument))?;
+
+    Ok(DrawRaffleArgs {
+        winners_count: u64::from_le_bytes(*body_sized),
+    })
+}
+
+fn parse_close_user_data_args(_body: &[u8]) -> Result<CloseUserDataArgs, ProgramError> {
+    Ok(CloseUserDataArgs {})
+}
+
+fn parse_deactivate_args(_body: &[u8]) -> Result<DeactivateArgs, ProgramError> {
+    Ok(DeactivateArgs {})
+}
+
+fn parse_set_usd_price_args(body: &[u8]) -> Result<SetUsdPriceArgs, ProgramError> {
+    let body_sized: &[u8; 48] = body
+        .try_into()
+        .or(Err(AirdropError::BadInstructionArgument))?;
+
+    let (price_usd_cents_array, pyth_price_account_array, max_price_age_slots_array) =
+        array_refs!(body_sized, 8, 32, 8);
+
+    Ok(SetUsdPriceArgs {
+        price_usd_cents: u64::from_le_bytes(*price_usd_cents_array),
+        pyth_price_account: Pubkey::new_from_array(*pyth_price_account_array),
+        max_price_age_slots: u64::from_le_bytes(*max_price_age_slots_array),
+    })
+}
+
+fn parse_finalize_args(body: &[u8]) -> Result<FinalizeArgs, ProgramError> {
+    let lock = match body {
+        [] | [0] => false,
+        [1] => true,
+        _ => return Err(AirdropError::BadInstructionArgument.into()),
+    };
+
+    Ok(FinalizeArgs { lock })
+}
+
+fn parse_set_user_blocked_args(body: &[u8]) -> Result<SetUserBlockedArgs, ProgramError> {
+    let blocked = match body {
+        [0] => false,
+        [1] => true,
+        _ => return Err(AirdropError::BadInstructionArgument.into()),
+    };
+
+    Ok(SetUserBlockedArgs { blocked })
+}
+
+fn parse_deregister_args(_body: &[u8]) -> Result<DeregisterArgs, ProgramError> {
+    Ok(DeregisterArgs {})
+}
+
+fn parse_recompute_bumps_args(_body: &[u8]) -> Result<RecomputeBumpsArgs, ProgramError> {
+    Ok(RecomputeBumpsArgs {})
+}
+
+fn parse_claim_preminted_args(body: &[u8]) -> Result<ClaimPremintedArgs, ProgramError> {
+    let nodes = body.chunks_exact(32);
+
+    if !nodes.remainder().is_empty() {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    Ok(ClaimPremintedArgs {
+        proof: nodes.map(|node| *array_ref![node, 0, 32]).collect(),
+    })
 }
 
 pub fn deserialize_instruction_data(
@@ -101,12 +970,78 @@ pub fn deserialize_instruction_data(
 
     match id {
         1 => Ok(AirdropInstruction::InitializeAirdrop(
-            parse_initialize_airdrop_args(body)?,
+            parse_initialize_airdrop_fixed_args(body)?,
         )),
         2 => Ok(AirdropInstruction::InitializeAirdropUser(
             parse_initialize_airdrop_user_args(body)?,
         )),
         3 => Ok(AirdropInstruction::MintOne(parse_mint_one_args(body)?)),
+        4 => Ok(AirdropInstruction::Reveal(parse_reveal_args(body)?)),
+        5 => Ok(AirdropInstruction::CanMint(parse_can_mint_args(body)?)),
+        6 => Ok(AirdropInstruction::IncreaseSupply(
+            parse_increase_supply_args(body)?,
+        )),
+        7 => Ok(AirdropInstruction::PreviewMint(parse_preview_mint_args(
+            body,
+        )?)),
+        8 => Ok(AirdropInstruction::BatchInitUsers(
+            parse_batch_init_users_args(body)?,
+        )),
+        9 => Ok(AirdropInstruction::UpdateWindow(parse_update_window_args(
+            body,
+        )?)),
+        10 => Ok(AirdropInstruction::InitializeAirdrop(
+            parse_initialize_airdrop_args(body)?,
+        )),
+        11 => Ok(AirdropInstruction::SweepMintAuthority(
+            parse_sweep_mint_authority_args(body)?,
+        )),
+        12 => Ok(AirdropInstruction::AddStage(parse_add_stage_args(body)?)),
+        13 => Ok(AirdropInstruction::RemoveStage(parse_remove_stage_args(
+            body,
+        )?)),
+        14 => Ok(AirdropInstruction::SweepUserAccount(
+            parse_sweep_user_account_args(body)?,
+        )),
+        15 => Ok(AirdropInstruction::MintToEscrow(parse_mint_to_escrow_args(
+            body,
+        )?)),
+        16 => Ok(AirdropInstruction::ClaimFromEscrow(
+            parse_claim_from_escrow_args(body)?,
+        )),
+        17 => Ok(AirdropInstruction::SweepEscrow(parse_sweep_escrow_args(
+            body,
+        )?)),
+        18 => Ok(AirdropInstruction::DepositPreminted(
+            parse_deposit_preminted_args(body)?,
+        )),
+        19 => Ok(AirdropInstruction::ClaimPreminted(
+            parse_claim_preminted_args(body)?,
+        )),
+        20 => Ok(AirdropInstruction::WithdrawUnclaimed(
+            parse_withdraw_unclaimed_args(body)?,
+        )),
+        21 => Ok(AirdropInstruction::UpdateRevenueWallet(
+            parse_update_revenue_wallet_args(body)?,
+        )),
+        22 => Ok(AirdropInstruction::DrawRaffle(parse_draw_raffle_args(
+            body,
+        )?)),
+        23 => Ok(AirdropInstruction::CloseUserData(
+            parse_close_user_data_args(body)?,
+        )),
+        24 => Ok(AirdropInstruction::Deactivate(parse_deactivate_args(body)?)),
+        25 => Ok(AirdropInstruction::SetUsdPrice(parse_set_usd_price_args(
+            body,
+        )?)),
+        26 => Ok(AirdropInstruction::Finalize(parse_finalize_args(body)?)),
+        27 => Ok(AirdropInstruction::SetUserBlocked(
+            parse_set_user_blocked_args(body)?,
+        )),
+        28 => Ok(AirdropInstruction::Deregister(parse_deregister_args(body)?)),
+        29 => Ok(AirdropInstruction::RecomputeBumps(
+            parse_recompute_bumps_args(body)?,
+        )),
         _ => Err(AirdropError::BadInstructionId.into()),
     }
 }
@@ -1,7 +1,21 @@
+/// Informational logging. Compiled only with `verbose-logs` feature,
+/// as every log call costs compute units
+macro_rules! debug_msg {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "verbose-logs")]
+        solana_program::msg!($($arg)*);
+    };
+}
+
+#[cfg(feature = "client")]
+pub mod client;
 pub mod error;
+pub mod event;
 pub mod instruction;
+pub mod naming;
 pub mod pda;
 pub mod processor;
+pub mod return_data;
 pub mod state;
 pub mod util;
 
@@ -0,0 +1,37 @@
+//! Metadata name, symbol and URI of minted tokens, shared by program and clients
+
+use solana_program::program_error::ProgramError;
+
+use crate::{error::AirdropError, state::MAX_URI_LENGTH};
+
+/// Symbol up to first NUL
+pub fn build_symbol(symbol: &[u8; 8]) -> String {
+    let end = symbol.iter().position(|&c| c == 0).unwrap_or(symbol.len());
+    String::from_utf8_lossy(&symbol[..end]).into_owned()
+}
+
+/// Builds `<symbol> #<index>`
+pub fn build_mint_name(symbol: &[u8; 8], index: u64) -> String {
+    format!("{} #{}", build_symbol(symbol), index)
+}
+
+/// Builds `<symbol> #<index> T<trait>` for drops deriving trait of every token
+pub fn build_trait_mint_name(symbol: &[u8; 8], index: u64, trait_id: u8) -> String {
+    format!("{} T{}", build_mint_name(symbol, index), trait_id)
+}
+
+/// Builds `<base>/<index>.json`, base URI may already end with slash
+pub fn revealed_uri(base_uri: &[u8], index: u64) -> Result<String, ProgramError> {
+    let base_uri = ::std::str::from_utf8(base_uri).or(Err(ProgramError::InvalidAccountData))?;
+    let separator = match base_uri.ends_with('/') {
+        true => "",
+        false => "/",
+    };
+
+    let uri = format!("{}{}{}.json", base_uri, separator, index);
+    if uri.len() > MAX_URI_LENGTH {
+        return Err(AirdropError::UriTooLong.into());
+    }
+
+    Ok(uri)
+}
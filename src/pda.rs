@@ -1,17 +1,166 @@
-use solana_program::pubkey::Pubkey;
+use solana_program::pubkey::{Pubkey, PubkeyError};
 
-use crate::state::{MINT_AUTHORITY, USER_DATA};
+use crate::state::{AIRDROP_CONFIG, ESCROW, MINT_AUTHORITY, STAGE, USER_DATA, VAULT};
+
+pub const AIRDROP_CONFIG_SEED: &[u8] = AIRDROP_CONFIG.as_bytes();
+pub const USER_DATA_SEED: &[u8] = USER_DATA.as_bytes();
+pub const MINT_AUTHORITY_SEED: &[u8] = MINT_AUTHORITY.as_bytes();
+pub const STAGE_SEED: &[u8] = STAGE.as_bytes();
+pub const ESCROW_SEED: &[u8] = ESCROW.as_bytes();
+pub const VAULT_SEED: &[u8] = VAULT.as_bytes();
+
+pub fn airdrop_config_seeds<'a>(
+    airdrop_authority: &'a Pubkey,
+    name: &'a [u8; 16],
+    bump: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [AIRDROP_CONFIG_SEED, airdrop_authority.as_ref(), name, bump]
+}
+
+pub fn airdrop_user_data_seeds<'a>(
+    airdrop_config: &'a Pubkey,
+    user: &'a Pubkey,
+    bump: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [USER_DATA_SEED, airdrop_config.as_ref(), user.as_ref(), bump]
+}
+
+pub fn mint_authority_seeds<'a>(airdrop_config: &'a Pubkey, bump: &'a [u8]) -> [&'a [u8]; 3] {
+    [MINT_AUTHORITY_SEED, airdrop_config.as_ref(), bump]
+}
+
+pub fn stage_seeds<'a>(
+    airdrop_config: &'a Pubkey,
+    stage_index: &'a [u8],
+    bump: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [STAGE_SEED, airdrop_config.as_ref(), stage_index, bump]
+}
+
+pub fn escrow_seeds<'a>(
+    airdrop_config: &'a Pubkey,
+    purchase_index: &'a [u8],
+    bump: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [ESCROW_SEED, airdrop_config.as_ref(), purchase_index, bump]
+}
+
+pub fn vault_seeds<'a>(airdrop_config: &'a Pubkey, bump: &'a [u8]) -> [&'a [u8]; 3] {
+    [VAULT_SEED, airdrop_config.as_ref(), bump]
+}
+
+pub fn find_airdrop_config(airdrop_authority: &Pubkey, name: &[u8; 16]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &airdrop_config_seeds(airdrop_authority, name, &[])[..3],
+        &crate::id(),
+    )
+}
+
+pub fn create_airdrop_config(
+    airdrop_authority: &Pubkey,
+    name: &[u8; 16],
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &airdrop_config_seeds(airdrop_authority, name, &[bump]),
+        &crate::id(),
+    )
+}
 
 pub fn find_airdrop_user_data(airdrop_config: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
-        &[USER_DATA.as_bytes(), airdrop_config.as_ref(), user.as_ref()],
+        &airdrop_user_data_seeds(airdrop_config, user, &[])[..3],
+        &crate::id(),
+    )
+}
+
+pub fn create_airdrop_user_data(
+    airdrop_config: &Pubkey,
+    user: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &airdrop_user_data_seeds(airdrop_config, user, &[bump]),
         &crate::id(),
     )
 }
 
 pub fn find_mint_authority(airdrop_config: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
-        &[MINT_AUTHORITY.as_bytes(), airdrop_config.as_ref()],
+        &mint_authority_seeds(airdrop_config, &[])[..2],
+        &crate::id(),
+    )
+}
+
+pub fn find_metadata(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            metaplex_token_metadata::state::PREFIX.as_bytes(),
+            metaplex_token_metadata::id().as_ref(),
+            mint.as_ref(),
+        ],
+        &metaplex_token_metadata::id(),
+    )
+    .0
+}
+
+pub fn find_master_edition(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            metaplex_token_metadata::state::PREFIX.as_bytes(),
+            metaplex_token_metadata::id().as_ref(),
+            mint.as_ref(),
+            metaplex_token_metadata::state::EDITION.as_bytes(),
+        ],
+        &metaplex_token_metadata::id(),
+    )
+    .0
+}
+
+pub fn create_mint_authority(airdrop_config: &Pubkey, bump: u8) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(&mint_authority_seeds(airdrop_config, &[bump]), &crate::id())
+}
+
+pub fn find_stage(airdrop_config: &Pubkey, stage_index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &stage_seeds(airdrop_config, &[stage_index], &[])[..3],
         &crate::id(),
     )
 }
+
+pub fn create_stage(
+    airdrop_config: &Pubkey,
+    stage_index: u8,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &stage_seeds(airdrop_config, &[stage_index], &[bump]),
+        &crate::id(),
+    )
+}
+
+pub fn find_escrow(airdrop_config: &Pubkey, purchase_index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &escrow_seeds(airdrop_config, &purchase_index.to_le_bytes(), &[])[..3],
+        &crate::id(),
+    )
+}
+
+pub fn create_escrow(
+    airdrop_config: &Pubkey,
+    purchase_index: u64,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &escrow_seeds(airdrop_config, &purchase_index.to_le_bytes(), &[bump]),
+        &crate::id(),
+    )
+}
+
+pub fn find_vault(airdrop_config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&vault_seeds(airdrop_config, &[])[..2], &crate::id())
+}
+
+pub fn create_vault(airdrop_config: &Pubkey, bump: u8) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(&vault_seeds(airdrop_config, &[bump]), &crate::id())
+}
@@ -1,24 +1,53 @@
+use metaplex_token_metadata::state::Metadata;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
     entrypoint::ProgramResult,
+    instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
     msg,
     program_error::ProgramError,
-    program_pack::IsInitialized,
+    program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
     rent::Rent,
     system_program,
-    sysvar::Sysvar,
+    sysvar::{
+        self,
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+        Sysvar,
+    },
 };
 
 use crate::{
     error::AirdropError,
-    instruction::deserialize_instruction_data,
-    pda::{find_airdrop_user_data, find_mint_authority},
-    state::{AirdropConfig, AirdropUserData},
+    instruction::{
+        deserialize_instruction_data, AddStageArgs, AirdropInstruction, MintOneArgs,
+        SetUsdPriceArgs,
+    },
+    pda::{
+        create_airdrop_user_data, create_escrow, create_mint_authority, create_stage, create_vault,
+        find_airdrop_config, find_airdrop_user_data, find_escrow, find_master_edition,
+        find_metadata, find_mint_authority, find_stage, find_vault,
+    },
+    state::{
+        AirdropConfig, AirdropEscrow, AirdropStage, AirdropUserData, MintAuthority, PremintVault,
+        RevenueSplit, MAX_AIRDROP_AMOUNT, MAX_DECIMALS, MAX_PRICE, MAX_REVENUE_SPLITS,
+        MAX_URI_LENGTH, MIN_PRICE, PLATFORM_FEE_LAMPORTS, PLATFORM_WALLET, PROTOCOL_FEE_BPS,
+        PROTOCOL_TREASURY, TOKEN_METADATA_PROGRAM_ID, USER_SWEEP_GRACE_SECS,
+    },
     util::{
-        process_airdrop_one_logic, process_initialize_airdrop_logic,
-        process_initialize_airdrop_user_account_logic,
+        allowlist_leaf, fair_mint_limit, is_raffle_winner, mint_rent_lamports,
+        process_add_stage_logic, process_airdrop_one_logic, process_bot_tax_logic,
+        process_claim_from_escrow_logic, process_claim_preminted_logic,
+        process_create_escrow_logic, process_deactivate_logic, process_deposit_preminted_logic,
+        process_draw_raffle_logic, process_finalize_logic, process_increase_supply_logic,
+        process_initialize_airdrop_logic, process_initialize_airdrop_user_account_logic,
+        process_platform_fee_logic, process_preview_mint_logic, process_recompute_bumps_logic,
+        process_registration_fee_logic, process_remove_stage_logic, process_reveal_logic,
+        process_set_usd_price_logic, process_set_user_blocked_logic, process_sweep_escrow_logic,
+        process_sweep_mint_authority_logic, process_sweep_user_account_logic,
+        process_update_revenue_wallet_logic, process_update_window_logic,
+        process_withdraw_unclaimed_logic, read_pyth_price, set_mint_return_data,
+        str_from_u8_nul_utf8, usd_cents_to_lamports, verify_merkle_proof,
     },
 };
 
@@ -33,27 +62,143 @@ pub fn process_instruction<'a>(
                 program_id,
                 accounts,
                 args.airdrop_amount,
-                args.metadata_prefix,
+                args.base_uri,
                 args.symbol,
                 args.price,
+                args.placeholder_uri,
+                args.auto_lock,
+                args.revenue_splits,
+                args.charge_payer,
+                args.max_mints_per_tx,
+                args.name,
+                args.trait_count,
+                args.mint_cooldown_secs,
+                args.referral_bps,
+                args.bot_tax_lamports,
+                args.expected_decimals,
+                args.escrow_timeout_secs,
+                args.mint_quantity,
+                args.registration_fee,
+                args.fairness_bps,
+                args.raffle,
+                args.require_registration,
+                args.deny_cpi_mint,
+                args.min_payer_balance,
             )
         }
-        crate::instruction::AirdropInstruction::InitializeAirdropUser(_) => {
-            process_initialize_airdrop_user(program_id, accounts)
+        crate::instruction::AirdropInstruction::InitializeAirdropUser(args) => {
+            process_initialize_airdrop_user(program_id, accounts, args.idempotent)
+        }
+        crate::instruction::AirdropInstruction::MintOne(args) => {
+            process_mint_one(program_id, accounts, args, None)
+        }
+        crate::instruction::AirdropInstruction::Reveal(_) => process_reveal(program_id, accounts),
+        crate::instruction::AirdropInstruction::CanMint(_) => {
+            process_can_mint(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::IncreaseSupply(args) => {
+            process_increase_supply(program_id, accounts, args.additional)
+        }
+        crate::instruction::AirdropInstruction::PreviewMint(args) => {
+            process_preview_mint(program_id, accounts, args.proof)
+        }
+        crate::instruction::AirdropInstruction::BatchInitUsers(_) => {
+            process_batch_init_users(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::UpdateWindow(args) => {
+            process_update_window(program_id, accounts, args.start_ts, args.end_ts)
+        }
+        crate::instruction::AirdropInstruction::SweepMintAuthority(_) => {
+            process_sweep_mint_authority(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::AddStage(args) => {
+            process_add_stage(program_id, accounts, args)
+        }
+        crate::instruction::AirdropInstruction::RemoveStage(_) => {
+            process_remove_stage(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::SweepUserAccount(_) => {
+            process_sweep_user_account(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::MintToEscrow(args) => {
+            let mint_one_args = MintOneArgs {
+                proof: args.proof,
+                gift: false,
+            };
+            process_mint_one(program_id, accounts, mint_one_args, Some(args.claimant))
+        }
+        crate::instruction::AirdropInstruction::ClaimFromEscrow(_) => {
+            process_claim_from_escrow(program_id, accounts)
         }
-        crate::instruction::AirdropInstruction::MintOne(_) => {
-            process_mint_one(program_id, accounts)
+        crate::instruction::AirdropInstruction::SweepEscrow(_) => {
+            process_sweep_escrow(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::DepositPreminted(_) => {
+            process_deposit_preminted(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::ClaimPreminted(args) => {
+            process_claim_preminted(program_id, accounts, args.proof)
+        }
+        crate::instruction::AirdropInstruction::WithdrawUnclaimed(_) => {
+            process_withdraw_unclaimed(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::UpdateRevenueWallet(args) => {
+            process_update_revenue_wallet(program_id, accounts, args.revenue_wallet)
+        }
+        crate::instruction::AirdropInstruction::DrawRaffle(args) => {
+            process_draw_raffle(program_id, accounts, args.winners_count)
+        }
+        crate::instruction::AirdropInstruction::CloseUserData(_) => {
+            process_close_user_data(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::Deactivate(_) => {
+            process_deactivate(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::SetUsdPrice(args) => {
+            process_set_usd_price(program_id, accounts, args)
+        }
+        crate::instruction::AirdropInstruction::Finalize(args) => {
+            process_finalize(program_id, accounts, args.lock)
+        }
+        crate::instruction::AirdropInstruction::SetUserBlocked(args) => {
+            process_set_user_blocked(program_id, accounts, args.blocked)
+        }
+        crate::instruction::AirdropInstruction::Deregister(_) => {
+            process_deregister(program_id, accounts)
+        }
+        crate::instruction::AirdropInstruction::RecomputeBumps(_) => {
+            process_recompute_bumps(program_id, accounts)
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_initialize_airdrop<'a>(
     program_id: &Pubkey,
     accounts: &'a [AccountInfo<'a>],
     airdrop_amount: u64,
-    metadata_prefix: [u8; 32],
+    base_uri: Vec<u8>,
     symbol: [u8; 8],
     price: u64,
+    placeholder_uri: [u8; 32],
+    auto_lock: bool,
+    revenue_splits: [RevenueSplit; MAX_REVENUE_SPLITS],
+    charge_payer: bool,
+    max_mints_per_tx: u8,
+    name: [u8; 16],
+    trait_count: u8,
+    mint_cooldown_secs: u64,
+    referral_bps: u16,
+    bot_tax_lamports: u64,
+    expected_decimals: u8,
+    escrow_timeout_secs: u64,
+    mint_quantity: u64,
+    registration_fee: u64,
+    fairness_bps: u16,
+    raffle: bool,
+    require_registration: bool,
+    deny_cpi_mint: bool,
+    min_payer_balance: u64,
 ) -> ProgramResult {
     let iter = &mut accounts.iter();
     let airdrop_account = next_account_info(iter)?;
@@ -63,38 +208,181 @@ fn process_initialize_airdrop<'a>(
     let admin_account = next_account_info(iter)?;
     let rent = next_account_info(iter)?;
     let fee_payer = next_account_info(iter)?;
-    let _system_program = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+
+    // Args checks
+    debug_msg!("Assert airdrop amount is not zero and below sanity ceiling");
+    if airdrop_amount == 0 || airdrop_amount > MAX_AIRDROP_AMOUNT {
+        return Err(AirdropError::InvalidSupply.into());
+    }
+
+    debug_msg!("Assert base URI and symbol are not empty");
+    if base_uri.is_empty() || symbol[0] == 0 {
+        return Err(AirdropError::InvalidMetadataParams.into());
+    }
+
+    debug_msg!("Assert symbol is ASCII alphanumeric");
+    match str_from_u8_nul_utf8(&symbol) {
+        Some(symbol) if symbol.bytes().all(|c| c.is_ascii_alphanumeric()) => {}
+        _ => return Err(AirdropError::InvalidSymbol.into()),
+    }
+
+    debug_msg!("Assert base URI fits token metadata");
+    if base_uri.len() > MAX_URI_LENGTH {
+        return Err(AirdropError::UriTooLong.into());
+    }
+
+    debug_msg!("Assert base URI is UTF-8 without NUL");
+    if std::str::from_utf8(&base_uri).is_err() || base_uri.contains(&0) {
+        return Err(AirdropError::InvalidMetadataPrefix.into());
+    }
+
+    debug_msg!("Assert price is below sanity ceiling");
+    if cfg!(not(feature = "no-price-ceiling")) && price > MAX_PRICE {
+        return Err(AirdropError::InvalidPrice.into());
+    }
+
+    debug_msg!("Assert price of the largest batch fits in u64");
+    if price.checked_mul(u8::MAX as u64).is_none() {
+        return Err(AirdropError::InvalidPrice.into());
+    }
+
+    debug_msg!("Assert paid price is above sanity floor");
+    assert_price_floor(price)?;
+
+    debug_msg!("Assert revenue splits are valid");
+    assert_revenue_splits(&revenue_splits)?;
+
+    debug_msg!("Assert referral cut is at most the whole price");
+    if referral_bps > 10000 {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    debug_msg!("Assert fairness share is at most the whole supply");
+    if fairness_bps > 10000 {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    debug_msg!("Assert token decimals are supported");
+    if expected_decimals > MAX_DECIMALS {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    debug_msg!("Assert every mint gives tokens");
+    if mint_quantity == 0 {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    // Raffle entrants and registration fee payers are registered users
+    debug_msg!("Assert open mint airdrop does not depend on registration");
+    if !require_registration && (raffle || registration_fee > 0) {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
 
     // Airdrop account checks
-    msg!("Assert airdrop config writeable");
+    debug_msg!("Assert airdrop config writeable");
     assert_writeable(airdrop_account)?;
-    msg!("Assert airdrop config owned by program");
-    assert_owned_by(airdrop_account, program_id)?;
 
-    // Airdrop authority checks
+    let (airdrop_config_pda, airdrop_config_bump) =
+        find_airdrop_config(airdrop_authority.key, &name);
+
+    // Airdrop config derived from authority and name is created by program.
+    // Any other account must be created and assigned to program beforehand
+    let airdrop_config_bump = if airdrop_config_pda == *airdrop_account.key {
+        debug_msg!("Assert airdrop authority is signer");
+        assert_signer(airdrop_authority)?;
+
+        // Lamports alone don't mean config exists, anyone can send them to PDA address
+        debug_msg!("Assert airdrop config is not created yet");
+        if airdrop_account.owner == program_id
+            && AirdropConfig::unpack_from_account(airdrop_account)?.is_initialized()
+        {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Some(airdrop_config_bump)
+    } else {
+        debug_msg!("Assert airdrop config owned by program");
+        assert_owned_by(airdrop_account, program_id)?;
+
+        None
+    };
 
     // Mint authority checks
     let (mint_authority_pda, mint_authority_bump) = find_mint_authority(airdrop_account.key);
 
-    msg!("Assert mint authority is PDA");
-    if mint_authority_pda != *mint_authority.key {
-        return Err(AirdropError::PdaCheckFailed.into());
-    }
+    debug_msg!("Assert mint authority is PDA");
+    assert_pda(
+        mint_authority,
+        &mint_authority_pda,
+        AirdropError::InvalidMintAuthorityPda,
+    )?;
 
-    msg!("Assert mint authority is writeable");
+    debug_msg!("Assert mint authority is writeable");
     assert_writeable(mint_authority)?;
 
-    // Revenues account checks
+    // Revenues account checks. Program addresses are off curve, so none of them,
+    // including addresses this program derives for its own accounts, can receive revenues
+    debug_msg!("Assert revenue wallet is a regular wallet");
+    if *revenues_account.owner != system_program::id()
+        || !revenues_account.key.is_on_curve()
+        || revenues_account.key == airdrop_account.key
+        || revenues_account.key == mint_authority.key
+        || revenues_account.key == fee_payer.key
+    {
+        return Err(AirdropError::InvalidRevenueWallet.into());
+    }
 
     // Fee payer checks
-    msg!("Assert fee payer is signer");
+    debug_msg!("Assert fee payer is signer");
     assert_signer(fee_payer)?;
 
+    // Program checks
+    debug_msg!("Assert system program is correct one");
+    assert_program_id(system_program, &system_program::id())?;
+
+    // Platform fee checks. Platform wallet is passed only when build charges the fee
+    let platform_wallet = match PLATFORM_FEE_LAMPORTS {
+        0 => None,
+        _ => {
+            let platform_wallet = next_account_info(iter)?;
+
+            debug_msg!("Assert that platform wallet is correct one");
+            if *platform_wallet.key != PLATFORM_WALLET {
+                return Err(AirdropError::WrongAccountAddress.into());
+            }
+            debug_msg!("Assert platform wallet is writeable");
+            assert_writeable(platform_wallet)?;
+            debug_msg!("Assert fee payer is writeable");
+            assert_writeable(fee_payer)?;
+
+            Some(platform_wallet)
+        }
+    };
+
     // ----------------
 
-    msg!("Get rent info from account");
+    debug_msg!("Get rent info from account");
     let rent = Rent::from_account_info(rent)?;
 
+    debug_msg!("Assert fee payer can afford created accounts and platform fee");
+    let config_rent = match airdrop_config_bump {
+        Some(_) => rent
+            .minimum_balance(AirdropConfig::LEN)
+            .saturating_sub(airdrop_account.lamports()),
+        None => 0,
+    };
+    let mint_authority_rent = rent
+        .minimum_balance(MintAuthority::LEN)
+        .saturating_sub(mint_authority.lamports());
+    if fee_payer.lamports() < config_rent + mint_authority_rent + PLATFORM_FEE_LAMPORTS {
+        return Err(AirdropError::InsufficientFunds.into());
+    }
+
+    if let Some(platform_wallet) = platform_wallet {
+        process_platform_fee_logic(fee_payer, platform_wallet)?;
+    }
+
     process_initialize_airdrop_logic(
         airdrop_account,
         airdrop_authority,
@@ -103,9 +391,29 @@ fn process_initialize_airdrop<'a>(
         admin_account,
         fee_payer,
         airdrop_amount,
-        metadata_prefix,
+        &base_uri,
         symbol,
         price,
+        placeholder_uri,
+        auto_lock,
+        revenue_splits,
+        charge_payer,
+        max_mints_per_tx,
+        name,
+        airdrop_config_bump,
+        trait_count,
+        mint_cooldown_secs,
+        referral_bps,
+        bot_tax_lamports,
+        expected_decimals,
+        escrow_timeout_secs,
+        mint_quantity,
+        registration_fee,
+        fairness_bps,
+        raffle,
+        require_registration,
+        deny_cpi_mint,
+        min_payer_balance,
         program_id,
         rent,
         mint_authority_bump,
@@ -117,6 +425,7 @@ fn process_initialize_airdrop<'a>(
 fn process_initialize_airdrop_user<'a>(
     program_id: &Pubkey,
     accounts: &'a [AccountInfo<'a>],
+    idempotent: bool,
 ) -> ProgramResult {
     let iter = &mut accounts.iter();
     let user_data_account = next_account_info(iter)?;
@@ -124,50 +433,107 @@ fn process_initialize_airdrop_user<'a>(
     let airdrop = next_account_info(iter)?;
     let rent = next_account_info(iter)?;
     let fee_payer = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
 
     // User data account checks
-    msg!("Assert user data is properly derived");
+    debug_msg!("Assert user data is properly derived");
     let (user_data_account_pda, user_data_account_bump) =
         find_airdrop_user_data(airdrop.key, user.key);
 
-    if user_data_account_pda != *user_data_account.key {
-        return Err(AirdropError::PdaCheckFailed.into());
-    }
-
-    msg!("Assert user data is not initialized");
-    if user_data_account.lamports() > 0 {
-        return Err(ProgramError::AccountAlreadyInitialized);
-    }
+    assert_pda(
+        user_data_account,
+        &user_data_account_pda,
+        AirdropError::InvalidUserDataPda,
+    )?;
 
-    msg!("Assert user data account is writeable");
+    debug_msg!("Assert user data account is writeable");
     assert_writeable(user_data_account)?;
 
     // User checks
-    // msg!("Assert that user is regular wallet");
+    // debug_msg!("Assert that user is regular wallet");
     // assert_owned_by(user, &system_program::id())?;
 
     // Airdrop config checks
-    msg!("Assert that airdrop config is owned by program");
+    debug_msg!("Assert that airdrop config is owned by program");
     assert_owned_by(airdrop, program_id)?;
-    msg!("Assert that airdrop config is writeable");
+    debug_msg!("Assert that airdrop config is writeable");
     assert_writeable(airdrop)?;
 
-    msg!("Assert airdrop config is initialized");
+    debug_msg!("Assert airdrop config is initialized");
     let airdrop_data = AirdropConfig::unpack_from_account(airdrop)?;
 
     if !airdrop_data.is_initialized() {
         return Err(AirdropError::Uninitialized.into());
     }
 
+    debug_msg!("Assert user is not registered");
+    let already_registered = is_user_registered(user_data_account, &airdrop_data, program_id)?;
+    if already_registered && !idempotent {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    if already_registered {
+        let user_data = AirdropUserData::unpack_from_account(user_data_account)?;
+
+        debug_msg!("Assert existing user data belongs to user and airdrop");
+        if user_data.user != *user.key || user_data.airdrop != *airdrop.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        debug_msg!("User data already exists, skip");
+        return Ok(());
+    }
+
+    debug_msg!("Assert registration is open");
+    assert_not_finalized(&airdrop_data)?;
+    assert_registration_open(&airdrop_data)?;
+
     // Fee payer checks
-    msg!("Assert that fee payer is signer");
+    debug_msg!("Assert that fee payer is signer");
     assert_signer(fee_payer)?;
 
+    // Program checks
+    debug_msg!("Assert system program is correct one");
+    assert_program_id(system_program, &system_program::id())?;
+
+    // Registration fee checks. Revenue wallet is passed only when airdrop charges the fee
+    let revenue_wallet = match airdrop_data.registration_fee {
+        0 => None,
+        _ => {
+            let revenue_wallet = next_account_info(iter)?;
+
+            debug_msg!("Assert that revenue wallet is correct one");
+            if airdrop_data.revenues_wallet != *revenue_wallet.key {
+                return Err(AirdropError::WrongAccountAddress.into());
+            }
+            debug_msg!("Assert revenue wallet is writeable");
+            assert_writeable(revenue_wallet)?;
+            debug_msg!("Assert fee payer is writeable");
+            assert_writeable(fee_payer)?;
+
+            Some(revenue_wallet)
+        }
+    };
+
     // ----------------
 
-    msg!("Get rent");
+    debug_msg!("Get rent");
     let rent = Rent::from_account_info(rent)?;
 
+    debug_msg!("Assert fee payer can afford registration fee and rent");
+    let required_lamports = rent
+        .minimum_balance(AirdropUserData::LEN)
+        .saturating_sub(user_data_account.lamports())
+        .checked_add(airdrop_data.registration_fee)
+        .ok_or(AirdropError::Overflow)?;
+    if fee_payer.lamports() < required_lamports {
+        return Err(AirdropError::InsufficientFunds.into());
+    }
+
+    if let Some(revenue_wallet) = revenue_wallet {
+        process_registration_fee_logic(fee_payer, revenue_wallet, airdrop_data.registration_fee)?;
+    }
+
     process_initialize_airdrop_user_account_logic(
         user_data_account,
         user,
@@ -181,7 +547,189 @@ fn process_initialize_airdrop_user<'a>(
     Ok(())
 }
 
-fn process_mint_one<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) -> ProgramResult {
+fn process_batch_init_users<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let rent = next_account_info(iter)?;
+    let fee_payer = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+    let recipients = iter.as_slice();
+
+    // Airdrop config checks
+    debug_msg!("Assert that airdrop config is owned by program");
+    assert_owned_by(airdrop, program_id)?;
+    debug_msg!("Assert that airdrop config is writeable");
+    assert_writeable(airdrop)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop)?;
+
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    debug_msg!("Assert registration is open");
+    assert_not_finalized(&airdrop_data)?;
+    assert_registration_open(&airdrop_data)?;
+
+    // Fee payer checks
+    debug_msg!("Assert that fee payer is signer");
+    assert_signer(fee_payer)?;
+
+    // Program checks
+    debug_msg!("Assert system program is correct one");
+    assert_program_id(system_program, &system_program::id())?;
+
+    debug_msg!("Assert recipient accounts come in pairs");
+    let pairs = recipients.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    // ----------------
+
+    debug_msg!("Get rent");
+    let rent = Rent::from_account_info(rent)?;
+
+    for pair in pairs {
+        let user_data_account = &pair[0];
+        let user = &pair[1];
+
+        debug_msg!("Assert user data is properly derived");
+        let (user_data_account_pda, user_data_account_bump) =
+            find_airdrop_user_data(airdrop.key, user.key);
+
+        assert_pda(
+            user_data_account,
+            &user_data_account_pda,
+            AirdropError::InvalidUserDataPda,
+        )?;
+
+        if is_user_registered(user_data_account, &airdrop_data, program_id)? {
+            debug_msg!("User data already exists, skip");
+            continue;
+        }
+
+        debug_msg!("Assert user data account is writeable");
+        assert_writeable(user_data_account)?;
+
+        process_initialize_airdrop_user_account_logic(
+            user_data_account,
+            user,
+            airdrop,
+            fee_payer,
+            rent,
+            program_id,
+            user_data_account_bump,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Registers user inside MintOne when user data does not exist yet or is stale, so first
+/// time minter does not need separate registration instruction
+#[allow(clippy::too_many_arguments)]
+fn register_user_on_mint<'a>(
+    program_id: &Pubkey,
+    airdrop_config: &'a AccountInfo<'a>,
+    airdrop_data: &AirdropConfig,
+    user_data_account: &'a AccountInfo<'a>,
+    user: &'a AccountInfo<'a>,
+    payer: &'a AccountInfo<'a>,
+    revenue_wallet: &'a AccountInfo<'a>,
+    rent: &'a AccountInfo<'a>,
+    system_program: &'a AccountInfo<'a>,
+) -> ProgramResult {
+    if !airdrop_data.require_registration {
+        return Ok(());
+    }
+
+    // Registered user data is checked by the mint itself. Stale one is registered again
+    if is_user_registered(user_data_account, airdrop_data, program_id)? {
+        return Ok(());
+    }
+
+    // User data account checks
+    debug_msg!("Assert user data is properly derived");
+    let (user_data_account_pda, user_data_account_bump) =
+        find_airdrop_user_data(airdrop_config.key, user.key);
+
+    assert_pda(
+        user_data_account,
+        &user_data_account_pda,
+        AirdropError::InvalidUserDataPda,
+    )?;
+
+    debug_msg!("Assert user data account is writeable");
+    assert_writeable(user_data_account)?;
+
+    debug_msg!("Assert registration is open");
+    assert_registration_open(airdrop_data)?;
+
+    // Payer checks
+    debug_msg!("Assert payer is signer");
+    assert_signer(payer)?;
+    debug_msg!("Assert payer is writeable");
+    assert_writeable(payer)?;
+
+    // Program checks
+    debug_msg!("Assert system program is correct one");
+    assert_program_id(system_program, &system_program::id())?;
+
+    // Registration fee checks
+    if airdrop_data.registration_fee > 0 {
+        debug_msg!("Assert that revenue wallet is correct one");
+        if airdrop_data.revenues_wallet != *revenue_wallet.key {
+            return Err(AirdropError::WrongAccountAddress.into());
+        }
+        debug_msg!("Assert revenue wallet is writeable");
+        assert_writeable(revenue_wallet)?;
+    }
+
+    // ----------------
+
+    debug_msg!("Get rent");
+    let rent = Rent::from_account_info(rent)?;
+
+    debug_msg!("Assert payer can afford registration fee and rent");
+    let required_lamports = rent
+        .minimum_balance(AirdropUserData::LEN)
+        .checked_add(airdrop_data.registration_fee)
+        .ok_or(AirdropError::Overflow)?;
+    if payer.lamports() < required_lamports {
+        return Err(AirdropError::InsufficientFunds.into());
+    }
+
+    if airdrop_data.registration_fee > 0 {
+        process_registration_fee_logic(payer, revenue_wallet, airdrop_data.registration_fee)?;
+    }
+
+    process_initialize_airdrop_user_account_logic(
+        user_data_account,
+        user,
+        airdrop_config,
+        payer,
+        rent,
+        program_id,
+        user_data_account_bump,
+    )
+}
+
+fn process_mint_one<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: MintOneArgs,
+    escrow_claimant: Option<Pubkey>,
+) -> ProgramResult {
     let iter = &mut accounts.iter();
     let airdrop_config = next_account_info(iter)?;
     let user_data_account = next_account_info(iter)?;
@@ -190,132 +738,2630 @@ fn process_mint_one<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) ->
     let user_token_account = next_account_info(iter)?;
     let token_metadata_account = next_account_info(iter)?;
     let mint_authority = next_account_info(iter)?;
-    let system_program = next_account_info(iter)?; // System program
+    let system_program = next_account_info(iter)?;
     let clock_var = next_account_info(iter)?;
     let rent_var = next_account_info(iter)?;
     let token_program = next_account_info(iter)?; // Token program
     let _ = next_account_info(iter)?; // Associated token program
-    let _ = next_account_info(iter)?; // Token metadata program
+    let token_metadata_program = next_account_info(iter)?;
     let payer = next_account_info(iter)?;
     let airdrop_authority = next_account_info(iter)?;
     let admin_account = next_account_info(iter)?;
     let revenue_wallet = next_account_info(iter)?;
+    let instructions_var = next_account_info(iter)?;
+    let slot_hashes_var = next_account_info(iter)?;
+    let master_edition_account = next_account_info(iter)?;
 
     // Airdrop config checks
-    msg!("Check if airdrop account is writeable");
+    debug_msg!("Check if airdrop account is writeable");
     assert_writeable(airdrop_config)?;
-    msg!("Check if airdrop account is owned by this program");
-    assert_owned_by(airdrop_config, program_id)?;
 
-    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+    let clock = Clock::from_account_info(clock_var)?;
 
-    msg!("Check if airdrop account is initialized");
-    if !airdrop_data.is_initialized() {
-        return Err(AirdropError::Uninitialized.into());
-    }
+    // Payer balance is checked as it was before inline registration takes the rent
+    let payer_balance = payer.lamports();
 
-    msg!("Check supply");
-    if airdrop_data.airdrop_index >= airdrop_data.airdrop_amount {
-        return Err(AirdropError::OutOfSupply.into());
-    }
+    // Config is unpacked once, counters written by registration and the mint itself are
+    // not read from this copy
+    let airdrop_data = assert_airdrop_mint_state(program_id, airdrop_config)?;
 
-    // User data account checks
-    msg!("Assert user data is writeable");
-    assert_writeable(user_data_account)?;
-    msg!("Check if user data is owned by this program");
-    assert_owned_by(user_data_account, program_id)?;
+    // Fresh wallet gets registered by the mint itself, all mint checks below still apply
+    register_user_on_mint(
+        program_id,
+        airdrop_config,
+        &airdrop_data,
+        user_data_account,
+        user,
+        payer,
+        revenue_wallet,
+        rent_var,
+        system_program,
+    )?;
 
-    let user_data = AirdropUserData::unpack_from_account(user_data_account)?;
+    let user_data = assert_open_user_state(
+        program_id,
+        airdrop_config,
+        &airdrop_data,
+        user_data_account,
+        user,
+    )?;
 
-    msg!("Check if user data account is initialized");
-    if !user_data.is_initialized() {
-        return Err(AirdropError::Uninitialized.into());
-    }
+    // User data account checks. Open mint passes a placeholder that is not read
+    let user_data_account = match user_data {
+        Some(_) => {
+            debug_msg!("Assert user data is writeable");
+            assert_writeable(user_data_account)?;
 
-    msg!("Check if airdrop data and user wallet are valid for user data account");
-    if !(user_data.user == *user.key && user_data.airdrop == *airdrop_config.key) {
-        return Err(ProgramError::InvalidAccountData);
-    }
+            Some(user_data_account)
+        }
+        None => None,
+    };
+    let user_data = user_data.unwrap_or_default();
 
-    let clock = Clock::from_account_info(clock_var)?;
+    // Closed window is settled together with other soft eligibility checks
+    debug_msg!("Check mint window");
+    let mint_window = assert_mint_window(&airdrop_data, &clock);
 
-    msg!("Check user timeout");
-    if user_data.locked_till >= clock.unix_timestamp as u64 {
-        return Err(AirdropError::UserTimeout.into());
+    debug_msg!("Check mint is not invoked by other program");
+    if airdrop_data.deny_cpi_mint && get_stack_height() > TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Err(AirdropError::CpiNotAllowed.into());
     }
 
     // Mint account checks
-    msg!("Assert that mint account is signer");
+    debug_msg!("Assert that mint account is signer");
     assert_signer(mint_account)?;
-    msg!("Assert that mint account is writeable");
+    debug_msg!("Assert that mint account is writeable");
     assert_writeable(mint_account)?;
 
-    // User token account checks
-    msg!("Assert token account is writeable");
-    assert_writeable(user_token_account)?;
-
     // Metadata account checks
-    msg!("Assert metadata account is writeable");
+    debug_msg!("Assert metadata account is properly derived");
+    assert_pda(
+        token_metadata_account,
+        &find_metadata(mint_account.key),
+        AirdropError::InvalidMetadataPda,
+    )?;
+
+    debug_msg!("Assert metadata account is writeable");
     assert_writeable(token_metadata_account)?;
 
+    // Master edition account checks
+    debug_msg!("Assert master edition account is properly derived");
+    assert_pda(
+        master_edition_account,
+        &find_master_edition(mint_account.key),
+        AirdropError::InvalidMasterEditionPda,
+    )?;
+
+    debug_msg!("Assert master edition account is writeable");
+    assert_writeable(master_edition_account)?;
+
     // Mint authority checks
-    let (mint_authority_pda, mint_authority_bump) = find_mint_authority(airdrop_config.key);
+    let mint_authority_bump = airdrop_data.mint_authority_bump;
+    let mint_authority_pda = create_mint_authority(airdrop_config.key, mint_authority_bump)
+        .or(Err(AirdropError::InvalidMintAuthorityPda))?;
 
-    msg!("Assert mint authority is properly derived");
-    if mint_authority_pda != *mint_authority.key {
-        return Err(AirdropError::PdaCheckFailed.into());
-    }
+    debug_msg!("Assert mint authority is properly derived");
+    assert_pda(
+        mint_authority,
+        &mint_authority_pda,
+        AirdropError::InvalidMintAuthorityPda,
+    )?;
 
     // Payer checks
-    msg!("Assert payer is signer");
+    debug_msg!("Assert payer is signer");
     assert_signer(payer)?;
-    msg!("Assert payer is writeable");
+    debug_msg!("Assert payer is writeable");
     assert_writeable(payer)?;
-    msg!("Assert payer is owned by system program");
+    debug_msg!("Assert payer is owned by system program");
     assert_owned_by(payer, &system_program::id())?;
+    debug_msg!("Assert payer holds required balance");
+    if payer_balance < airdrop_data.min_payer_balance {
+        return Err(AirdropError::InsufficientStake.into());
+    }
+
+    // User pays the price unless airdrop charges payer
+    if !airdrop_data.charge_payer {
+        debug_msg!("Assert user is signer");
+        if !user.is_signer {
+            return Err(AirdropError::UserSignerRequired.into());
+        }
+
+        debug_msg!("Assert user is writeable");
+        assert_writeable(user)?;
+        debug_msg!("Assert user is owned by system program");
+        assert_owned_by(user, &system_program::id())?;
+    }
 
     // Airdrop authority checks
-    msg!("Assert drop is approved by airdrop authority");
-    assert_signer(airdrop_authority)?;
+    debug_msg!("Assert drop is approved by airdrop authority");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
 
     // Admin account checks
-    msg!("Assert that admin account is correct one");
+    debug_msg!("Assert that admin account is correct one");
     if airdrop_data.admin_account != *admin_account.key {
         return Err(AirdropError::WrongAccountAddress.into());
     }
 
     // Revenue wallet checks
-    msg!("Assert that revenue wallet is correct one");
+    debug_msg!("Assert that revenue wallet is correct one");
     if airdrop_data.revenues_wallet != *revenue_wallet.key {
         return Err(AirdropError::WrongAccountAddress.into());
     }
 
-    msg!("Assert revenue wallet is writeable");
-    assert_writeable(revenue_wallet)?;
+    // Instructions sysvar checks
+    debug_msg!("Assert instructions sysvar is correct one");
+    if !sysvar::instructions::check_id(instructions_var.key) {
+        return Err(AirdropError::WrongAccountAddress.into());
+    }
 
-    // ----------------
+    // Program checks
+    debug_msg!("Assert system program is correct one");
+    assert_program_id(system_program, &system_program::id())?;
+    debug_msg!("Assert token metadata program is correct one");
+    assert_program_id(token_metadata_program, &TOKEN_METADATA_PROGRAM_ID)?;
 
-    let rent = Rent::from_account_info(rent_var)?;
+    // Slot hashes sysvar checks
+    debug_msg!("Assert slot hashes sysvar is correct one");
+    if !sysvar::slot_hashes::check_id(slot_hashes_var.key) {
+        return Err(AirdropError::WrongAccountAddress.into());
+    }
 
-    process_airdrop_one_logic(
-        airdrop_config,
-        user_data_account,
-        mint_account,
-        user,
-        user_token_account,
-        token_metadata_account,
-        mint_authority,
-        rent_var,
-        clock,
-        rent,
-        payer,
-        admin_account,
+    // Revenue split wallets checks
+    let revenue_split_wallets = next_revenue_split_wallets(iter, &airdrop_data)?;
+
+    // Protocol treasury checks. Treasury is passed only by builds taking protocol fee
+    let protocol_treasury = match PROTOCOL_FEE_BPS {
+        0 => None,
+        _ => {
+            let protocol_treasury = next_account_info(iter)?;
+
+            debug_msg!("Assert that protocol treasury is correct one");
+            if *protocol_treasury.key != PROTOCOL_TREASURY {
+                return Err(AirdropError::WrongAccountAddress.into());
+            }
+
+            Some(protocol_treasury)
+        }
+    };
+
+    // Gift recipient or escrow checks
+    let recipient = match args.gift || escrow_claimant.is_some() {
+        true => next_account_info(iter)?,
+        false => user,
+    };
+
+    // Off-curve owners are program addresses, associated token account is not created for them
+    debug_msg!("Assert recipient is a wallet");
+    if args.gift && !recipient.key.is_on_curve() {
+        return Err(AirdropError::InvalidRecipient.into());
+    }
+
+    let escrow = match escrow_claimant {
+        Some(claimant) => {
+            debug_msg!("Assert escrow is derived from minted index");
+            let (escrow_pda, escrow_bump) =
+                find_escrow(airdrop_config.key, airdrop_data.airdrop_index);
+            assert_pda(recipient, &escrow_pda, AirdropError::InvalidEscrowPda)?;
+
+            debug_msg!("Assert escrow is writeable");
+            assert_writeable(recipient)?;
+
+            // Lamports alone don't mean escrow exists, anyone can send them to PDA address
+            debug_msg!("Assert escrow is not created yet");
+            if recipient.owner == program_id || !recipient.data_is_empty() {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+
+            Some(AirdropEscrow {
+                initialized: true,
+                airdrop: *airdrop_config.key,
+                purchase_index: airdrop_data.airdrop_index,
+                bump: escrow_bump,
+                claimant,
+                mint: *mint_account.key,
+                payer: *payer.key,
+                created_ts: clock.unix_timestamp,
+            })
+        }
+        None => None,
+    };
+
+    // User token account checks
+    debug_msg!("Assert token account is recipient's associated token account");
+    assert_pda(
+        user_token_account,
+        &spl_associated_token_account::get_associated_token_address(
+            recipient.key,
+            mint_account.key,
+        ),
+        AirdropError::InvalidTokenAccountPda,
+    )?;
+
+    debug_msg!("Assert token account is writeable");
+    assert_writeable(user_token_account)?;
+
+    // Stage checks
+    let stage = match airdrop_data.stage_count {
+        0 => None,
+        _ => Some(next_account_info(iter)?),
+    };
+
+    // Soft stage failures are settled together with other soft eligibility checks
+    let active_stage = match stage {
+        Some(stage) => Some(defer_soft_failure(assert_active_stage(
+            program_id,
+            airdrop_config,
+            stage,
+            user,
+            &user_data,
+            &clock,
+            &args.proof,
+        ))?),
+        None => None,
+    };
+
+    let price = match &active_stage {
+        Some(Ok(stage)) => stage.price,
+        _ => airdrop_data.price,
+    };
+
+    // USD price replaces lamport prices of airdrop and stages
+    let price = next_usd_price(iter, &airdrop_data, &clock)?.unwrap_or(price);
+
+    // Free mint transfers nothing, so price receivers may be read-only
+    if price > 0 || airdrop_data.bot_tax_lamports > 0 {
+        debug_msg!("Assert revenue wallet is writeable");
+        assert_writeable(revenue_wallet)?;
+    }
+
+    if price > 0 {
+        for split_wallet in revenue_split_wallets.iter() {
+            debug_msg!("Assert revenue split wallet is writeable");
+            assert_writeable(split_wallet)?;
+        }
+
+        if let Some(protocol_treasury) = protocol_treasury {
+            debug_msg!("Assert protocol treasury is writeable");
+            assert_writeable(protocol_treasury)?;
+        }
+    }
+
+    debug_msg!("Assert critical accounts are distinct");
+    let mut critical_accounts = vec![
+        airdrop_config,
+        mint_account,
+        user_token_account,
+        token_metadata_account,
+        mint_authority,
+        revenue_wallet,
+        payer,
+    ];
+
+    if let Some(user_data_account) = user_data_account {
+        critical_accounts.push(user_data_account);
+    }
+
+    // User paying for own mint is the usual case
+    if user.key != payer.key {
+        critical_accounts.push(user);
+    }
+
+    if recipient.key != user.key && recipient.key != payer.key {
+        critical_accounts.push(recipient);
+    }
+
+    if let Some(stage) = stage {
+        critical_accounts.push(stage);
+    }
+
+    assert_distinct(&critical_accounts)?;
+
+    debug_msg!("Assert revenue split wallets are neither payer nor user");
+    if revenue_split_wallets
+        .iter()
+        .any(|wallet| wallet.key == payer.key || wallet.key == user.key)
+    {
+        return Err(AirdropError::DuplicateAccount.into());
+    }
+
+    // Referrer checks
+    let referrer = match airdrop_data.referral_bps {
+        0 => None,
+        _ => iter.next(),
+    };
+
+    if let Some(referrer) = referrer {
+        debug_msg!("Assert referrer is neither payer nor user");
+        if referrer.key == payer.key || referrer.key == user.key {
+            return Err(AirdropError::InvalidReferrer.into());
+        }
+
+        debug_msg!("Assert referrer is a regular wallet");
+        if *referrer.owner != system_program::id() {
+            return Err(AirdropError::InvalidReferrer.into());
+        }
+
+        debug_msg!("Assert referrer is writeable");
+        assert_writeable(referrer)?;
+    }
+
+    // Soft eligibility checks. Airdrop with bot tax charges payer instead of failing
+    let soft_eligibility = mint_window
+        .and(active_stage.map_or(Ok(()), |stage| stage.map(|_| ())))
+        .and_then(|_| {
+            assert_soft_eligibility(
+                program_id,
+                &airdrop_data,
+                &user_data,
+                &clock,
+                instructions_var,
+            )
+        });
+
+    match soft_eligibility {
+        Err(error) if airdrop_data.bot_tax_lamports > 0 && is_soft_failure(&error) => {
+            msg!("BotTaxed");
+            return process_bot_tax_logic(payer, revenue_wallet, airdrop_data.bot_tax_lamports);
+        }
+        result => result?,
+    }
+
+    // ----------------
+
+    let rent = Rent::from_account_info(rent_var)?;
+
+    debug_msg!("Assert payer can afford rent of minted accounts and price it pays");
+    let mut required_lamports =
+        mint_rent_lamports(&rent, &airdrop_data, user_token_account.lamports() == 0)?;
+    if airdrop_data.charge_payer || payer.key == user.key {
+        required_lamports = required_lamports
+            .checked_add(price)
+            .ok_or(AirdropError::Overflow)?;
+    }
+    if payer.lamports() < required_lamports {
+        return Err(AirdropError::PayerCannotAffordRent.into());
+    }
+
+    process_airdrop_one_logic(
+        airdrop_config,
+        &airdrop_data,
+        user_data_account,
+        mint_account,
+        user,
+        recipient,
+        user_token_account,
+        token_metadata_account,
+        master_edition_account,
+        mint_authority,
+        rent_var,
+        slot_hashes_var,
+        clock,
+        rent,
+        payer,
+        admin_account,
         revenue_wallet,
+        &revenue_split_wallets,
+        stage,
+        referrer,
+        protocol_treasury,
+        price,
+        args.gift,
         mint_authority_bump,
         system_program,
         token_program,
     )?;
 
+    if let Some(escrow) = escrow {
+        process_create_escrow_logic(recipient, payer, rent, program_id, escrow)?;
+    }
+
+    set_mint_return_data(airdrop_data.airdrop_index, mint_account.key, price);
+
+    Ok(())
+}
+
+fn process_reveal<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // ----------------
+
+    process_reveal_logic(airdrop_config)?;
+
+    Ok(())
+}
+
+fn process_increase_supply<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    additional: u64,
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // ----------------
+
+    process_increase_supply_logic(airdrop_config, additional)?;
+
+    Ok(())
+}
+
+fn process_update_window<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    start_ts: i64,
+    end_ts: i64,
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+
+    // Args checks
+    debug_msg!("Assert mint window ends after it starts");
+    if end_ts != 0 && end_ts <= start_ts {
+        return Err(AirdropError::InvalidWindow.into());
+    }
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // Deactivated airdrop can't be resumed by opening the window again
+    debug_msg!("Assert airdrop is active");
+    assert_active(&airdrop_data)?;
+
+    // ----------------
+
+    process_update_window_logic(airdrop_config, start_ts, end_ts)?;
+
+    Ok(())
+}
+
+fn process_update_revenue_wallet<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    revenue_wallet: Pubkey,
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // Args checks
+    debug_msg!("Assert revenue wallet is not default or program account");
+    let mint_authority =
+        create_mint_authority(airdrop_config.key, airdrop_data.mint_authority_bump)
+            .or(Err(AirdropError::InvalidMintAuthorityPda))?;
+    if revenue_wallet == Pubkey::default()
+        || revenue_wallet == *airdrop_config.key
+        || revenue_wallet == mint_authority
+    {
+        return Err(AirdropError::InvalidRevenueWallet.into());
+    }
+
+    // ----------------
+
+    process_update_revenue_wallet_logic(airdrop_config, revenue_wallet)?;
+
+    Ok(())
+}
+
+fn process_set_user_blocked<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    blocked: bool,
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let user_data_account = next_account_info(iter)?;
+    let user = next_account_info(iter)?;
+    let rent = next_account_info(iter)?;
+    let fee_payer = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // User data account checks
+    debug_msg!("Assert user data is properly derived");
+    let (user_data_account_pda, user_data_account_bump) =
+        find_airdrop_user_data(airdrop_config.key, user.key);
+
+    assert_pda(
+        user_data_account,
+        &user_data_account_pda,
+        AirdropError::InvalidUserDataPda,
+    )?;
+
+    debug_msg!("Assert user data account is writeable");
+    assert_writeable(user_data_account)?;
+
+    let already_initialized = is_user_data_initialized(user_data_account, program_id)?;
+
+    if !already_initialized {
+        // Creating user data registers the wallet
+        debug_msg!("Assert registration is open");
+        assert_registration_open(&airdrop_data)?;
+
+        // Fee payer checks
+        debug_msg!("Assert that fee payer is signer");
+        assert_signer(fee_payer)?;
+
+        // Program checks
+        debug_msg!("Assert system program is correct one");
+        assert_program_id(system_program, &system_program::id())?;
+    }
+
+    // ----------------
+
+    if !already_initialized {
+        debug_msg!("Get rent");
+        let rent = Rent::from_account_info(rent)?;
+
+        process_initialize_airdrop_user_account_logic(
+            user_data_account,
+            user,
+            airdrop_config,
+            fee_payer,
+            rent,
+            program_id,
+            user_data_account_bump,
+        )?;
+    }
+
+    process_set_user_blocked_logic(user_data_account, blocked)?;
+
+    Ok(())
+}
+
+fn process_finalize<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    lock: bool,
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let clock_var = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    debug_msg!("Assert supply is exhausted or mint window ended");
+    let clock = Clock::from_account_info(clock_var)?;
+    let sold_out = airdrop_data.airdrop_index >= airdrop_data.airdrop_amount;
+    let window_ended = airdrop_data.end_ts != 0 && clock.unix_timestamp >= airdrop_data.end_ts;
+    if !sold_out && !window_ended {
+        return Err(AirdropError::AirdropNotEnded.into());
+    }
+
+    // ----------------
+
+    process_finalize_logic(airdrop_config, lock)?;
+
+    Ok(())
+}
+
+fn process_deactivate<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    debug_msg!("Assert airdrop is active");
+    assert_active(&airdrop_data)?;
+
+    // ----------------
+
+    process_deactivate_logic(airdrop_config)?;
+
+    Ok(())
+}
+
+fn process_set_usd_price<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: SetUsdPriceArgs,
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+
+    // Args checks
+    debug_msg!("Assert USD price has price feed and staleness window");
+    if args.price_usd_cents > 0
+        && (args.pyth_price_account == Pubkey::default() || args.max_price_age_slots == 0)
+    {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // ----------------
+
+    process_set_usd_price_logic(
+        airdrop_config,
+        args.price_usd_cents,
+        args.pyth_price_account,
+        args.max_price_age_slots,
+    )?;
+
+    Ok(())
+}
+
+fn process_draw_raffle<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    winners_count: u64,
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let slot_hashes = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // Raffle checks
+    debug_msg!("Assert airdrop is in raffle mode");
+    if !airdrop_data.raffle {
+        return Err(AirdropError::RaffleDisabled.into());
+    }
+
+    debug_msg!("Assert raffle is not drawn yet");
+    assert_registration_open(&airdrop_data)?;
+
+    // Args checks
+    debug_msg!("Assert winners count is not zero");
+    if winners_count == 0 {
+        return Err(AirdropError::BadInstructionArgument.into());
+    }
+
+    // Sysvar checks
+    debug_msg!("Assert slot hashes sysvar is correct one");
+    if !sysvar::slot_hashes::check_id(slot_hashes.key) {
+        return Err(AirdropError::WrongAccountAddress.into());
+    }
+
+    // ----------------
+
+    process_draw_raffle_logic(airdrop_config, slot_hashes, winners_count)?;
+
+    Ok(())
+}
+
+fn process_close_user_data<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let user = next_account_info(iter)?;
+    let user_data_account = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    // User checks
+    debug_msg!("Assert user is signer");
+    assert_signer(user)?;
+    debug_msg!("Assert user is writeable");
+    assert_writeable(user)?;
+
+    // User data account checks
+    debug_msg!("Assert user data is owned by program");
+    assert_owned_by(user_data_account, program_id)?;
+    debug_msg!("Assert user data is writeable");
+    assert_writeable(user_data_account)?;
+
+    let user_data = assert_user_data_of(airdrop_config, user, user_data_account)?;
+
+    // Raffle checks
+    debug_msg!("Assert airdrop is in raffle mode");
+    if !airdrop_data.raffle {
+        return Err(AirdropError::RaffleDisabled.into());
+    }
+
+    debug_msg!("Assert raffle is drawn");
+    if airdrop_data.winners_count == 0 {
+        return Err(AirdropError::RaffleNotDrawn.into());
+    }
+
+    // Winners keep their user data to mint
+    debug_msg!("Assert user lost the raffle");
+    if user_data.nonce == airdrop_data.nonce
+        && is_raffle_winner(
+            &airdrop_data.raffle_seed,
+            user_data.registration_index,
+            airdrop_data.registered_count,
+            airdrop_data.winners_count,
+        )
+    {
+        return Err(AirdropError::RaffleWinner.into());
+    }
+
+    // ----------------
+
+    process_sweep_user_account_logic(airdrop_config, user_data_account, user)?;
+
+    Ok(())
+}
+
+fn process_deregister<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let user = next_account_info(iter)?;
+    let user_data_account = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    // User checks
+    debug_msg!("Assert user is signer");
+    assert_signer(user)?;
+    debug_msg!("Assert user is writeable");
+    assert_writeable(user)?;
+
+    // User data account checks
+    debug_msg!("Assert user data is owned by program");
+    assert_owned_by(user_data_account, program_id)?;
+    debug_msg!("Assert user data is writeable");
+    assert_writeable(user_data_account)?;
+
+    let user_data = assert_user_data_of(airdrop_config, user, user_data_account)?;
+
+    debug_msg!("Assert user has not minted");
+    if user_data.mints_amount > 0 {
+        return Err(AirdropError::UserHasMinted.into());
+    }
+
+    // Registering again would clear the block
+    debug_msg!("Assert user is not blocked");
+    if user_data.blocked {
+        return Err(AirdropError::UserBlocked.into());
+    }
+
+    // ----------------
+
+    process_sweep_user_account_logic(airdrop_config, user_data_account, user)?;
+
+    Ok(())
+}
+
+fn process_recompute_bumps<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let mint_authority = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // Mint authority checks
+    debug_msg!("Assert mint authority is the re-derived one");
+    let (mint_authority_pda, mint_authority_bump) = find_mint_authority(airdrop_config.key);
+    assert_pda(
+        mint_authority,
+        &mint_authority_pda,
+        AirdropError::InvalidMintAuthorityPda,
+    )?;
+
+    // Minted token metadata checks. Re-derived PDA must be the one that signed minted tokens
+    if airdrop_data.airdrop_index > 0 {
+        let token_metadata_account = next_account_info(iter)?;
+
+        debug_msg!("Assert minted token metadata is owned by token metadata program");
+        assert_owned_by(token_metadata_account, &TOKEN_METADATA_PROGRAM_ID)?;
+
+        debug_msg!("Assert minted token is verified by re-derived mint authority");
+        let token_metadata = Metadata::from_account_info(token_metadata_account)?;
+        let verified_by_mint_authority = token_metadata
+            .data
+            .creators
+            .unwrap_or_default()
+            .iter()
+            .any(|creator| creator.address == mint_authority_pda && creator.verified);
+
+        if !verified_by_mint_authority {
+            return Err(AirdropError::InvalidMintAuthorityPda.into());
+        }
+    }
+
+    // User data accounts checks
+    let mut user_data_accounts = vec![];
+
+    for user_data_account in iter {
+        debug_msg!("Assert user data is owned by program");
+        assert_owned_by(user_data_account, program_id)?;
+        debug_msg!("Assert user data is writeable");
+        assert_writeable(user_data_account)?;
+
+        let user_data = AirdropUserData::unpack_initialized_from_account(user_data_account)?;
+
+        debug_msg!("Assert user data belongs to airdrop");
+        if user_data.airdrop != *airdrop_config.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        debug_msg!("Assert user data is properly derived");
+        let (user_data_pda, user_data_bump) =
+            find_airdrop_user_data(airdrop_config.key, &user_data.user);
+        assert_pda(
+            user_data_account,
+            &user_data_pda,
+            AirdropError::InvalidUserDataPda,
+        )?;
+
+        user_data_accounts.push((user_data_account, user_data_bump));
+    }
+
+    // ----------------
+
+    process_recompute_bumps_logic(airdrop_config, mint_authority_bump, &user_data_accounts)?;
+
+    Ok(())
+}
+
+fn process_add_stage<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: AddStageArgs,
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let stage_account = next_account_info(iter)?;
+    let rent = next_account_info(iter)?;
+    let fee_payer = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+
+    // Args checks
+    debug_msg!("Assert stage window ends after it starts");
+    if args.end_ts <= args.start_ts {
+        return Err(AirdropError::InvalidWindow.into());
+    }
+
+    debug_msg!("Assert stage supply cap is not zero");
+    if args.supply_cap == 0 {
+        return Err(AirdropError::InvalidSupply.into());
+    }
+
+    debug_msg!("Assert price is below sanity ceiling");
+    if cfg!(not(feature = "no-price-ceiling")) && args.price > MAX_PRICE {
+        return Err(AirdropError::InvalidPrice.into());
+    }
+
+    debug_msg!("Assert paid price is above sanity floor");
+    assert_price_floor(args.price)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert nothing is minted yet");
+    if airdrop_data.airdrop_index > 0 {
+        return Err(AirdropError::AirdropLive.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // Stage checks
+    let (stage_pda, stage_bump) = find_stage(airdrop_config.key, args.stage_index);
+
+    debug_msg!("Assert stage is properly derived");
+    assert_pda(stage_account, &stage_pda, AirdropError::InvalidStagePda)?;
+
+    debug_msg!("Assert stage is writeable");
+    assert_writeable(stage_account)?;
+
+    debug_msg!("Assert stage is not created yet");
+    if stage_account.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Fee payer checks
+    debug_msg!("Assert fee payer is signer");
+    assert_signer(fee_payer)?;
+
+    // Program checks
+    debug_msg!("Assert system program is correct one");
+    assert_program_id(system_program, &system_program::id())?;
+
+    let stage = AirdropStage {
+        initialized: true,
+        airdrop: *airdrop_config.key,
+        stage_index: args.stage_index,
+        bump: stage_bump,
+        price: args.price,
+        supply_cap: args.supply_cap,
+        minted: 0,
+        start_ts: args.start_ts,
+        end_ts: args.end_ts,
+        merkle_root: args.merkle_root,
+        per_user_limit: args.per_user_limit,
+    };
+
+    // Existing stages checks
+    let mut stage_accounts = vec![stage_account];
+
+    for existing_account in iter {
+        debug_msg!("Assert existing stage belongs to airdrop");
+        assert_owned_by(existing_account, program_id)?;
+        let existing = AirdropStage::unpack_from_account(existing_account)?;
+        if !existing.is_initialized() || existing.airdrop != *airdrop_config.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        debug_msg!("Assert stage windows do not overlap");
+        if existing.overlaps(&stage) {
+            return Err(AirdropError::StageOverlap.into());
+        }
+
+        stage_accounts.push(existing_account);
+    }
+
+    debug_msg!("Assert every existing stage is passed once");
+    assert_distinct(&stage_accounts)?;
+    if stage_accounts.len() - 1 != airdrop_data.stage_count as usize {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    // ----------------
+
+    let rent = Rent::from_account_info(rent)?;
+
+    process_add_stage_logic(
+        airdrop_config,
+        stage_account,
+        fee_payer,
+        rent,
+        program_id,
+        stage,
+    )?;
+
+    Ok(())
+}
+
+fn process_remove_stage<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let stage_account = next_account_info(iter)?;
+    let receiver = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // Stage checks
+    debug_msg!("Assert stage is owned by program");
+    assert_owned_by(stage_account, program_id)?;
+    debug_msg!("Assert stage is writeable");
+    assert_writeable(stage_account)?;
+
+    let stage = AirdropStage::unpack_from_account(stage_account)?;
+
+    debug_msg!("Assert stage is initialized");
+    if !stage.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert stage is properly derived from airdrop");
+    let stage_pda = create_stage(airdrop_config.key, stage.stage_index, stage.bump)
+        .or(Err(AirdropError::InvalidStagePda))?;
+    assert_pda(stage_account, &stage_pda, AirdropError::InvalidStagePda)?;
+
+    debug_msg!("Assert nothing was minted in stage");
+    if stage.minted > 0 {
+        return Err(AirdropError::StageInUse.into());
+    }
+
+    // Receiver checks
+    debug_msg!("Assert receiver is writeable");
+    assert_writeable(receiver)?;
+
+    debug_msg!("Assert receiver is not closed account");
+    assert_distinct(&[airdrop_config, stage_account, receiver])?;
+
+    // ----------------
+
+    process_remove_stage_logic(airdrop_config, stage_account, receiver)?;
+
+    Ok(())
+}
+
+fn process_sweep_user_account<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let user_data_account = next_account_info(iter)?;
+    let clock_var = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is writeable");
+    assert_writeable(airdrop_config)?;
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+    debug_msg!("Assert airdrop authority is writeable");
+    assert_writeable(airdrop_authority)?;
+
+    debug_msg!("Assert mint window ended before grace period");
+    let clock = Clock::from_account_info(clock_var)?;
+    if airdrop_data.end_ts == 0
+        || clock.unix_timestamp < airdrop_data.end_ts.saturating_add(USER_SWEEP_GRACE_SECS)
+    {
+        return Err(AirdropError::AirdropNotEnded.into());
+    }
+
+    // User data account checks
+    debug_msg!("Assert user data is owned by program");
+    assert_owned_by(user_data_account, program_id)?;
+    debug_msg!("Assert user data is writeable");
+    assert_writeable(user_data_account)?;
+
+    let user_data = AirdropUserData::unpack_initialized_from_account(user_data_account)?;
+
+    debug_msg!("Assert user data belongs to airdrop");
+    if user_data.airdrop != *airdrop_config.key {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    debug_msg!("Assert user data is properly derived");
+    let user_data_pda = cached_user_data_pda(airdrop_config.key, &user_data.user, user_data.bump)?;
+
+    assert_pda(
+        user_data_account,
+        &user_data_pda,
+        AirdropError::InvalidUserDataPda,
+    )?;
+
+    // ----------------
+
+    process_sweep_user_account_logic(airdrop_config, user_data_account, airdrop_authority)?;
+
+    Ok(())
+}
+
+fn process_claim_from_escrow<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let escrow_account = next_account_info(iter)?;
+    let escrow_token_account = next_account_info(iter)?;
+    let claimant = next_account_info(iter)?;
+    let claimant_token_account = next_account_info(iter)?;
+    let mint = next_account_info(iter)?;
+    let payer = next_account_info(iter)?;
+    let rent_var = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+    let token_program = next_account_info(iter)?;
+    let _ = next_account_info(iter)?; // Associated token program
+
+    let escrow = assert_escrow(
+        program_id,
+        airdrop_config,
+        escrow_account,
+        escrow_token_account,
+        payer,
+    )?;
+
+    // Claimant checks
+    debug_msg!("Assert claimant is signer");
+    assert_signer(claimant)?;
+    debug_msg!("Assert claimant is writeable");
+    assert_writeable(claimant)?;
+
+    debug_msg!("Assert claimant is recorded in escrow");
+    if escrow.claimant != *claimant.key {
+        return Err(AirdropError::NotEscrowClaimant.into());
+    }
+
+    // Mint checks
+    debug_msg!("Assert mint is escrowed one");
+    if escrow.mint != *mint.key {
+        return Err(AirdropError::WrongAccountAddress.into());
+    }
+
+    // Claimant token account checks
+    debug_msg!("Assert claimant token account is claimant's associated token account");
+    assert_pda(
+        claimant_token_account,
+        &spl_associated_token_account::get_associated_token_address(claimant.key, mint.key),
+        AirdropError::InvalidTokenAccountPda,
+    )?;
+
+    debug_msg!("Assert claimant token account is writeable");
+    assert_writeable(claimant_token_account)?;
+
+    // Program checks
+    debug_msg!("Assert system program is correct one");
+    assert_program_id(system_program, &system_program::id())?;
+    debug_msg!("Assert token program is correct one");
+    assert_program_id(token_program, &spl_token::id())?;
+
+    // ----------------
+
+    process_claim_from_escrow_logic(
+        escrow_account,
+        escrow_token_account,
+        claimant,
+        claimant_token_account,
+        mint,
+        payer,
+        rent_var,
+        system_program,
+        token_program,
+    )?;
+
+    Ok(())
+}
+
+fn process_sweep_escrow<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let escrow_account = next_account_info(iter)?;
+    let escrow_token_account = next_account_info(iter)?;
+    let destination = next_account_info(iter)?;
+    let payer = next_account_info(iter)?;
+    let clock_var = next_account_info(iter)?;
+    let token_program = next_account_info(iter)?;
+
+    let escrow = assert_escrow(
+        program_id,
+        airdrop_config,
+        escrow_account,
+        escrow_token_account,
+        payer,
+    )?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    debug_msg!("Assert escrow is past claim timeout");
+    let clock = Clock::from_account_info(clock_var)?;
+    if airdrop_data.escrow_timeout_secs == 0
+        || clock.unix_timestamp
+            < escrow
+                .created_ts
+                .saturating_add(airdrop_data.escrow_timeout_secs as i64)
+    {
+        return Err(AirdropError::EscrowNotExpired.into());
+    }
+
+    // Destination checks
+    debug_msg!("Assert destination is writeable");
+    assert_writeable(destination)?;
+
+    // Program checks
+    debug_msg!("Assert token program is correct one");
+    assert_program_id(token_program, &spl_token::id())?;
+
+    // ----------------
+
+    process_sweep_escrow_logic(
+        escrow_account,
+        escrow_token_account,
+        destination,
+        payer,
+        token_program,
+    )?;
+
+    Ok(())
+}
+
+fn process_deposit_preminted<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let vault = next_account_info(iter)?;
+    let vault_token_account = next_account_info(iter)?;
+    let mint = next_account_info(iter)?;
+    let source_token_account = next_account_info(iter)?;
+    let fee_payer = next_account_info(iter)?;
+    let rent_var = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+    let token_program = next_account_info(iter)?;
+    let _ = next_account_info(iter)?; // Associated token program
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert airdrop is not finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // Vault checks
+    let (vault_pda, vault_bump) = find_vault(airdrop_config.key);
+
+    debug_msg!("Assert vault is properly derived");
+    assert_pda(vault, &vault_pda, AirdropError::InvalidVaultPda)?;
+    debug_msg!("Assert vault is writeable");
+    assert_writeable(vault)?;
+
+    if !vault.data_is_empty() {
+        debug_msg!("Assert vault is owned by program");
+        assert_owned_by(vault, program_id)?;
+    }
+
+    // Vault token account checks
+    debug_msg!("Assert vault token account is vault's associated token account");
+    assert_pda(
+        vault_token_account,
+        &spl_associated_token_account::get_associated_token_address(vault.key, mint.key),
+        AirdropError::InvalidTokenAccountPda,
+    )?;
+
+    debug_msg!("Assert vault token account is writeable");
+    assert_writeable(vault_token_account)?;
+
+    // Mint checks
+    debug_msg!("Assert mint is owned by token program");
+    assert_owned_by(mint, &spl_token::id())?;
+
+    debug_msg!("Assert deposited mint is an NFT");
+    let mint_data = spl_token::state::Mint::unpack(&mint.data.borrow())?;
+    if mint_data.supply != 1 || mint_data.decimals != 0 {
+        return Err(AirdropError::InvalidMint.into());
+    }
+
+    // Source token account checks
+    debug_msg!("Assert source token account is writeable");
+    assert_writeable(source_token_account)?;
+
+    // Fee payer checks
+    debug_msg!("Assert fee payer is signer");
+    assert_signer(fee_payer)?;
+    debug_msg!("Assert fee payer is writeable");
+    assert_writeable(fee_payer)?;
+
+    // Program checks
+    debug_msg!("Assert system program is correct one");
+    assert_program_id(system_program, &system_program::id())?;
+    debug_msg!("Assert token program is correct one");
+    assert_program_id(token_program, &spl_token::id())?;
+
+    // ----------------
+
+    let rent = Rent::from_account_info(rent_var)?;
+
+    process_deposit_preminted_logic(
+        airdrop_config,
+        airdrop_authority,
+        vault,
+        vault_token_account,
+        mint,
+        source_token_account,
+        fee_payer,
+        rent_var,
+        rent,
+        program_id,
+        vault_bump,
+        system_program,
+        token_program,
+    )?;
+
+    Ok(())
+}
+
+fn process_claim_preminted<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let user_data_account = next_account_info(iter)?;
+    let user = next_account_info(iter)?;
+    let user_token_account = next_account_info(iter)?;
+    let vault = next_account_info(iter)?;
+    let vault_token_account = next_account_info(iter)?;
+    let mint = next_account_info(iter)?;
+    let payer = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let revenue_wallet = next_account_info(iter)?;
+    let clock_var = next_account_info(iter)?;
+    let rent_var = next_account_info(iter)?;
+    let instructions_var = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+    let token_program = next_account_info(iter)?;
+    let _ = next_account_info(iter)?; // Associated token program
+
+    // Airdrop config checks
+    debug_msg!("Check if airdrop account is writeable");
+    assert_writeable(airdrop_config)?;
+
+    // User data account checks
+    debug_msg!("Assert user data is writeable");
+    assert_writeable(user_data_account)?;
+
+    let clock = Clock::from_account_info(clock_var)?;
+
+    let (airdrop_data, user_data) =
+        assert_mint_state(program_id, airdrop_config, user_data_account, user)?;
+
+    debug_msg!("Check mint window");
+    assert_mint_window(&airdrop_data, &clock)?;
+
+    // Vault checks
+    debug_msg!("Assert vault is owned by program");
+    assert_owned_by(vault, program_id)?;
+    debug_msg!("Assert vault is writeable");
+    assert_writeable(vault)?;
+
+    let vault_data = PremintVault::unpack_from_account(vault)?;
+
+    debug_msg!("Assert vault is properly derived");
+    let vault_pda =
+        create_vault(airdrop_config.key, vault_data.bump).or(Err(AirdropError::InvalidVaultPda))?;
+    assert_pda(vault, &vault_pda, AirdropError::InvalidVaultPda)?;
+
+    debug_msg!("Assert mint is the next queued one");
+    match vault_data.next_mint(vault)? {
+        Some(next_mint) if next_mint == *mint.key => {}
+        Some(_) => return Err(AirdropError::WrongAccountAddress.into()),
+        None => return Err(AirdropError::VaultEmpty.into()),
+    }
+
+    // Token accounts checks
+    debug_msg!("Assert vault token account is vault's associated token account");
+    assert_pda(
+        vault_token_account,
+        &spl_associated_token_account::get_associated_token_address(vault.key, mint.key),
+        AirdropError::InvalidTokenAccountPda,
+    )?;
+    debug_msg!("Assert vault token account is writeable");
+    assert_writeable(vault_token_account)?;
+
+    debug_msg!("Assert token account is user's associated token account");
+    assert_pda(
+        user_token_account,
+        &spl_associated_token_account::get_associated_token_address(user.key, mint.key),
+        AirdropError::InvalidTokenAccountPda,
+    )?;
+    debug_msg!("Assert token account is writeable");
+    assert_writeable(user_token_account)?;
+
+    // Payer checks
+    debug_msg!("Assert payer is signer");
+    assert_signer(payer)?;
+    debug_msg!("Assert payer is writeable");
+    assert_writeable(payer)?;
+    debug_msg!("Assert payer is owned by system program");
+    assert_owned_by(payer, &system_program::id())?;
+
+    // User pays the price unless airdrop charges payer
+    if !airdrop_data.charge_payer {
+        debug_msg!("Assert user is signer");
+        if !user.is_signer {
+            return Err(AirdropError::UserSignerRequired.into());
+        }
+
+        debug_msg!("Assert user is writeable");
+        assert_writeable(user)?;
+        debug_msg!("Assert user is owned by system program");
+        assert_owned_by(user, &system_program::id())?;
+    }
+
+    // Airdrop authority checks
+    debug_msg!("Assert claim is approved by airdrop authority");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+    debug_msg!("Assert airdrop authority is writeable");
+    assert_writeable(airdrop_authority)?;
+
+    // Revenue wallet checks
+    debug_msg!("Assert that revenue wallet is correct one");
+    if airdrop_data.revenues_wallet != *revenue_wallet.key {
+        return Err(AirdropError::WrongAccountAddress.into());
+    }
+
+    // Program checks
+    debug_msg!("Assert system program is correct one");
+    assert_program_id(system_program, &system_program::id())?;
+    debug_msg!("Assert token program is correct one");
+    assert_program_id(token_program, &spl_token::id())?;
+
+    let revenue_split_wallets = next_revenue_split_wallets(iter, &airdrop_data)?;
+
+    // Stage checks
+    let stage = match airdrop_data.stage_count {
+        0 => None,
+        _ => Some(next_account_info(iter)?),
+    };
+
+    let price = match stage {
+        Some(stage) => {
+            assert_active_stage(
+                program_id,
+                airdrop_config,
+                stage,
+                user,
+                &user_data,
+                &clock,
+                &proof,
+            )?
+            .price
+        }
+        None => airdrop_data.price,
+    };
+
+    // USD price replaces lamport prices of airdrop and stages
+    let price = next_usd_price(iter, &airdrop_data, &clock)?.unwrap_or(price);
+
+    if price > 0 {
+        debug_msg!("Assert revenue wallet is writeable");
+        assert_writeable(revenue_wallet)?;
+
+        for split_wallet in revenue_split_wallets.iter() {
+            debug_msg!("Assert revenue split wallet is writeable");
+            assert_writeable(split_wallet)?;
+        }
+    }
+
+    debug_msg!("Assert critical accounts are distinct");
+    let mut critical_accounts = vec![
+        airdrop_config,
+        user_data_account,
+        user_token_account,
+        vault,
+        vault_token_account,
+        revenue_wallet,
+        payer,
+    ];
+
+    if user.key != payer.key {
+        critical_accounts.push(user);
+    }
+
+    if let Some(stage) = stage {
+        critical_accounts.push(stage);
+    }
+
+    assert_distinct(&critical_accounts)?;
+
+    debug_msg!("Assert revenue split wallets are neither payer nor user");
+    if revenue_split_wallets
+        .iter()
+        .any(|wallet| wallet.key == payer.key || wallet.key == user.key)
+    {
+        return Err(AirdropError::DuplicateAccount.into());
+    }
+
+    assert_soft_eligibility(
+        program_id,
+        &airdrop_data,
+        &user_data,
+        &clock,
+        instructions_var,
+    )?;
+
+    // ----------------
+
+    process_claim_preminted_logic(
+        airdrop_config,
+        user_data_account,
+        user,
+        user_token_account,
+        vault,
+        vault_token_account,
+        mint,
+        payer,
+        airdrop_authority,
+        revenue_wallet,
+        &revenue_split_wallets,
+        stage,
+        clock,
+        price,
+        rent_var,
+        system_program,
+        token_program,
+    )?;
+
+    Ok(())
+}
+
+fn process_withdraw_unclaimed<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let vault = next_account_info(iter)?;
+    let vault_token_account = next_account_info(iter)?;
+    let mint = next_account_info(iter)?;
+    let destination = next_account_info(iter)?;
+    let rent_receiver = next_account_info(iter)?;
+    let clock_var = next_account_info(iter)?;
+    let rent_var = next_account_info(iter)?;
+    let token_program = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    debug_msg!("Assert mint window has ended");
+    let clock = Clock::from_account_info(clock_var)?;
+    if airdrop_data.end_ts == 0 || clock.unix_timestamp < airdrop_data.end_ts {
+        return Err(AirdropError::AirdropNotEnded.into());
+    }
+
+    // Vault checks
+    debug_msg!("Assert vault is owned by program");
+    assert_owned_by(vault, program_id)?;
+    debug_msg!("Assert vault is writeable");
+    assert_writeable(vault)?;
+
+    let vault_data = PremintVault::unpack_from_account(vault)?;
+
+    debug_msg!("Assert vault is properly derived");
+    let vault_pda =
+        create_vault(airdrop_config.key, vault_data.bump).or(Err(AirdropError::InvalidVaultPda))?;
+    assert_pda(vault, &vault_pda, AirdropError::InvalidVaultPda)?;
+
+    debug_msg!("Assert mint is queued and not claimed");
+    let mut queue_index = None;
+    for index in vault_data.cursor..vault_data.len {
+        if PremintVault::mint_at(vault, index)? == *mint.key {
+            queue_index = Some(index);
+            break;
+        }
+    }
+    let queue_index = queue_index.ok_or(AirdropError::MintNotQueued)?;
+
+    // Vault token account checks
+    debug_msg!("Assert vault token account is vault's associated token account");
+    assert_pda(
+        vault_token_account,
+        &spl_associated_token_account::get_associated_token_address(vault.key, mint.key),
+        AirdropError::InvalidTokenAccountPda,
+    )?;
+    debug_msg!("Assert vault token account is writeable");
+    assert_writeable(vault_token_account)?;
+
+    // Destination checks
+    debug_msg!("Assert destination is writeable");
+    assert_writeable(destination)?;
+    debug_msg!("Assert rent receiver is writeable");
+    assert_writeable(rent_receiver)?;
+
+    // Program checks
+    debug_msg!("Assert token program is correct one");
+    assert_program_id(token_program, &spl_token::id())?;
+
+    // ----------------
+
+    let rent = Rent::from_account_info(rent_var)?;
+
+    process_withdraw_unclaimed_logic(
+        airdrop_config,
+        vault,
+        vault_token_account,
+        destination,
+        rent_receiver,
+        queue_index,
+        rent,
+        token_program,
+    )
+}
+
+/// Takes one revenue split wallet account for each used split, in config order
+fn next_revenue_split_wallets<'a, 'b>(
+    iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+    airdrop_data: &AirdropConfig,
+) -> Result<Vec<&'b AccountInfo<'a>>, ProgramError> {
+    let mut revenue_split_wallets = Vec::with_capacity(MAX_REVENUE_SPLITS);
+
+    for split in airdrop_data.revenue_splits.iter().filter(|s| s.is_used()) {
+        let split_wallet = next_account_info(iter)?;
+
+        debug_msg!("Assert that revenue split wallet is correct one");
+        if split.wallet != *split_wallet.key {
+            return Err(AirdropError::WrongAccountAddress.into());
+        }
+
+        revenue_split_wallets.push(split_wallet);
+    }
+
+    Ok(revenue_split_wallets)
+}
+
+/// Takes Pyth price account when airdrop is priced in USD. Returns USD price in lamports
+fn next_usd_price<'a, 'b>(
+    iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+    airdrop_data: &AirdropConfig,
+    clock: &Clock,
+) -> Result<Option<u64>, ProgramError> {
+    if airdrop_data.price_usd_cents == 0 {
+        return Ok(None);
+    }
+
+    let price_account = next_account_info(iter)?;
+
+    debug_msg!("Assert that price account is correct one");
+    if airdrop_data.pyth_price_account != *price_account.key {
+        return Err(AirdropError::WrongAccountAddress.into());
+    }
+
+    let (sol_price, expo) = read_pyth_price(
+        &price_account.data.borrow(),
+        clock.slot,
+        airdrop_data.max_price_age_slots,
+    )?;
+    let lamports = usd_cents_to_lamports(airdrop_data.price_usd_cents, sol_price, expo)
+        .ok_or(AirdropError::Overflow)?;
+
+    msg!(
+        "UsdPrice {} cents {} lamports",
+        airdrop_data.price_usd_cents,
+        lamports
+    );
+
+    Ok(Some(lamports))
+}
+
+/// Checks escrow and its token account shared by claim and sweep. Returns escrow state
+fn assert_escrow(
+    program_id: &Pubkey,
+    airdrop_config: &AccountInfo,
+    escrow_account: &AccountInfo,
+    escrow_token_account: &AccountInfo,
+    payer: &AccountInfo,
+) -> Result<AirdropEscrow, ProgramError> {
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    // Escrow checks
+    debug_msg!("Assert escrow is owned by program");
+    assert_owned_by(escrow_account, program_id)?;
+    debug_msg!("Assert escrow is writeable");
+    assert_writeable(escrow_account)?;
+
+    let escrow = AirdropEscrow::unpack_initialized_from_account(escrow_account)?;
+
+    debug_msg!("Assert escrow belongs to airdrop");
+    if escrow.airdrop != *airdrop_config.key {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    debug_msg!("Assert escrow is properly derived");
+    let escrow_pda = create_escrow(airdrop_config.key, escrow.purchase_index, escrow.bump)
+        .or(Err(AirdropError::InvalidEscrowPda))?;
+    assert_pda(escrow_account, &escrow_pda, AirdropError::InvalidEscrowPda)?;
+
+    // Escrow token account checks
+    debug_msg!("Assert escrow token account is escrow's associated token account");
+    assert_pda(
+        escrow_token_account,
+        &spl_associated_token_account::get_associated_token_address(
+            escrow_account.key,
+            &escrow.mint,
+        ),
+        AirdropError::InvalidTokenAccountPda,
+    )?;
+
+    debug_msg!("Assert escrow token account is writeable");
+    assert_writeable(escrow_token_account)?;
+
+    // Payer checks
+    debug_msg!("Assert payer is the one recorded in escrow");
+    if escrow.payer != *payer.key {
+        return Err(AirdropError::WrongAccountAddress.into());
+    }
+
+    debug_msg!("Assert payer is writeable");
+    assert_writeable(payer)?;
+
+    Ok(escrow)
+}
+
+fn process_sweep_mint_authority<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let airdrop_authority = next_account_info(iter)?;
+    let mint_authority = next_account_info(iter)?;
+    let revenue_wallet = next_account_info(iter)?;
+    let system_program = next_account_info(iter)?;
+
+    // Airdrop config checks
+    debug_msg!("Assert airdrop config is owned by program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Assert airdrop config is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    // Airdrop authority checks
+    debug_msg!("Assert airdrop authority is correct signer");
+    assert_airdrop_authority(&airdrop_data, airdrop_authority)?;
+
+    // Mint authority checks
+    let mint_authority_bump = airdrop_data.mint_authority_bump;
+    let mint_authority_pda = create_mint_authority(airdrop_config.key, mint_authority_bump)
+        .or(Err(AirdropError::InvalidMintAuthorityPda))?;
+
+    debug_msg!("Assert mint authority is properly derived");
+    assert_pda(
+        mint_authority,
+        &mint_authority_pda,
+        AirdropError::InvalidMintAuthorityPda,
+    )?;
+
+    debug_msg!("Assert mint authority is writeable");
+    assert_writeable(mint_authority)?;
+
+    // Revenue wallet checks
+    debug_msg!("Assert that revenue wallet is correct one");
+    if airdrop_data.revenues_wallet != *revenue_wallet.key {
+        return Err(AirdropError::WrongAccountAddress.into());
+    }
+
+    debug_msg!("Assert revenue wallet is writeable");
+    assert_writeable(revenue_wallet)?;
+
+    // Program checks
+    debug_msg!("Assert system program is correct one");
+    assert_program_id(system_program, &system_program::id())?;
+
+    // ----------------
+
+    process_sweep_mint_authority_logic(
+        airdrop_config,
+        mint_authority,
+        revenue_wallet,
+        program_id,
+        mint_authority_bump,
+    )?;
+
+    Ok(())
+}
+
+fn process_can_mint<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let user_data_account = next_account_info(iter)?;
+    let user = next_account_info(iter)?;
+    let clock_var = next_account_info(iter)?;
+
+    let clock = Clock::from_account_info(clock_var)?;
+
+    assert_mint_eligibility(program_id, airdrop_config, user_data_account, user, &clock)?;
+
+    msg!("User can mint");
+
+    Ok(())
+}
+
+fn process_preview_mint<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let airdrop_config = next_account_info(iter)?;
+    let user_data_account = next_account_info(iter)?;
+    let user = next_account_info(iter)?;
+    let clock_var = next_account_info(iter)?;
+    let rent_var = next_account_info(iter)?;
+    let mint_account = next_account_info(iter)?;
+    let user_token_account = next_account_info(iter)?;
+
+    let clock = Clock::from_account_info(clock_var)?;
+    let rent = Rent::from_account_info(rent_var)?;
+
+    let (airdrop_data, user_data) =
+        assert_mint_eligibility(program_id, airdrop_config, user_data_account, user, &clock)?;
+
+    // User token account checks
+    debug_msg!("Assert token account is user's associated token account");
+    assert_pda(
+        user_token_account,
+        &spl_associated_token_account::get_associated_token_address(user.key, mint_account.key),
+        AirdropError::InvalidTokenAccountPda,
+    )?;
+
+    // Price is resolved the same way MintOne does
+    let stage = match airdrop_data.stage_count {
+        0 => None,
+        _ => Some(next_account_info(iter)?),
+    };
+
+    let price = match stage {
+        Some(stage) => {
+            read_active_stage(
+                program_id,
+                airdrop_config,
+                stage,
+                user,
+                &user_data,
+                &clock,
+                &proof,
+            )?
+            .price
+        }
+        None => airdrop_data.price,
+    };
+
+    let price = next_usd_price(iter, &airdrop_data, &clock)?.unwrap_or(price);
+
+    // ----------------
+
+    let estimated_rent_lamports =
+        mint_rent_lamports(&rent, &airdrop_data, user_token_account.lamports() == 0)?;
+
+    process_preview_mint_logic(&airdrop_data, price, estimated_rent_lamports)
+}
+
+/// Checks shared by CanMint and PreviewMint. Returns airdrop config and user data on success,
+/// user data is default when airdrop does not require registration
+fn assert_mint_eligibility(
+    program_id: &Pubkey,
+    airdrop_config: &AccountInfo,
+    user_data_account: &AccountInfo,
+    user: &AccountInfo,
+    clock: &Clock,
+) -> Result<(AirdropConfig, AirdropUserData), ProgramError> {
+    let airdrop_data = assert_airdrop_mint_state(program_id, airdrop_config)?;
+    let user_data = assert_open_user_state(
+        program_id,
+        airdrop_config,
+        &airdrop_data,
+        user_data_account,
+        user,
+    )?
+    .unwrap_or_default();
+
+    debug_msg!("Check mint window");
+    assert_mint_window(&airdrop_data, clock)?;
+
+    debug_msg!("Check user timeout");
+    assert_user_unlocked(&user_data, clock)?;
+
+    Ok((airdrop_data, user_data))
+}
+
+/// Eligibility checks bot tax is charged for instead of failing
+fn assert_soft_eligibility(
+    program_id: &Pubkey,
+    airdrop_data: &AirdropConfig,
+    user_data: &AirdropUserData,
+    clock: &Clock,
+    instructions_var: &AccountInfo,
+) -> Result<(), ProgramError> {
+    debug_msg!("Check user timeout");
+    assert_user_unlocked(user_data, clock)?;
+
+    if airdrop_data.max_mints_per_tx > 0 || airdrop_data.fairness_bps > 0 {
+        debug_msg!("Assert transaction does not contain too many mints");
+        let (mints_in_transaction, mints_before) =
+            count_mint_instructions(program_id, instructions_var)?;
+
+        // Supply as it was before the first mint of transaction, so every mint sees same limit
+        let remaining_supply = airdrop_data
+            .airdrop_amount
+            .saturating_sub(airdrop_data.airdrop_index)
+            .saturating_add(mints_before);
+
+        if mints_in_transaction > max_mints_in_transaction(airdrop_data, remaining_supply) {
+            return Err(AirdropError::TooManyMintsInTransaction.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Per transaction limit clamped by fairness share of remaining supply. Never below one mint
+fn max_mints_in_transaction(airdrop_data: &AirdropConfig, remaining_supply: u64) -> u64 {
+    let limit = match airdrop_data.max_mints_per_tx {
+        0 => u64::MAX,
+        max => max as u64,
+    };
+
+    match airdrop_data.fairness_bps {
+        0 => limit,
+        bps => limit.min(fair_mint_limit(remaining_supply, bps)),
+    }
+}
+
+fn is_soft_failure(error: &ProgramError) -> bool {
+    [
+        AirdropError::UserTimeout,
+        AirdropError::TooManyMintsInTransaction,
+        AirdropError::MintWindowClosed,
+        AirdropError::StageNotActive,
+        AirdropError::NotAllowlisted,
+        AirdropError::StageLimitReached,
+    ]
+    .iter()
+    .any(|soft| *error == (*soft).into())
+}
+
+/// Raises hard failure right away and hands soft failure back to be settled by bot tax
+fn defer_soft_failure<T>(
+    result: Result<T, ProgramError>,
+) -> Result<Result<T, ProgramError>, ProgramError> {
+    match result {
+        Err(error) if !is_soft_failure(&error) => Err(error),
+        result => Ok(result),
+    }
+}
+
+fn assert_active_stage(
+    program_id: &Pubkey,
+    airdrop_config: &AccountInfo,
+    stage_account: &AccountInfo,
+    user: &AccountInfo,
+    user_data: &AirdropUserData,
+    clock: &Clock,
+    proof: &[[u8; 32]],
+) -> Result<AirdropStage, ProgramError> {
+    debug_msg!("Assert stage is writeable");
+    assert_writeable(stage_account)?;
+
+    read_active_stage(
+        program_id,
+        airdrop_config,
+        stage_account,
+        user,
+        user_data,
+        clock,
+        proof,
+    )
+}
+
+/// Stage checks of [`assert_active_stage`] without writing stage, shared with PreviewMint
+fn read_active_stage(
+    program_id: &Pubkey,
+    airdrop_config: &AccountInfo,
+    stage_account: &AccountInfo,
+    user: &AccountInfo,
+    user_data: &AirdropUserData,
+    clock: &Clock,
+    proof: &[[u8; 32]],
+) -> Result<AirdropStage, ProgramError> {
+    debug_msg!("Assert stage is owned by program");
+    assert_owned_by(stage_account, program_id)?;
+
+    let stage = AirdropStage::unpack_from_account(stage_account)?;
+
+    debug_msg!("Assert stage is initialized");
+    if !stage.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Assert stage is properly derived from airdrop");
+    let stage_pda = create_stage(airdrop_config.key, stage.stage_index, stage.bump)
+        .or(Err(AirdropError::InvalidStagePda))?;
+    assert_pda(stage_account, &stage_pda, AirdropError::InvalidStagePda)?;
+
+    debug_msg!("Assert stage is active");
+    let now = clock.unix_timestamp;
+    if now < stage.start_ts || now >= stage.end_ts {
+        return Err(AirdropError::StageNotActive.into());
+    }
+
+    debug_msg!("Assert stage has supply left");
+    if stage.minted >= stage.supply_cap {
+        return Err(AirdropError::OutOfSupply.into());
+    }
+
+    debug_msg!("Assert user is below stage limit");
+    if stage.per_user_limit > 0 && user_data.mints_amount >= stage.per_user_limit {
+        return Err(AirdropError::StageLimitReached.into());
+    }
+
+    debug_msg!("Assert user is on stage allowlist");
+    if stage.has_allowlist()
+        && !verify_merkle_proof(allowlist_leaf(user.key), proof, &stage.merkle_root)
+    {
+        return Err(AirdropError::NotAllowlisted.into());
+    }
+
+    Ok(stage)
+}
+
+fn assert_user_unlocked(user_data: &AirdropUserData, clock: &Clock) -> Result<(), ProgramError> {
+    match user_data.locked_till > clock.unix_timestamp as u64 {
+        true => Err(AirdropError::UserTimeout.into()),
+        false => Ok(()),
+    }
+}
+
+fn assert_not_finalized(airdrop_data: &AirdropConfig) -> ProgramResult {
+    match airdrop_data.finalized {
+        true => Err(AirdropError::AirdropFinalized.into()),
+        false => Ok(()),
+    }
+}
+
+fn assert_active(airdrop_data: &AirdropConfig) -> ProgramResult {
+    match airdrop_data.is_active {
+        true => Ok(()),
+        false => Err(AirdropError::AirdropEnded.into()),
+    }
+}
+
+fn assert_mint_window(airdrop_data: &AirdropConfig, clock: &Clock) -> Result<(), ProgramError> {
+    let now = clock.unix_timestamp;
+
+    match now >= airdrop_data.start_ts && (airdrop_data.end_ts == 0 || now < airdrop_data.end_ts) {
+        true => Ok(()),
+        false => Err(AirdropError::MintWindowClosed.into()),
+    }
+}
+
+/// Airdrop and user data checks shared by all mint related instructions
+fn assert_mint_state(
+    program_id: &Pubkey,
+    airdrop_config: &AccountInfo,
+    user_data_account: &AccountInfo,
+    user: &AccountInfo,
+) -> Result<(AirdropConfig, AirdropUserData), ProgramError> {
+    let airdrop_data = assert_airdrop_mint_state(program_id, airdrop_config)?;
+    let user_data = assert_user_mint_state(
+        program_id,
+        airdrop_config,
+        &airdrop_data,
+        user_data_account,
+        user,
+    )?;
+
+    Ok((airdrop_data, user_data))
+}
+
+/// Open mint airdrop reads no user data, its users are checked as fresh ones
+/// and per user limits are not enforced
+/// User data checks of airdrop that requires registration. Open mint has no user data
+fn assert_open_user_state(
+    program_id: &Pubkey,
+    airdrop_config: &AccountInfo,
+    airdrop_data: &AirdropConfig,
+    user_data_account: &AccountInfo,
+    user: &AccountInfo,
+) -> Result<Option<AirdropUserData>, ProgramError> {
+    match airdrop_data.require_registration {
+        true => Ok(Some(assert_user_mint_state(
+            program_id,
+            airdrop_config,
+            airdrop_data,
+            user_data_account,
+            user,
+        )?)),
+        false => Ok(None),
+    }
+}
+
+fn assert_airdrop_mint_state(
+    program_id: &Pubkey,
+    airdrop_config: &AccountInfo,
+) -> Result<AirdropConfig, ProgramError> {
+    // Airdrop config checks
+    debug_msg!("Check if airdrop account is owned by this program");
+    assert_owned_by(airdrop_config, program_id)?;
+
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Check if airdrop account is initialized");
+    if !airdrop_data.is_initialized() {
+        return Err(AirdropError::Uninitialized.into());
+    }
+
+    debug_msg!("Check if airdrop is finalized");
+    assert_not_finalized(&airdrop_data)?;
+
+    debug_msg!("Check if airdrop is active");
+    assert_active(&airdrop_data)?;
+
+    debug_msg!("Check supply");
+    if airdrop_data.airdrop_index >= airdrop_data.airdrop_amount {
+        return Err(AirdropError::OutOfSupply.into());
+    }
+
+    Ok(airdrop_data)
+}
+
+fn assert_user_mint_state(
+    program_id: &Pubkey,
+    airdrop_config: &AccountInfo,
+    airdrop_data: &AirdropConfig,
+    user_data_account: &AccountInfo,
+    user: &AccountInfo,
+) -> Result<AirdropUserData, ProgramError> {
+    // User data account checks
+    debug_msg!("Check if user data is owned by this program");
+    assert_owned_by(user_data_account, program_id)?;
+
+    debug_msg!("Check if user data account is initialized");
+    let user_data = AirdropUserData::unpack_initialized_from_account(user_data_account)?;
+
+    debug_msg!("Check if airdrop data and user wallet are valid for user data account");
+    if !(user_data.user == *user.key && user_data.airdrop == *airdrop_config.key) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    debug_msg!("Check if user data is properly derived");
+    let user_data_pda = cached_user_data_pda(airdrop_config.key, user.key, user_data.bump)?;
+
+    assert_pda(
+        user_data_account,
+        &user_data_pda,
+        AirdropError::InvalidUserDataPda,
+    )?;
+
+    debug_msg!("Check if user data belongs to current airdrop");
+    if user_data.nonce != airdrop_data.nonce {
+        return Err(AirdropError::StaleUserAccount.into());
+    }
+
+    debug_msg!("Check if user is blocked");
+    if user_data.blocked {
+        return Err(AirdropError::UserBlocked.into());
+    }
+
+    if airdrop_data.raffle {
+        debug_msg!("Check if user won the raffle");
+        if airdrop_data.winners_count == 0 {
+            return Err(AirdropError::RaffleNotDrawn.into());
+        }
+
+        if !is_raffle_winner(
+            &airdrop_data.raffle_seed,
+            user_data.registration_index,
+            airdrop_data.registered_count,
+            airdrop_data.winners_count,
+        ) {
+            return Err(AirdropError::NotRaffleWinner.into());
+        }
+    }
+
+    Ok(user_data)
+}
+
+/// Raffle entrants are fixed once winners are drawn
+fn assert_registration_open(airdrop_data: &AirdropConfig) -> ProgramResult {
+    if airdrop_data.raffle && airdrop_data.winners_count != 0 {
+        return Err(AirdropError::RaffleDrawn.into());
+    }
+
+    Ok(())
+}
+
+/// Mint instructions in transaction, and how many of them come before the current one
+fn count_mint_instructions(
+    program_id: &Pubkey,
+    instructions_var: &AccountInfo,
+) -> Result<(u64, u64), ProgramError> {
+    if !sysvar::instructions::check_id(instructions_var.key) {
+        return Err(AirdropError::WrongAccountAddress.into());
+    }
+
+    let current_index = load_current_index_checked(instructions_var)? as usize;
+    let mut count = 0;
+    let mut before = 0;
+    let mut index = 0;
+
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions_var) {
+        if instruction.program_id == *program_id {
+            if let Ok(
+                AirdropInstruction::MintOne(_)
+                | AirdropInstruction::MintToEscrow(_)
+                | AirdropInstruction::ClaimPreminted(_),
+            ) = deserialize_instruction_data(&instruction.data)
+            {
+                count += 1;
+
+                if index < current_index {
+                    before += 1;
+                }
+            }
+        }
+
+        index += 1;
+    }
+
+    Ok((count, before))
+}
+
+fn assert_revenue_splits(splits: &[RevenueSplit]) -> Result<(), ProgramError> {
+    let total_basis_points: u32 = splits.iter().map(|s| s.basis_points as u32).sum();
+    let wallets_set = splits
+        .iter()
+        .filter(|s| s.is_used())
+        .all(|s| s.wallet != Pubkey::default());
+
+    // No splits at all means that everything goes to revenue wallet
+    match (total_basis_points == 0 || total_basis_points == 10000) && wallets_set {
+        true => Ok(()),
+        false => Err(AirdropError::InvalidSplit.into()),
+    }
+}
+
+fn assert_airdrop_authority(
+    airdrop_data: &AirdropConfig,
+    airdrop_authority: &AccountInfo,
+) -> Result<(), ProgramError> {
+    assert_signer(airdrop_authority)?;
+
+    match airdrop_data.airdrop_authority.eq(airdrop_authority.key) {
+        true => Ok(()),
+        false => Err(AirdropError::WrongAccountAddress.into()),
+    }
+}
+
+fn assert_price_floor(price: u64) -> ProgramResult {
+    match cfg!(not(feature = "no-price-floor")) && price > 0 && price < MIN_PRICE {
+        true => Err(AirdropError::SuspiciousPrice.into()),
+        false => Ok(()),
+    }
+}
+
+fn assert_program_id(acc: &AccountInfo, program_id: &Pubkey) -> Result<(), ProgramError> {
+    match acc.key == program_id {
+        true => Ok(()),
+        false => Err(AirdropError::InvalidProgramAccount.into()),
+    }
+}
+
+fn assert_pda(acc: &AccountInfo, expected: &Pubkey, error: AirdropError) -> ProgramResult {
+    if acc.key != expected {
+        msg!("Expected {} but received {}", expected, acc.key);
+        return Err(error.into());
+    }
+
+    Ok(())
+}
+
+/// User data address built from bump cached on registration, one `create_program_address`
+/// instead of the `find_program_address` search. Accounts registered before bumps were
+/// cached hold zero there and are rejected until RecomputeBumps rewrites it.
+///
+/// Non-canonical bump is not rejected on purpose. Bump is read from program owned user data,
+/// and the program only ever writes bumps returned by `find_program_address` (registration,
+/// RecomputeBumps), so a stored bump is canonical. Proving it here would mean trying every
+/// higher bump, which is the same search the cached bump exists to skip
+fn cached_user_data_pda(airdrop: &Pubkey, user: &Pubkey, bump: u8) -> Result<Pubkey, ProgramError> {
+    create_airdrop_user_data(airdrop, user, bump).or(Err(AirdropError::InvalidUserDataPda.into()))
+}
+
+/// Unpacks initialized user data and checks that it is derived for user and airdrop
+fn assert_user_data_of(
+    airdrop_config: &AccountInfo,
+    user: &AccountInfo,
+    user_data_account: &AccountInfo,
+) -> Result<AirdropUserData, ProgramError> {
+    let user_data = AirdropUserData::unpack_initialized_from_account(user_data_account)?;
+
+    debug_msg!("Assert user data belongs to user and airdrop");
+    if user_data.user != *user.key || user_data.airdrop != *airdrop_config.key {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    debug_msg!("Assert user data is properly derived");
+    let user_data_pda = cached_user_data_pda(airdrop_config.key, user.key, user_data.bump)?;
+
+    assert_pda(
+        user_data_account,
+        &user_data_pda,
+        AirdropError::InvalidUserDataPda,
+    )?;
+
+    Ok(user_data)
+}
+
+/// User data left over from a previous airdrop at the same config address is not
+/// a registration. It is re-initialized in place when user registers again
+fn is_user_registered(
+    user_data_account: &AccountInfo,
+    airdrop_data: &AirdropConfig,
+    program_id: &Pubkey,
+) -> Result<bool, ProgramError> {
+    if !is_user_data_initialized(user_data_account, program_id)? {
+        return Ok(false);
+    }
+
+    Ok(AirdropUserData::unpack_from_account(user_data_account)?.nonce == airdrop_data.nonce)
+}
+
+/// Lamports alone don't mean user data exists, anyone can send them to PDA address
+fn is_user_data_initialized(
+    user_data_account: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<bool, ProgramError> {
+    if user_data_account.owner != program_id {
+        return Ok(false);
+    }
+
+    Ok(AirdropUserData::unpack_from_account(user_data_account)?.is_initialized())
+}
+
+fn assert_distinct(accounts: &[&AccountInfo]) -> ProgramResult {
+    for (i, acc) in accounts.iter().enumerate() {
+        if accounts[i + 1..].iter().any(|other| other.key == acc.key) {
+            return Err(AirdropError::DuplicateAccount.into());
+        }
+    }
+
     Ok(())
 }
 
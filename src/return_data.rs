@@ -0,0 +1,92 @@
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// Return data set by MintOne. Clients decode it from simulation results
+/// or confirmed transaction meta with `MintReturnData::unpack_from_slice`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MintReturnData {
+    pub index: u64,
+    pub mint: Pubkey,
+    pub price_paid: u64,
+}
+
+impl Sealed for MintReturnData {}
+
+impl Pack for MintReturnData {
+    const LEN: usize = 8 + 32 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, MintReturnData::LEN];
+        let (index, mint, price_paid) = mut_array_refs![dst, 8, 32, 8];
+
+        index.copy_from_slice(&self.index.to_le_bytes());
+        mint.copy_from_slice(&self.mint.to_bytes());
+        price_paid.copy_from_slice(&self.price_paid.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != MintReturnData::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src = array_ref![src, 0, MintReturnData::LEN];
+        let (index_src, mint_src, price_paid_src) = array_refs![src, 8, 32, 8];
+
+        Ok(MintReturnData {
+            index: u64::from_le_bytes(*index_src),
+            mint: Pubkey::new_from_array(*mint_src),
+            price_paid: u64::from_le_bytes(*price_paid_src),
+        })
+    }
+}
+
+/// Return data set by PreviewMint. All amounts are in lamports
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MintPreview {
+    pub effective_price: u64,
+    pub estimated_rent_lamports: u64,
+    pub remaining_supply: u64,
+    pub remaining_allowance: u64,
+}
+
+impl Sealed for MintPreview {}
+
+impl Pack for MintPreview {
+    const LEN: usize = 8 + 8 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, MintPreview::LEN];
+        let (effective_price, estimated_rent_lamports, remaining_supply, remaining_allowance) =
+            mut_array_refs![dst, 8, 8, 8, 8];
+
+        effective_price.copy_from_slice(&self.effective_price.to_le_bytes());
+        estimated_rent_lamports.copy_from_slice(&self.estimated_rent_lamports.to_le_bytes());
+        remaining_supply.copy_from_slice(&self.remaining_supply.to_le_bytes());
+        remaining_allowance.copy_from_slice(&self.remaining_allowance.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != MintPreview::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let src = array_ref![src, 0, MintPreview::LEN];
+        let (
+            effective_price_src,
+            estimated_rent_lamports_src,
+            remaining_supply_src,
+            remaining_allowance_src,
+        ) = array_refs![src, 8, 8, 8, 8];
+
+        Ok(MintPreview {
+            effective_price: u64::from_le_bytes(*effective_price_src),
+            estimated_rent_lamports: u64::from_le_bytes(*estimated_rent_lamports_src),
+            remaining_supply: u64::from_le_bytes(*remaining_supply_src),
+            remaining_allowance: u64::from_le_bytes(*remaining_allowance_src),
+        })
+    }
+}
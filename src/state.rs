@@ -1,13 +1,77 @@
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use solana_program::{
     account_info::AccountInfo,
+    native_token::LAMPORTS_PER_SOL,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
 
+use crate::error::AirdropError;
+
+/// Metaplex token metadata program mints are created with. Other deployments of
+/// the program use incompatible account layouts
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Longest URI token metadata accepts
+pub const MAX_URI_LENGTH: usize = metaplex_token_metadata::state::MAX_URI_LENGTH;
+
+pub const AIRDROP_CONFIG: &str = "airdrop_config";
 pub const USER_DATA: &str = "user_data";
 pub const MINT_AUTHORITY: &str = "mint_authority";
+pub const STAGE: &str = "stage";
+pub const ESCROW: &str = "escrow";
+pub const VAULT: &str = "vault";
+
+/// Sanity ceiling for mint price, disabled by `no-price-ceiling` feature
+pub const MAX_PRICE: u64 = 1_000 * LAMPORTS_PER_SOL;
+
+/// Sanity floor for paid mint price, disabled by `no-price-floor` feature.
+/// Zero price is always allowed as explicit free mint
+pub const MIN_PRICE: u64 = LAMPORTS_PER_SOL / 1_000;
+
+/// Time after mint window end before authority may close user data accounts
+pub const USER_SWEEP_GRACE_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Most decimals of minted token. 0 mints NFTs, more mints semi-fungible tokens
+pub const MAX_DECIMALS: u8 = 9;
+
+/// One-time fee organizer pays to platform wallet when initializing airdrop.
+/// Charged only by builds with `platform-fee` feature
+pub const PLATFORM_FEE_LAMPORTS: u64 = if cfg!(feature = "platform-fee") {
+    LAMPORTS_PER_SOL / 10
+} else {
+    0
+};
+
+/// Wallet receiving platform fee. Hosted deployments replace it with their own wallet
+pub const PLATFORM_WALLET: Pubkey =
+    solana_program::pubkey!("7C253A2cKgmM53ifopZU28mpAtAfj61hv5JXenKYhTds");
+
+/// Share of every MintOne payment routed to protocol treasury, in basis points.
+/// Taken only by builds with `protocol-fee` feature
+pub const PROTOCOL_FEE_BPS: u16 = if cfg!(feature = "protocol-fee") {
+    250
+} else {
+    0
+};
+
+/// Wallet receiving protocol fee. Licensed deployments replace it with their own wallet
+pub const PROTOCOL_TREASURY: Pubkey =
+    solana_program::pubkey!("9ALtg59TFBRThDpbwaMTTBTdPu2PyR92fZRpuqhSNj1j");
+
+/// Sanity ceiling for airdrop supply
+pub const MAX_AIRDROP_AMOUNT: u64 = 1_000_000;
+
+pub const MAX_REVENUE_SPLITS: usize = 4;
+pub const REVENUE_SPLITS_LEN: usize = RevenueSplit::LEN * MAX_REVENUE_SPLITS;
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct RevenueSplit {
+    pub wallet: Pubkey,
+    pub basis_points: u16,
+}
 
 #[derive(Debug, Copy, Clone)]
 pub struct AirdropConfig {
@@ -15,21 +79,111 @@ pub struct AirdropConfig {
     pub airdrop_authority: Pubkey,
     pub airdrop_index: u64,
     pub airdrop_amount: u64,
-    pub metadata_prefix: [u8; 32],
+    pub base_uri_len: u8,
+    pub base_uri: [u8; MAX_URI_LENGTH],
     pub symbol: [u8; 8],
     pub airdrop_users: u64,
     pub revenues_wallet: Pubkey,
     pub admin_account: Pubkey,
     pub price: u64,
+    pub revealed: bool,
+    pub placeholder_uri: [u8; 32],
+    pub auto_lock: bool,
+    pub revenue_splits: [RevenueSplit; MAX_REVENUE_SPLITS],
+    pub charge_payer: bool,
+    pub mint_authority_bump: u8,
+    pub max_mints_per_tx: u8,
+    pub nonce: u64,
+    pub trait_count: u8,
+    pub mint_cooldown_secs: u64,
+    pub referral_bps: u16,
+    pub bot_tax_lamports: u64,
+    pub name: [u8; 16],
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub stage_count: u8,
+    pub expected_decimals: u8,
+    pub escrow_timeout_secs: u64,
+    pub mint_quantity: u64,
+    pub registration_fee: u64,
+    pub registered_count: u64,
+    pub fairness_bps: u16,
+    pub raffle: bool,
+    pub raffle_seed: [u8; 32],
+    pub winners_count: u64,
+    pub is_active: bool,
+    pub price_usd_cents: u64,
+    pub pyth_price_account: Pubkey,
+    pub max_price_age_slots: u64,
+    pub finalized: bool,
+    pub gifted_count: u64,
+    pub total_revenue: u64,
+    pub final_minted: u64,
+    pub final_gifted: u64,
+    pub final_registered: u64,
+    pub final_revenue: u64,
+    pub require_registration: bool,
+    pub deny_cpi_mint: bool,
+    pub min_payer_balance: u64,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 pub struct AirdropUserData {
     pub initialized: bool,
     pub airdrop: Pubkey,
     pub user: Pubkey,
     pub mints_amount: u64,
     pub locked_till: u64,
+    pub bump: u8,
+    pub nonce: u64,
+    pub registration_index: u64,
+    pub blocked: bool,
+}
+
+/// Sale stage of airdrop with own price, supply cap and mint window.
+/// While airdrop has stages, every mint goes through the active one
+#[derive(Debug, Copy, Clone)]
+pub struct AirdropStage {
+    pub initialized: bool,
+    pub airdrop: Pubkey,
+    pub stage_index: u8,
+    pub bump: u8,
+    pub price: u64,
+    pub supply_cap: u64,
+    pub minted: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    /// Allowlist merkle root, all zeroes for no allowlist
+    pub merkle_root: [u8; 32],
+    /// Most tokens user may have minted in total by the end of this stage, zero for no limit
+    pub per_user_limit: u64,
+}
+
+/// Token minted into escrow and waiting for its claimant. Owns the escrow token account
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AirdropEscrow {
+    pub initialized: bool,
+    pub airdrop: Pubkey,
+    /// Airdrop index the token was minted at
+    pub purchase_index: u64,
+    pub bump: u8,
+    pub claimant: Pubkey,
+    pub mint: Pubkey,
+    /// Payer of the mint. Receives escrow rent back once escrow is closed
+    pub payer: Pubkey,
+    pub created_ts: i64,
+}
+
+/// Queue of deposited preminted NFTs. Header is followed by mint addresses, 32 bytes each,
+/// and account grows with every deposit. Mints before cursor are already claimed.
+/// Owns vault token accounts of queued mints
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PremintVault {
+    pub initialized: bool,
+    pub airdrop: Pubkey,
+    pub bump: u8,
+    pub cursor: u32,
+    pub len: u32,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -44,7 +198,56 @@ impl IsInitialized for AirdropConfig {
 }
 
 impl Pack for AirdropConfig {
-    const LEN: usize = 1 + 32 + 8 + 8 + 32 + 8 + 8 + 32 + 32 + 8;
+    const LEN: usize = 1
+        + 32
+        + 8
+        + 8
+        + 1
+        + MAX_URI_LENGTH
+        + 8
+        + 8
+        + 32
+        + 32
+        + 8
+        + 1
+        + 32
+        + 1
+        + REVENUE_SPLITS_LEN
+        + 1
+        + 1
+        + 1
+        + 8
+        + 1
+        + 8
+        + 2
+        + 8
+        + 16
+        + 8
+        + 8
+        + 1
+        + 1
+        + 8
+        + 8
+        + 8
+        + 8
+        + 2
+        + 1
+        + 32
+        + 8
+        + 1
+        + 8
+        + 32
+        + 8
+        + 1
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 1
+        + 1
+        + 8;
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let dst = array_mut_ref![dst, 0, AirdropConfig::LEN];
@@ -54,24 +257,156 @@ impl Pack for AirdropConfig {
             airdrop_authority,
             airdrop_index,
             airdrop_amount,
-            metadata_prefix,
+            base_uri_len,
+            base_uri,
             symbol,
             airdrop_users,
             revenues_wallet,
             admin_account,
             price,
-        ) = mut_array_refs![dst, 1, 32, 8, 8, 32, 8, 8, 32, 32, 8];
+            revealed,
+            placeholder_uri,
+            auto_lock,
+            revenue_splits,
+            charge_payer,
+            mint_authority_bump,
+            max_mints_per_tx,
+            nonce,
+            trait_count,
+            mint_cooldown_secs,
+            referral_bps,
+            bot_tax_lamports,
+            name,
+            start_ts,
+            end_ts,
+            stage_count,
+            expected_decimals,
+            escrow_timeout_secs,
+            mint_quantity,
+            registration_fee,
+            registered_count,
+            fairness_bps,
+            raffle,
+            raffle_seed,
+            winners_count,
+            is_active,
+            price_usd_cents,
+            pyth_price_account,
+            max_price_age_slots,
+            finalized,
+            gifted_count,
+            total_revenue,
+            final_minted,
+            final_gifted,
+            final_registered,
+            final_revenue,
+            require_registration,
+            deny_cpi_mint,
+            min_payer_balance,
+        ) = mut_array_refs![
+            dst,
+            1,
+            32,
+            8,
+            8,
+            1,
+            MAX_URI_LENGTH,
+            8,
+            8,
+            32,
+            32,
+            8,
+            1,
+            32,
+            1,
+            REVENUE_SPLITS_LEN,
+            1,
+            1,
+            1,
+            8,
+            1,
+            8,
+            2,
+            8,
+            16,
+            8,
+            8,
+            1,
+            1,
+            8,
+            8,
+            8,
+            8,
+            2,
+            1,
+            32,
+            8,
+            1,
+            8,
+            32,
+            8,
+            1,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            1,
+            1,
+            8
+        ];
 
         initialized[0] = self.initialized as u8;
         airdrop_authority.copy_from_slice(&self.airdrop_authority.to_bytes());
         airdrop_index.copy_from_slice(&self.airdrop_index.to_le_bytes());
         airdrop_amount.copy_from_slice(&self.airdrop_amount.to_le_bytes());
-        metadata_prefix.copy_from_slice(&self.metadata_prefix);
+        base_uri_len[0] = self.base_uri_len;
+        base_uri.copy_from_slice(&self.base_uri);
         symbol.copy_from_slice(&self.symbol);
         airdrop_users.copy_from_slice(&self.airdrop_users.to_le_bytes());
         revenues_wallet.copy_from_slice(&self.revenues_wallet.to_bytes());
         admin_account.copy_from_slice(&self.admin_account.to_bytes());
         price.copy_from_slice(&self.price.to_le_bytes());
+        revealed[0] = self.revealed as u8;
+        placeholder_uri.copy_from_slice(&self.placeholder_uri);
+        auto_lock[0] = self.auto_lock as u8;
+        pack_revenue_splits(&self.revenue_splits, revenue_splits);
+        charge_payer[0] = self.charge_payer as u8;
+        mint_authority_bump[0] = self.mint_authority_bump;
+        max_mints_per_tx[0] = self.max_mints_per_tx;
+        nonce.copy_from_slice(&self.nonce.to_le_bytes());
+        trait_count[0] = self.trait_count;
+        mint_cooldown_secs.copy_from_slice(&self.mint_cooldown_secs.to_le_bytes());
+        referral_bps.copy_from_slice(&self.referral_bps.to_le_bytes());
+        bot_tax_lamports.copy_from_slice(&self.bot_tax_lamports.to_le_bytes());
+        name.copy_from_slice(&self.name);
+        start_ts.copy_from_slice(&self.start_ts.to_le_bytes());
+        end_ts.copy_from_slice(&self.end_ts.to_le_bytes());
+        stage_count[0] = self.stage_count;
+        expected_decimals[0] = self.expected_decimals;
+        escrow_timeout_secs.copy_from_slice(&self.escrow_timeout_secs.to_le_bytes());
+        mint_quantity.copy_from_slice(&self.mint_quantity.to_le_bytes());
+        registration_fee.copy_from_slice(&self.registration_fee.to_le_bytes());
+        registered_count.copy_from_slice(&self.registered_count.to_le_bytes());
+        fairness_bps.copy_from_slice(&self.fairness_bps.to_le_bytes());
+        raffle[0] = self.raffle as u8;
+        raffle_seed.copy_from_slice(&self.raffle_seed);
+        winners_count.copy_from_slice(&self.winners_count.to_le_bytes());
+        is_active[0] = self.is_active as u8;
+        price_usd_cents.copy_from_slice(&self.price_usd_cents.to_le_bytes());
+        pyth_price_account.copy_from_slice(&self.pyth_price_account.to_bytes());
+        max_price_age_slots.copy_from_slice(&self.max_price_age_slots.to_le_bytes());
+        finalized[0] = self.finalized as u8;
+        gifted_count.copy_from_slice(&self.gifted_count.to_le_bytes());
+        total_revenue.copy_from_slice(&self.total_revenue.to_le_bytes());
+        final_minted.copy_from_slice(&self.final_minted.to_le_bytes());
+        final_gifted.copy_from_slice(&self.final_gifted.to_le_bytes());
+        final_registered.copy_from_slice(&self.final_registered.to_le_bytes());
+        final_revenue.copy_from_slice(&self.final_revenue.to_le_bytes());
+        require_registration[0] = self.require_registration as u8;
+        deny_cpi_mint[0] = self.deny_cpi_mint as u8;
+        min_payer_balance.copy_from_slice(&self.min_payer_balance.to_le_bytes());
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, solana_program::program_error::ProgramError> {
@@ -82,13 +417,105 @@ impl Pack for AirdropConfig {
             airdrop_authority_src,
             airdrop_index_src,
             airdrop_amount_src,
-            metadata_prefix_src,
+            base_uri_len_src,
+            base_uri_src,
             symbol_src,
             airdrop_users_src,
             revenues_wallet_src,
             admin_account_src,
             price_src,
-        ) = array_refs![src, 1, 32, 8, 8, 32, 8, 8, 32, 32, 8];
+            revealed_src,
+            placeholder_uri_src,
+            auto_lock_src,
+            revenue_splits_src,
+            charge_payer_src,
+            mint_authority_bump_src,
+            max_mints_per_tx_src,
+            nonce_src,
+            trait_count_src,
+            mint_cooldown_secs_src,
+            referral_bps_src,
+            bot_tax_lamports_src,
+            name_src,
+            start_ts_src,
+            end_ts_src,
+            stage_count_src,
+            expected_decimals_src,
+            escrow_timeout_secs_src,
+            mint_quantity_src,
+            registration_fee_src,
+            registered_count_src,
+            fairness_bps_src,
+            raffle_src,
+            raffle_seed_src,
+            winners_count_src,
+            is_active_src,
+            price_usd_cents_src,
+            pyth_price_account_src,
+            max_price_age_slots_src,
+            finalized_src,
+            gifted_count_src,
+            total_revenue_src,
+            final_minted_src,
+            final_gifted_src,
+            final_registered_src,
+            final_revenue_src,
+            require_registration_src,
+            deny_cpi_mint_src,
+            min_payer_balance_src,
+        ) = array_refs![
+            src,
+            1,
+            32,
+            8,
+            8,
+            1,
+            MAX_URI_LENGTH,
+            8,
+            8,
+            32,
+            32,
+            8,
+            1,
+            32,
+            1,
+            REVENUE_SPLITS_LEN,
+            1,
+            1,
+            1,
+            8,
+            1,
+            8,
+            2,
+            8,
+            16,
+            8,
+            8,
+            1,
+            1,
+            8,
+            8,
+            8,
+            8,
+            2,
+            1,
+            32,
+            8,
+            1,
+            8,
+            32,
+            8,
+            1,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            1,
+            1,
+            8
+        ];
 
         let initialized = match initialized_src {
             [0] => false,
@@ -99,30 +526,190 @@ impl Pack for AirdropConfig {
         let airdrop_authority = Pubkey::new_from_array(*airdrop_authority_src);
         let airdrop_index = u64::from_le_bytes(*airdrop_index_src);
         let airdrop_amount = u64::from_le_bytes(*airdrop_amount_src);
-        let symbol = symbol_src.clone();
-        let metadata_prefix = metadata_prefix_src.clone();
+        let symbol = *symbol_src;
+        let base_uri_len = base_uri_len_src[0];
+        if base_uri_len as usize > MAX_URI_LENGTH {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let base_uri = *base_uri_src;
         let airdrop_users = u64::from_le_bytes(*airdrop_users_src);
         let revenues_wallet = Pubkey::new_from_array(*revenues_wallet_src);
         let admin_account = Pubkey::new_from_array(*admin_account_src);
         let price = u64::from_le_bytes(*price_src);
 
+        let revealed = match revealed_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let placeholder_uri = *placeholder_uri_src;
+
+        let auto_lock = match auto_lock_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let revenue_splits = unpack_revenue_splits(revenue_splits_src);
+
+        let charge_payer = match charge_payer_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let mint_authority_bump = mint_authority_bump_src[0];
+
+        let max_mints_per_tx = max_mints_per_tx_src[0];
+
+        let nonce = u64::from_le_bytes(*nonce_src);
+
+        let trait_count = trait_count_src[0];
+
+        let mint_cooldown_secs = u64::from_le_bytes(*mint_cooldown_secs_src);
+
+        let referral_bps = u16::from_le_bytes(*referral_bps_src);
+
+        let bot_tax_lamports = u64::from_le_bytes(*bot_tax_lamports_src);
+
+        let name = *name_src;
+
+        let start_ts = i64::from_le_bytes(*start_ts_src);
+
+        let end_ts = i64::from_le_bytes(*end_ts_src);
+
+        let stage_count = stage_count_src[0];
+
+        let expected_decimals = expected_decimals_src[0];
+
+        let escrow_timeout_secs = u64::from_le_bytes(*escrow_timeout_secs_src);
+
+        let mint_quantity = u64::from_le_bytes(*mint_quantity_src);
+
+        let registration_fee = u64::from_le_bytes(*registration_fee_src);
+
+        let registered_count = u64::from_le_bytes(*registered_count_src);
+
+        let fairness_bps = u16::from_le_bytes(*fairness_bps_src);
+
+        let raffle = match raffle_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let raffle_seed = *raffle_seed_src;
+
+        let winners_count = u64::from_le_bytes(*winners_count_src);
+
+        let is_active = match is_active_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let price_usd_cents = u64::from_le_bytes(*price_usd_cents_src);
+
+        let pyth_price_account = Pubkey::new_from_array(*pyth_price_account_src);
+
+        let max_price_age_slots = u64::from_le_bytes(*max_price_age_slots_src);
+
+        let finalized = match finalized_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let gifted_count = u64::from_le_bytes(*gifted_count_src);
+
+        let total_revenue = u64::from_le_bytes(*total_revenue_src);
+
+        let final_minted = u64::from_le_bytes(*final_minted_src);
+
+        let final_gifted = u64::from_le_bytes(*final_gifted_src);
+
+        let final_registered = u64::from_le_bytes(*final_registered_src);
+
+        let final_revenue = u64::from_le_bytes(*final_revenue_src);
+
+        let require_registration = match require_registration_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let deny_cpi_mint = match deny_cpi_mint_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let min_payer_balance = u64::from_le_bytes(*min_payer_balance_src);
+
         Ok(AirdropConfig {
             initialized,
             airdrop_authority,
             airdrop_index,
             airdrop_amount,
             symbol,
-            metadata_prefix,
+            base_uri_len,
+            base_uri,
             airdrop_users,
             revenues_wallet,
             admin_account,
             price,
+            revealed,
+            placeholder_uri,
+            auto_lock,
+            revenue_splits,
+            charge_payer,
+            mint_authority_bump,
+            max_mints_per_tx,
+            nonce,
+            trait_count,
+            mint_cooldown_secs,
+            referral_bps,
+            bot_tax_lamports,
+            name,
+            start_ts,
+            end_ts,
+            stage_count,
+            expected_decimals,
+            escrow_timeout_secs,
+            mint_quantity,
+            registration_fee,
+            registered_count,
+            fairness_bps,
+            raffle,
+            raffle_seed,
+            winners_count,
+            is_active,
+            price_usd_cents,
+            pyth_price_account,
+            max_price_age_slots,
+            finalized,
+            gifted_count,
+            total_revenue,
+            final_minted,
+            final_gifted,
+            final_registered,
+            final_revenue,
+            require_registration,
+            deny_cpi_mint,
+            min_payer_balance,
         })
     }
 }
 
 impl AirdropConfig {
     pub fn unpack_from_account(account: &AccountInfo) -> Result<AirdropConfig, ProgramError> {
+        // Spoofed or truncated account must not be sliced past its end
+        if account.data_len() < Self::LEN {
+            return Err(AirdropError::InvalidAccountData.into());
+        }
+
         Self::unpack_unchecked(&account.data.borrow())
     }
 
@@ -132,8 +719,95 @@ impl AirdropConfig {
     ) -> Result<(), ProgramError> {
         Self::pack(state, &mut account.data.borrow_mut())
     }
+
+    /// Offset of airdrop index, which every mint bumps without unpacking whole account.
+    /// It follows initialized flag and airdrop authority
+    pub const AIRDROP_INDEX_OFFSET: usize = 1 + 32;
+
+    /// Offset of gifted count, which paid and gift mints bump in place. It is followed by
+    /// total revenue, four final stats, two flags and min payer balance
+    pub const GIFTED_COUNT_OFFSET: usize = Self::LEN - (8 + 8 + 4 * 8 + 1 + 1 + 8);
+
+    /// Offset of total revenue, which follows gifted count
+    pub const TOTAL_REVENUE_OFFSET: usize = Self::GIFTED_COUNT_OFFSET + 8;
+
+    /// Single token without decimals is a 1/1 NFT, it gets a master edition when minted
+    pub fn creates_master_edition(&self) -> bool {
+        self.mint_quantity == 1 && self.expected_decimals == 0
+    }
+
+    /// Base URI revealed metadata URIs are built from
+    pub fn base_uri(&self) -> &[u8] {
+        &self.base_uri[..self.base_uri_len as usize]
+    }
+
+    /// Reads airdrop index in place
+    pub fn read_airdrop_index(account: &AccountInfo) -> Result<u64, ProgramError> {
+        let data = account.data.borrow();
+
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(u64::from_le_bytes(*array_ref![
+            data,
+            Self::AIRDROP_INDEX_OFFSET,
+            8
+        ]))
+    }
+
+    /// Increments airdrop index in place and returns the new value
+    pub fn increment_airdrop_index(account: &AccountInfo) -> Result<u64, ProgramError> {
+        let mut data = account.data.borrow_mut();
+
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let airdrop_index = array_mut_ref![data, Self::AIRDROP_INDEX_OFFSET, 8];
+        let value = u64::from_le_bytes(*airdrop_index)
+            .checked_add(1)
+            .ok_or(AirdropError::Overflow)?;
+
+        airdrop_index.copy_from_slice(&value.to_le_bytes());
+
+        Ok(value)
+    }
+
+    /// Adds mint revenue and gift to statistics in place
+    pub fn add_mint_stats(
+        account: &AccountInfo,
+        revenue: u64,
+        gift: bool,
+    ) -> Result<(), ProgramError> {
+        let mut data = account.data.borrow_mut();
+
+        if data.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let gifted_count = array_mut_ref![data, Self::GIFTED_COUNT_OFFSET, 8];
+        let value = u64::from_le_bytes(*gifted_count)
+            .checked_add(gift as u64)
+            .ok_or(AirdropError::Overflow)?;
+        gifted_count.copy_from_slice(&value.to_le_bytes());
+
+        let total_revenue = array_mut_ref![data, Self::TOTAL_REVENUE_OFFSET, 8];
+        let value = u64::from_le_bytes(*total_revenue).saturating_add(revenue);
+        total_revenue.copy_from_slice(&value.to_le_bytes());
+
+        Ok(())
+    }
 }
 
+// Account sizes are allocated from `Pack::LEN`. Bump these together with the layout
+const _: () = assert!(AirdropConfig::LEN == 755);
+const _: () = assert!(AirdropUserData::LEN == 99);
+const _: () = assert!(AirdropStage::LEN == 115);
+const _: () = assert!(AirdropEscrow::LEN == 146);
+const _: () = assert!(PremintVault::LEN == 42);
+const _: () = assert!(MintAuthority::LEN == 0);
+
 impl Sealed for AirdropUserData {}
 
 impl IsInitialized for AirdropUserData {
@@ -143,26 +817,48 @@ impl IsInitialized for AirdropUserData {
 }
 
 impl Pack for AirdropUserData {
-    const LEN: usize = 1 + 32 + 32 + 8 + 8;
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 1 + 8 + 8 + 1;
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let dst = array_mut_ref![dst, 0, AirdropUserData::LEN];
 
-        let (initialized, airdrop, user, mints_amount, locked_till) =
-            mut_array_refs![dst, 1, 32, 32, 8, 8];
+        let (
+            initialized,
+            airdrop,
+            user,
+            mints_amount,
+            locked_till,
+            bump,
+            nonce,
+            registration_index,
+            blocked,
+        ) = mut_array_refs![dst, 1, 32, 32, 8, 8, 1, 8, 8, 1];
 
         initialized[0] = self.initialized as u8;
         airdrop.copy_from_slice(&self.airdrop.to_bytes());
         user.copy_from_slice(&self.user.to_bytes());
         mints_amount.copy_from_slice(&self.mints_amount.to_le_bytes());
         locked_till.copy_from_slice(&self.locked_till.to_le_bytes());
+        bump[0] = self.bump;
+        nonce.copy_from_slice(&self.nonce.to_le_bytes());
+        registration_index.copy_from_slice(&self.registration_index.to_le_bytes());
+        blocked[0] = self.blocked as u8;
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
         let src = array_ref![src, 0, AirdropUserData::LEN];
 
-        let (initialized_src, airdrop_src, user_src, mints_amount_src, locked_till_src) =
-            array_refs![src, 1, 32, 32, 8, 8];
+        let (
+            initialized_src,
+            airdrop_src,
+            user_src,
+            mints_amount_src,
+            locked_till_src,
+            bump_src,
+            nonce_src,
+            registration_index_src,
+            blocked_src,
+        ) = array_refs![src, 1, 32, 32, 8, 8, 1, 8, 8, 1];
 
         let initialized = match initialized_src {
             [0] => false,
@@ -175,12 +871,28 @@ impl Pack for AirdropUserData {
         let mints_amount = u64::from_le_bytes(*mints_amount_src);
         let locked_till = u64::from_le_bytes(*locked_till_src);
 
+        let bump = bump_src[0];
+
+        let nonce = u64::from_le_bytes(*nonce_src);
+
+        let registration_index = u64::from_le_bytes(*registration_index_src);
+
+        let blocked = match blocked_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
         Ok(AirdropUserData {
             initialized,
             airdrop,
             user,
             mints_amount,
             locked_till,
+            bump,
+            nonce,
+            registration_index,
+            blocked,
         })
     }
 }
@@ -190,6 +902,23 @@ impl AirdropUserData {
         Self::unpack_unchecked(&account.data.borrow())
     }
 
+    /// Unpacks user data, rejecting zero-filled accounts that were never registered
+    pub fn unpack_initialized(src: &[u8]) -> Result<AirdropUserData, ProgramError> {
+        let user_data = Self::unpack_unchecked(src)?;
+
+        if !user_data.is_initialized() {
+            return Err(AirdropError::Uninitialized.into());
+        }
+
+        Ok(user_data)
+    }
+
+    pub fn unpack_initialized_from_account(
+        account: &AccountInfo,
+    ) -> Result<AirdropUserData, ProgramError> {
+        Self::unpack_initialized(&account.data.borrow())
+    }
+
     pub fn pack_into_account(
         state: AirdropUserData,
         account: &AccountInfo,
@@ -198,6 +927,315 @@ impl AirdropUserData {
     }
 }
 
+impl Sealed for AirdropStage {}
+
+impl IsInitialized for AirdropStage {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Pack for AirdropStage {
+    const LEN: usize = 1 + 32 + 1 + 1 + 8 + 8 + 8 + 8 + 8 + 32 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, AirdropStage::LEN];
+
+        let (
+            initialized,
+            airdrop,
+            stage_index,
+            bump,
+            price,
+            supply_cap,
+            minted,
+            start_ts,
+            end_ts,
+            merkle_root,
+            per_user_limit,
+        ) = mut_array_refs![dst, 1, 32, 1, 1, 8, 8, 8, 8, 8, 32, 8];
+
+        initialized[0] = self.initialized as u8;
+        airdrop.copy_from_slice(&self.airdrop.to_bytes());
+        stage_index[0] = self.stage_index;
+        bump[0] = self.bump;
+        price.copy_from_slice(&self.price.to_le_bytes());
+        supply_cap.copy_from_slice(&self.supply_cap.to_le_bytes());
+        minted.copy_from_slice(&self.minted.to_le_bytes());
+        start_ts.copy_from_slice(&self.start_ts.to_le_bytes());
+        end_ts.copy_from_slice(&self.end_ts.to_le_bytes());
+        merkle_root.copy_from_slice(&self.merkle_root);
+        per_user_limit.copy_from_slice(&self.per_user_limit.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, AirdropStage::LEN];
+
+        let (
+            initialized_src,
+            airdrop_src,
+            stage_index_src,
+            bump_src,
+            price_src,
+            supply_cap_src,
+            minted_src,
+            start_ts_src,
+            end_ts_src,
+            merkle_root_src,
+            per_user_limit_src,
+        ) = array_refs![src, 1, 32, 1, 1, 8, 8, 8, 8, 8, 32, 8];
+
+        let initialized = match initialized_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(AirdropStage {
+            initialized,
+            airdrop: Pubkey::new_from_array(*airdrop_src),
+            stage_index: stage_index_src[0],
+            bump: bump_src[0],
+            price: u64::from_le_bytes(*price_src),
+            supply_cap: u64::from_le_bytes(*supply_cap_src),
+            minted: u64::from_le_bytes(*minted_src),
+            start_ts: i64::from_le_bytes(*start_ts_src),
+            end_ts: i64::from_le_bytes(*end_ts_src),
+            merkle_root: *merkle_root_src,
+            per_user_limit: u64::from_le_bytes(*per_user_limit_src),
+        })
+    }
+}
+
+impl AirdropStage {
+    pub fn unpack_from_account(account: &AccountInfo) -> Result<AirdropStage, ProgramError> {
+        Self::unpack_unchecked(&account.data.borrow())
+    }
+
+    pub fn pack_into_account(
+        state: AirdropStage,
+        account: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        Self::pack(state, &mut account.data.borrow_mut())
+    }
+
+    pub fn has_allowlist(&self) -> bool {
+        self.merkle_root != [0; 32]
+    }
+
+    /// Whether time windows of two stages share any moment
+    pub fn overlaps(&self, other: &AirdropStage) -> bool {
+        self.start_ts < other.end_ts && other.start_ts < self.end_ts
+    }
+}
+
+impl Sealed for AirdropEscrow {}
+
+impl IsInitialized for AirdropEscrow {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Pack for AirdropEscrow {
+    const LEN: usize = 1 + 32 + 8 + 1 + 32 + 32 + 32 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, AirdropEscrow::LEN];
+
+        let (initialized, airdrop, purchase_index, bump, claimant, mint, payer, created_ts) =
+            mut_array_refs![dst, 1, 32, 8, 1, 32, 32, 32, 8];
+
+        initialized[0] = self.initialized as u8;
+        airdrop.copy_from_slice(&self.airdrop.to_bytes());
+        purchase_index.copy_from_slice(&self.purchase_index.to_le_bytes());
+        bump[0] = self.bump;
+        claimant.copy_from_slice(&self.claimant.to_bytes());
+        mint.copy_from_slice(&self.mint.to_bytes());
+        payer.copy_from_slice(&self.payer.to_bytes());
+        created_ts.copy_from_slice(&self.created_ts.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, AirdropEscrow::LEN];
+
+        let (
+            initialized_src,
+            airdrop_src,
+            purchase_index_src,
+            bump_src,
+            claimant_src,
+            mint_src,
+            payer_src,
+            created_ts_src,
+        ) = array_refs![src, 1, 32, 8, 1, 32, 32, 32, 8];
+
+        let initialized = match initialized_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(AirdropEscrow {
+            initialized,
+            airdrop: Pubkey::new_from_array(*airdrop_src),
+            purchase_index: u64::from_le_bytes(*purchase_index_src),
+            bump: bump_src[0],
+            claimant: Pubkey::new_from_array(*claimant_src),
+            mint: Pubkey::new_from_array(*mint_src),
+            payer: Pubkey::new_from_array(*payer_src),
+            created_ts: i64::from_le_bytes(*created_ts_src),
+        })
+    }
+}
+
+impl AirdropEscrow {
+    /// Unpacks escrow, failing with `Uninitialized` for closed or never created escrow
+    pub fn unpack_initialized_from_account(
+        account: &AccountInfo,
+    ) -> Result<AirdropEscrow, ProgramError> {
+        let escrow = Self::unpack_unchecked(&account.data.borrow())?;
+
+        if !escrow.initialized {
+            return Err(AirdropError::Uninitialized.into());
+        }
+
+        Ok(escrow)
+    }
+
+    pub fn pack_into_account(
+        state: AirdropEscrow,
+        account: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        Self::pack(state, &mut account.data.borrow_mut())
+    }
+}
+
+impl Sealed for PremintVault {}
+
+impl IsInitialized for PremintVault {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Pack for PremintVault {
+    const LEN: usize = 1 + 32 + 1 + 4 + 4;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, PremintVault::LEN];
+
+        let (initialized, airdrop, bump, cursor, len) = mut_array_refs![dst, 1, 32, 1, 4, 4];
+
+        initialized[0] = self.initialized as u8;
+        airdrop.copy_from_slice(&self.airdrop.to_bytes());
+        bump[0] = self.bump;
+        cursor.copy_from_slice(&self.cursor.to_le_bytes());
+        len.copy_from_slice(&self.len.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, PremintVault::LEN];
+
+        let (initialized_src, airdrop_src, bump_src, cursor_src, len_src) =
+            array_refs![src, 1, 32, 1, 4, 4];
+
+        let initialized = match initialized_src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(PremintVault {
+            initialized,
+            airdrop: Pubkey::new_from_array(*airdrop_src),
+            bump: bump_src[0],
+            cursor: u32::from_le_bytes(*cursor_src),
+            len: u32::from_le_bytes(*len_src),
+        })
+    }
+}
+
+impl PremintVault {
+    /// Account size holding `len` queued mints
+    pub fn space(len: u32) -> usize {
+        Self::LEN + len as usize * 32
+    }
+
+    /// Unpacks header only, queued mints are read with `mint_at`
+    pub fn unpack_from_account(account: &AccountInfo) -> Result<PremintVault, ProgramError> {
+        let data = account.data.borrow();
+
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Self::unpack_from_slice(&data[..Self::LEN])
+    }
+
+    pub fn pack_into_account(
+        state: PremintVault,
+        account: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        state.pack_into_slice(&mut account.data.borrow_mut()[..Self::LEN]);
+        Ok(())
+    }
+
+    /// Queued mint at position `index`
+    pub fn mint_at(account: &AccountInfo, index: u32) -> Result<Pubkey, ProgramError> {
+        let offset = Self::space(index);
+        let data = account.data.borrow();
+        let src = data
+            .get(offset..offset + 32)
+            .ok_or(ProgramError::InvalidAccountData)?;
+
+        Ok(Pubkey::new_from_array(*array_ref![src, 0, 32]))
+    }
+
+    /// Next mint to be claimed, None once every deposited mint is claimed
+    pub fn next_mint(&self, account: &AccountInfo) -> Result<Option<Pubkey>, ProgramError> {
+        match self.cursor < self.len {
+            true => Self::mint_at(account, self.cursor).map(Some),
+            false => Ok(None),
+        }
+    }
+}
+
 impl MintAuthority {
     pub const LEN: usize = 0;
 }
+
+impl RevenueSplit {
+    pub const LEN: usize = 32 + 2;
+
+    pub fn is_used(&self) -> bool {
+        self.basis_points > 0
+    }
+}
+
+pub fn pack_revenue_splits(
+    splits: &[RevenueSplit; MAX_REVENUE_SPLITS],
+    dst: &mut [u8; REVENUE_SPLITS_LEN],
+) {
+    for (split, chunk) in splits.iter().zip(dst.chunks_exact_mut(RevenueSplit::LEN)) {
+        let chunk = array_mut_ref![chunk, 0, RevenueSplit::LEN];
+        let (wallet, basis_points) = mut_array_refs![chunk, 32, 2];
+
+        wallet.copy_from_slice(&split.wallet.to_bytes());
+        basis_points.copy_from_slice(&split.basis_points.to_le_bytes());
+    }
+}
+
+pub fn unpack_revenue_splits(src: &[u8; REVENUE_SPLITS_LEN]) -> [RevenueSplit; MAX_REVENUE_SPLITS] {
+    let mut splits = [RevenueSplit::default(); MAX_REVENUE_SPLITS];
+
+    for (split, chunk) in splits.iter_mut().zip(src.chunks_exact(RevenueSplit::LEN)) {
+        let chunk = array_ref![chunk, 0, RevenueSplit::LEN];
+        let (wallet_src, basis_points_src) = array_refs![chunk, 32, 2];
+
+        split.wallet = Pubkey::new_from_array(*wallet_src);
+        split.basis_points = u16::from_le_bytes(*basis_points_src);
+    }
+
+    splits
+}
@@ -1,37 +1,96 @@
+use std::convert::TryFrom;
+
+use arrayref::{array_ref, array_refs};
 use metaplex_token_metadata::state::Creator;
 use solana_program::{
     account_info::AccountInfo,
     clock::Clock,
     entrypoint::ProgramResult,
+    hash::hashv,
     msg,
-    program::{invoke, invoke_signed},
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
+    program_option::COption,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
     rent::Rent,
     system_instruction,
+    sysvar::Sysvar,
 };
 
-use crate::state::{AirdropConfig, AirdropUserData, MintAuthority, MINT_AUTHORITY, USER_DATA};
+use crate::{
+    error::AirdropError,
+    event::MintEvent,
+    naming::{build_mint_name, build_symbol, build_trait_mint_name, revealed_uri},
+    pda::{
+        airdrop_config_seeds, airdrop_user_data_seeds, escrow_seeds, mint_authority_seeds,
+        stage_seeds, vault_seeds,
+    },
+    return_data::{MintPreview, MintReturnData},
+    state::{
+        AirdropConfig, AirdropEscrow, AirdropStage, AirdropUserData, MintAuthority, PremintVault,
+        RevenueSplit, MAX_AIRDROP_AMOUNT, MAX_REVENUE_SPLITS, MAX_URI_LENGTH,
+        PLATFORM_FEE_LAMPORTS, PROTOCOL_FEE_BPS,
+    },
+};
 
+#[allow(clippy::too_many_arguments)]
 pub fn process_initialize_airdrop_logic<'a>(
-    airdrop_account: &AccountInfo,
+    airdrop_account: &'a AccountInfo<'a>,
     airdrop_authority: &AccountInfo,
     mint_authority: &'a AccountInfo<'a>,
     revenues_account: &AccountInfo,
     admin_account: &AccountInfo,
     fee_payer: &'a AccountInfo<'a>,
     airdrop_amount: u64,
-    metadata_prefix: [u8; 32],
+    base_uri: &[u8],
     symbol: [u8; 8],
     price: u64,
+    placeholder_uri: [u8; 32],
+    auto_lock: bool,
+    revenue_splits: [RevenueSplit; MAX_REVENUE_SPLITS],
+    charge_payer: bool,
+    max_mints_per_tx: u8,
+    name: [u8; 16],
+    airdrop_config_bump: Option<u8>,
+    trait_count: u8,
+    mint_cooldown_secs: u64,
+    referral_bps: u16,
+    bot_tax_lamports: u64,
+    expected_decimals: u8,
+    escrow_timeout_secs: u64,
+    mint_quantity: u64,
+    registration_fee: u64,
+    fairness_bps: u16,
+    raffle: bool,
+    require_registration: bool,
+    deny_cpi_mint: bool,
+    min_payer_balance: u64,
     program_id: &Pubkey,
     rent: Rent,
     mint_authority_bump: u8,
 ) -> ProgramResult {
+    if let Some(airdrop_config_bump) = airdrop_config_bump {
+        let airdrop_config_bump_seed = [airdrop_config_bump];
+        let airdrop_config_seed =
+            airdrop_config_seeds(airdrop_authority.key, &name, &airdrop_config_bump_seed);
+
+        if airdrop_account.owner != program_id {
+            debug_msg!("Initialize airdrop config account");
+            create_pda_account(
+                fee_payer,
+                airdrop_account,
+                AirdropConfig::LEN,
+                program_id,
+                &rent,
+                &airdrop_config_seed,
+            )?;
+        }
+    }
+
     let airdrop_data = AirdropConfig::unpack_from_account(airdrop_account)?;
 
-    msg!("Check if airdrop data already initialized");
+    debug_msg!("Check if airdrop data already initialized");
     if airdrop_data.is_initialized() {
         return Err(ProgramError::AccountAlreadyInitialized);
     }
@@ -41,34 +100,77 @@ pub fn process_initialize_airdrop_logic<'a>(
         airdrop_authority: *airdrop_authority.key,
         airdrop_index: 0,
         airdrop_amount,
-        metadata_prefix,
+        base_uri_len: base_uri.len() as u8,
+        base_uri: {
+            let mut padded = [0; MAX_URI_LENGTH];
+            padded[..base_uri.len()].copy_from_slice(base_uri);
+            padded
+        },
         symbol,
         airdrop_users: 0,
         admin_account: *admin_account.key,
         revenues_wallet: *revenues_account.key,
         price,
+        // Airdrop without placeholder is revealed from the start
+        revealed: placeholder_uri[0] == 0,
+        placeholder_uri,
+        auto_lock,
+        revenue_splits,
+        charge_payer,
+        mint_authority_bump,
+        max_mints_per_tx,
+        // Distinguishes this airdrop from previous ones at the same address
+        nonce: Clock::get()?.slot,
+        trait_count,
+        mint_cooldown_secs,
+        referral_bps,
+        bot_tax_lamports,
+        name,
+        // Mint window is open until set with UpdateWindow
+        start_ts: 0,
+        end_ts: 0,
+        stage_count: 0,
+        expected_decimals,
+        registered_count: 0,
+        escrow_timeout_secs,
+        mint_quantity,
+        registration_fee,
+        fairness_bps,
+        raffle,
+        // Set by DrawRaffle
+        raffle_seed: [0; 32],
+        winners_count: 0,
+        is_active: true,
+        // Set with SetUsdPrice
+        price_usd_cents: 0,
+        pyth_price_account: Pubkey::default(),
+        max_price_age_slots: 0,
+        gifted_count: 0,
+        total_revenue: 0,
+        // Set by Finalize
+        finalized: false,
+        final_minted: 0,
+        final_gifted: 0,
+        final_registered: 0,
+        final_revenue: 0,
+        require_registration,
+        deny_cpi_mint,
+        min_payer_balance,
     };
 
     AirdropConfig::pack_into_account(airdrop_data, airdrop_account)?;
 
-    let lamports = rent.minimum_balance(MintAuthority::LEN);
-    let mint_authority_seed = &[
-        MINT_AUTHORITY.as_bytes(),
-        airdrop_account.key.as_ref(),
-        &[mint_authority_bump],
-    ];
+    let mint_authority_bump_seed = [mint_authority_bump];
+    let mint_authority_seed = mint_authority_seeds(airdrop_account.key, &mint_authority_bump_seed);
 
-    msg!("Initialize mint authority");
-    invoke_signed(
-        &system_instruction::create_account(
-            fee_payer.key,
-            mint_authority.key,
-            lamports,
-            MintAuthority::LEN as u64,
-            program_id,
-        ),
-        &[fee_payer.clone(), mint_authority.clone()],
-        &[mint_authority_seed],
+    debug_msg!("Initialize mint authority");
+    create_pda_account(
+        fee_payer,
+        mint_authority,
+        MintAuthority::LEN,
+        program_id,
+        &rent,
+        &mint_authority_seed,
     )?;
 
     Ok(())
@@ -84,26 +186,24 @@ pub fn process_initialize_airdrop_user_account_logic<'a>(
     user_data_account_bump: u8,
 ) -> ProgramResult {
     // Create account
-    msg!("Initialize user airdrop account");
-    let lamports = rent.minimum_balance(AirdropUserData::LEN);
-    let user_data_account_seed = &[
-        USER_DATA.as_bytes(),
-        airdrop_config.key.as_ref(),
-        user.key.as_ref(),
-        &[user_data_account_bump],
-    ];
+    debug_msg!("Initialize user airdrop account");
+    let user_data_account_bump_seed = [user_data_account_bump];
+    let user_data_account_seed =
+        airdrop_user_data_seeds(airdrop_config.key, user.key, &user_data_account_bump_seed);
 
-    invoke_signed(
-        &system_instruction::create_account(
-            fee_payer.key,
-            user_data_account.key,
-            lamports,
-            AirdropUserData::LEN as u64,
+    // Stale user data of previous airdrop is already created, it is only rewritten
+    if user_data_account.owner != program_id {
+        create_pda_account(
+            fee_payer,
+            user_data_account,
+            AirdropUserData::LEN,
             program_id,
-        ),
-        &[fee_payer.clone(), user_data_account.clone()],
-        &[user_data_account_seed],
-    )?;
+            &rent,
+            &user_data_account_seed,
+        )?;
+    }
+
+    let mut airdrop_config_data = AirdropConfig::unpack_from_account(airdrop_config)?;
 
     // Write account data
     let user_account_data = AirdropUserData {
@@ -112,32 +212,140 @@ pub fn process_initialize_airdrop_user_account_logic<'a>(
         user: *user.key,
         mints_amount: 0,
         locked_till: 0,
+        bump: user_data_account_bump,
+        nonce: airdrop_config_data.nonce,
+        registration_index: airdrop_config_data.registered_count,
+        blocked: false,
     };
 
     AirdropUserData::pack_into_account(user_account_data, user_data_account)?;
 
-    // Increase user counter
-    let mut airdrop_config_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+    // Increase user counters. Registered count never goes down, so it orders registrations
     airdrop_config_data.airdrop_users += 1;
+    airdrop_config_data.registered_count = airdrop_config_data
+        .registered_count
+        .checked_add(1)
+        .ok_or(AirdropError::Overflow)?;
     AirdropConfig::pack_into_account(airdrop_config_data, airdrop_config)?;
 
+    msg!(
+        "UserRegistered {} {}",
+        user.key,
+        user_account_data.registration_index
+    );
+
     Ok(())
 }
 
+/// Charged before airdrop accounts are created
+pub fn process_platform_fee_logic<'a>(
+    fee_payer: &'a AccountInfo<'a>,
+    platform_wallet: &'a AccountInfo<'a>,
+) -> ProgramResult {
+    debug_msg!("Transfer platform fee");
+    invoke(
+        &system_instruction::transfer(fee_payer.key, platform_wallet.key, PLATFORM_FEE_LAMPORTS),
+        &[fee_payer.clone(), platform_wallet.clone()],
+    )
+}
+
+/// Charged before user data is created, so registration fails as a whole when payer is short
+pub fn process_registration_fee_logic<'a>(
+    fee_payer: &'a AccountInfo<'a>,
+    revenue_wallet: &'a AccountInfo<'a>,
+    registration_fee: u64,
+) -> ProgramResult {
+    debug_msg!("Transfer registration fee");
+    invoke(
+        &system_instruction::transfer(fee_payer.key, revenue_wallet.key, registration_fee),
+        &[fee_payer.clone(), revenue_wallet.clone()],
+    )
+}
+
+/// Steps of the mint path that call other programs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MintStep {
+    CreateMint,
+    InitializeMint,
+    CreateTokenAccount,
+    MintTo,
+    CreateMetadata,
+    CreateMasterEdition,
+}
+
+impl MintStep {
+    pub fn name(self) -> &'static str {
+        match self {
+            MintStep::CreateMint => "create mint",
+            MintStep::InitializeMint => "init mint",
+            MintStep::CreateTokenAccount => "create ATA",
+            MintStep::MintTo => "mint to",
+            MintStep::CreateMetadata => "create metadata",
+            MintStep::CreateMasterEdition => "create edition",
+        }
+    }
+}
+
+/// Logs the step before the call in verbose builds. On chain a failed call aborts the
+/// transaction before its error gets back, so the last logged step is the failing one then
+#[cfg_attr(not(feature = "verbose-logs"), allow(unused_variables))]
+fn mint_step(step: MintStep, call: impl FnOnce() -> ProgramResult) -> ProgramResult {
+    debug_msg!("MintStep {}", step.name());
+
+    call()
+}
+
+/// Lamports payer spends on rent of accounts created by the mint
+pub fn mint_rent_lamports(
+    rent: &Rent,
+    airdrop_data: &AirdropConfig,
+    creates_token_account: bool,
+) -> Result<u64, ProgramError> {
+    let mut sizes = vec![
+        spl_token::state::Mint::LEN,
+        metaplex_token_metadata::state::MAX_METADATA_LEN,
+    ];
+
+    if creates_token_account {
+        sizes.push(spl_token::state::Account::LEN);
+    }
+
+    if airdrop_data.creates_master_edition() {
+        sizes.push(metaplex_token_metadata::state::MAX_MASTER_EDITION_LEN);
+    }
+
+    sizes.into_iter().try_fold(0u64, |total, size| {
+        total
+            .checked_add(rent.minimum_balance(size))
+            .ok_or_else(|| AirdropError::Overflow.into())
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn process_airdrop_one_logic<'a>(
     airdrop_config: &'a AccountInfo<'a>,
-    user_data_account: &'a AccountInfo<'a>,
+    airdrop_data: &AirdropConfig,
+    user_data_account: Option<&'a AccountInfo<'a>>,
     mint: &'a AccountInfo<'a>,
     user: &'a AccountInfo<'a>,
+    recipient: &'a AccountInfo<'a>,
     user_token_account: &'a AccountInfo<'a>,
     metadata: &'a AccountInfo<'a>,
+    master_edition: &'a AccountInfo<'a>,
     mint_authority: &'a AccountInfo<'a>,
     rent_account: &'a AccountInfo<'a>,
+    slot_hashes: &'a AccountInfo<'a>,
     clock: Clock,
     rent: Rent,
     payer: &'a AccountInfo<'a>,
     admin: &'a AccountInfo<'a>,
     revenue_wallet: &'a AccountInfo<'a>,
+    revenue_split_wallets: &[&'a AccountInfo<'a>],
+    stage: Option<&'a AccountInfo<'a>>,
+    referrer: Option<&'a AccountInfo<'a>>,
+    protocol_treasury: Option<&'a AccountInfo<'a>>,
+    price: u64,
+    gift: bool,
     mint_authority_bump: u8,
     system_program: &'a AccountInfo<'a>,
     token_program: &'a AccountInfo<'a>,
@@ -145,56 +353,63 @@ pub fn process_airdrop_one_logic<'a>(
     // Create mint account for token
     let lamports = rent.minimum_balance(spl_token::state::Mint::LEN);
 
-    msg!("Initialize account for mint");
+    debug_msg!("Initialize account for mint");
     // Create mint
-    invoke(
-        &system_instruction::create_account(
-            payer.key,
-            mint.key,
-            lamports,
-            spl_token::state::Mint::LEN as u64,
-            &spl_token::id(),
-        ),
-        &[payer.clone(), mint.clone()],
-    )?;
+    mint_step(MintStep::CreateMint, || {
+        invoke(
+            &system_instruction::create_account(
+                payer.key,
+                mint.key,
+                lamports,
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            &[payer.clone(), mint.clone()],
+        )
+    })?;
 
-    msg!("Fill mint data");
+    debug_msg!("Fill mint data");
     // Initialize mint
-    invoke(
-        &spl_token::instruction::initialize_mint(
-            &spl_token::id(),
-            mint.key,
-            mint_authority.key,
-            None,
-            0,
-        )?,
-        &[mint.clone(), rent_account.clone()],
-    )?;
-
-    msg!("Initialize user token account");
-    // Initialize user token account
-    invoke(
-        &spl_associated_token_account::instruction::create_associated_token_account(
-            payer.key, user.key, mint.key,
-        ),
-        &[
-            payer.clone(),
-            user_token_account.clone(),
-            user.clone(),
-            mint.clone(),
-            system_program.clone(),
-            token_program.clone(),
-        ],
-    )?;
+    mint_step(MintStep::InitializeMint, || {
+        invoke(
+            &spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                mint.key,
+                mint_authority.key,
+                None,
+                airdrop_data.expected_decimals,
+            )?,
+            &[mint.clone(), rent_account.clone()],
+        )
+    })?;
 
-    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
-    let symbol_str = str_from_u8_nul_utf8(&airdrop_data.symbol)
-        .or(Err(ProgramError::InvalidAccountData))?
-        .to_string();
+    debug_msg!("Initialize recipient token account");
+    // Initialize recipient token account
+    mint_step(MintStep::CreateTokenAccount, || {
+        invoke(
+            &spl_associated_token_account::instruction::create_associated_token_account(
+                payer.key,
+                recipient.key,
+                mint.key,
+            ),
+            &[
+                payer.clone(),
+                user_token_account.clone(),
+                recipient.clone(),
+                mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+            ],
+        )
+    })?;
 
-    let uri_prefix = str_from_u8_nul_utf8(&airdrop_data.metadata_prefix)
-        .or(Err(ProgramError::InvalidAccountData))?
-        .to_string();
+    let uri = if airdrop_data.revealed {
+        revealed_uri(airdrop_data.base_uri(), airdrop_data.airdrop_index)?
+    } else {
+        str_from_u8_nul_utf8(&airdrop_data.placeholder_uri)
+            .ok_or(ProgramError::InvalidAccountData)?
+            .to_string()
+    };
 
     let creators = vec![
         Creator {
@@ -209,113 +424,1356 @@ pub fn process_airdrop_one_logic<'a>(
         },
     ];
 
-    let mint_authority_seed = &[
-        MINT_AUTHORITY.as_bytes(),
-        airdrop_config.key.as_ref(),
-        &[mint_authority_bump],
-    ];
+    let mint_authority_bump_seed = [mint_authority_bump];
+    let mint_authority_seed = mint_authority_seeds(airdrop_config.key, &mint_authority_bump_seed);
+
+    // Trait is stored per token in its metadata name
+    let name = match airdrop_data.trait_count {
+        0 => build_mint_name(&airdrop_data.symbol, airdrop_data.airdrop_index),
+        trait_count => {
+            let trait_id = derive_trait_id(slot_hashes, airdrop_data.airdrop_index, trait_count)?;
 
-    msg!("Initialize metadata");
+            build_trait_mint_name(&airdrop_data.symbol, airdrop_data.airdrop_index, trait_id)
+        }
+    };
+
+    // Revealed tokens of auto locked airdrop are immutable from the start
+    let is_mutable = !(airdrop_data.auto_lock && airdrop_data.revealed);
+
+    debug_msg!("Initialize metadata");
     // Create token metadata
+    mint_step(MintStep::CreateMetadata, || {
+        invoke_signed(
+            &metaplex_token_metadata::instruction::create_metadata_accounts(
+                metaplex_token_metadata::id(),
+                *metadata.key,
+                *mint.key,
+                *mint_authority.key,
+                *payer.key,
+                *mint_authority.key,
+                name,
+                build_symbol(&airdrop_data.symbol),
+                uri,
+                Some(creators),
+                1000,
+                false,
+                is_mutable,
+            ),
+            &[
+                metadata.clone(),
+                mint.clone(),
+                mint_authority.clone(),
+                payer.clone(),
+                mint_authority.clone(),
+                system_program.clone(),
+                rent_account.clone(),
+            ],
+            &[&mint_authority_seed],
+        )
+    })?;
+
+    debug_msg!("Mint to recipient");
+    // Mint configured quantity to recipient
+    mint_step(MintStep::MintTo, || {
+        invoke_signed(
+            &spl_token::instruction::mint_to(
+                &spl_token::id(),
+                mint.key,
+                user_token_account.key,
+                mint_authority.key,
+                &[],
+                airdrop_data.mint_quantity,
+            )?,
+            &[
+                mint.clone(),
+                user_token_account.clone(),
+                mint_authority.clone(),
+            ],
+            &[&mint_authority_seed],
+        )
+    })?;
+
+    if airdrop_data.creates_master_edition() {
+        debug_msg!("Create master edition");
+        // Master edition takes over mint authority, so no more tokens can be minted
+        mint_step(MintStep::CreateMasterEdition, || {
+            invoke_signed(
+                &metaplex_token_metadata::instruction::create_master_edition(
+                    metaplex_token_metadata::id(),
+                    *master_edition.key,
+                    *mint.key,
+                    *mint_authority.key,
+                    *mint_authority.key,
+                    *metadata.key,
+                    *payer.key,
+                    Some(0),
+                ),
+                &[
+                    master_edition.clone(),
+                    mint.clone(),
+                    mint_authority.clone(),
+                    payer.clone(),
+                    metadata.clone(),
+                    token_program.clone(),
+                    system_program.clone(),
+                    rent_account.clone(),
+                ],
+                &[&mint_authority_seed],
+            )
+        })?;
+    }
+
+    debug_msg!("Update metadata");
+    // Mark NFT as sold and transfer update authority
     invoke_signed(
-        &metaplex_token_metadata::instruction::create_metadata_accounts(
+        &metaplex_token_metadata::instruction::update_metadata_accounts(
             metaplex_token_metadata::id(),
             *metadata.key,
-            *mint.key,
-            *mint_authority.key,
-            *payer.key,
             *mint_authority.key,
-            symbol_str.clone() + &format!(" #{}", airdrop_data.airdrop_index),
-            symbol_str,
-            uri_prefix + &format!("{}.json", airdrop_data.airdrop_index),
-            Some(creators),
-            1000,
-            false,
-            true,
+            Some(*admin.key),
+            None,
+            Some(true),
         ),
+        &[metadata.clone(), mint_authority.clone()],
+        &[&mint_authority_seed],
+    )?;
+
+    if !airdrop_data.creates_master_edition() {
+        debug_msg!("Revoke mint authority");
+        // Revoke mint authority
+        invoke_signed(
+            &spl_token::instruction::set_authority(
+                &spl_token::id(),
+                mint.key,
+                None,
+                spl_token::instruction::AuthorityType::MintTokens,
+                mint_authority.key,
+                &[],
+            )?,
+            &[mint.clone(), mint_authority.clone()],
+            &[&mint_authority_seed],
+        )?;
+    }
+
+    debug_msg!("Assert minted token is locked");
+    assert_mint_locked(mint, airdrop_data, master_edition.key)?;
+
+    let price_payer = match airdrop_data.charge_payer {
+        true => payer,
+        false => user,
+    };
+
+    collect_price(
+        airdrop_data,
+        price,
+        price_payer,
+        referrer,
+        protocol_treasury,
+        revenue_wallet,
+        revenue_split_wallets,
+    )?;
+
+    record_mint(
+        airdrop_config,
+        airdrop_data,
+        user_data_account,
+        stage,
+        &clock,
+        price,
+        gift,
+    )?;
+
+    MintEvent {
+        user: *user.key,
+        mint: *mint.key,
+        index: airdrop_data.airdrop_index,
+        price,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Transfers price from price payer. Protocol fee goes first, then referral cut of the
+/// remainder, then the rest to revenue receivers
+fn collect_price<'a>(
+    airdrop_data: &AirdropConfig,
+    price: u64,
+    price_payer: &'a AccountInfo<'a>,
+    referrer: Option<&'a AccountInfo<'a>>,
+    protocol_treasury: Option<&'a AccountInfo<'a>>,
+    revenue_wallet: &'a AccountInfo<'a>,
+    revenue_split_wallets: &[&'a AccountInfo<'a>],
+) -> ProgramResult {
+    let price = match protocol_treasury {
+        Some(protocol_treasury) => {
+            let fee = calculate_protocol_fee(price, PROTOCOL_FEE_BPS)?;
+
+            if fee > 0 {
+                debug_msg!("Transfer protocol fee");
+                invoke(
+                    &system_instruction::transfer(price_payer.key, protocol_treasury.key, fee),
+                    &[price_payer.clone(), protocol_treasury.clone()],
+                )?;
+            }
+
+            price - fee
+        }
+        None => price,
+    };
+
+    let mut revenue = price;
+
+    if let (Some(referrer), true) = (referrer, price > 0) {
+        debug_msg!("Transfer referral reward");
+        let reward = (price as u128 * airdrop_data.referral_bps as u128 / 10000) as u64;
+        revenue -= reward;
+
+        invoke(
+            &system_instruction::transfer(price_payer.key, referrer.key, reward),
+            &[price_payer.clone(), referrer.clone()],
+        )?;
+    }
+
+    debug_msg!("Transfer SOL");
+    if price == 0 {
+        debug_msg!("Free mint, nothing to transfer");
+    } else if revenue_split_wallets.is_empty() {
+        // Transfer SOL to revenue wallet
+        invoke(
+            &system_instruction::transfer(price_payer.key, revenue_wallet.key, revenue),
+            &[price_payer.clone(), revenue_wallet.clone()],
+        )?;
+    } else {
+        // Transfer SOL to every revenue split wallet
+        let shares = calculate_revenue_shares(revenue, &airdrop_data.revenue_splits);
+
+        for (split_wallet, share) in revenue_split_wallets.iter().zip(shares) {
+            invoke(
+                &system_instruction::transfer(price_payer.key, split_wallet.key, share),
+                &[price_payer.clone(), (*split_wallet).clone()],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Counts mint against airdrop supply, user and stage.
+/// Open mint airdrop has no user data to count against
+#[allow(clippy::too_many_arguments)]
+fn record_mint(
+    airdrop_config: &AccountInfo,
+    airdrop_data: &AirdropConfig,
+    user_data_account: Option<&AccountInfo>,
+    stage: Option<&AccountInfo>,
+    clock: &Clock,
+    price: u64,
+    gift: bool,
+) -> ProgramResult {
+    debug_msg!("Write changes to program accounts");
+    // Config was unpacked once by the mint, counters are updated in place
+    AirdropConfig::increment_airdrop_index(airdrop_config)?;
+
+    // Free mints of own token leave statistics unchanged
+    if price > 0 || gift {
+        AirdropConfig::add_mint_stats(airdrop_config, price, gift)?;
+    }
+
+    if let Some(user_data_account) = user_data_account {
+        let mut user_data = AirdropUserData::unpack_from_account(user_data_account)?;
+        user_data.mints_amount += 1;
+        user_data.locked_till = (clock.unix_timestamp as u64)
+            .checked_add(airdrop_data.mint_cooldown_secs)
+            .ok_or(AirdropError::Overflow)?;
+        AirdropUserData::pack_into_account(user_data, user_data_account)?;
+    }
+
+    if let Some(stage) = stage {
+        let mut stage_data = AirdropStage::unpack_from_account(stage)?;
+        stage_data.minted += 1;
+        AirdropStage::pack_into_account(stage_data, stage)?;
+    }
+
+    Ok(())
+}
+
+/// Sets `MintReturnData`. Must be the last step of instruction,
+/// as every CPI resets return data
+pub fn set_mint_return_data(index: u64, mint: &Pubkey, price_paid: u64) {
+    let return_data = MintReturnData {
+        index,
+        mint: *mint,
+        price_paid,
+    };
+    let mut return_data_bytes = [0u8; MintReturnData::LEN];
+    return_data.pack_into_slice(&mut return_data_bytes);
+    set_return_data(&return_data_bytes);
+}
+
+pub fn process_bot_tax_logic<'a>(
+    payer: &'a AccountInfo<'a>,
+    revenue_wallet: &'a AccountInfo<'a>,
+    bot_tax_lamports: u64,
+) -> ProgramResult {
+    debug_msg!("Charge bot tax");
+    invoke(
+        &system_instruction::transfer(payer.key, revenue_wallet.key, bot_tax_lamports),
+        &[payer.clone(), revenue_wallet.clone()],
+    )
+}
+
+pub fn process_sweep_mint_authority_logic<'a>(
+    airdrop_config: &AccountInfo,
+    mint_authority: &'a AccountInfo<'a>,
+    revenue_wallet: &'a AccountInfo<'a>,
+    program_id: &Pubkey,
+    mint_authority_bump: u8,
+) -> ProgramResult {
+    let amount = mint_authority.lamports();
+
+    // Mint authority holds no data, so nothing needs to be kept for rent
+    if mint_authority.owner == program_id {
+        debug_msg!("Move lamports from program owned mint authority");
+        **revenue_wallet.lamports.borrow_mut() = revenue_wallet
+            .lamports()
+            .checked_add(amount)
+            .ok_or(AirdropError::Overflow)?;
+        **mint_authority.lamports.borrow_mut() = 0;
+    } else if amount > 0 {
+        debug_msg!("Transfer lamports from system owned mint authority");
+        let mint_authority_bump_seed = [mint_authority_bump];
+        let mint_authority_seed =
+            mint_authority_seeds(airdrop_config.key, &mint_authority_bump_seed);
+
+        invoke_signed(
+            &system_instruction::transfer(mint_authority.key, revenue_wallet.key, amount),
+            &[mint_authority.clone(), revenue_wallet.clone()],
+            &[&mint_authority_seed],
+        )?;
+    }
+
+    msg!("Swept {} lamports", amount);
+
+    Ok(())
+}
+
+pub fn process_add_stage_logic<'a>(
+    airdrop_config: &AccountInfo,
+    stage_account: &'a AccountInfo<'a>,
+    fee_payer: &'a AccountInfo<'a>,
+    rent: Rent,
+    program_id: &Pubkey,
+    stage: AirdropStage,
+) -> ProgramResult {
+    debug_msg!("Create stage account");
+    let stage_index_seed = [stage.stage_index];
+    let stage_bump_seed = [stage.bump];
+    let stage_seed = stage_seeds(airdrop_config.key, &stage_index_seed, &stage_bump_seed);
+
+    create_pda_account(
+        fee_payer,
+        stage_account,
+        AirdropStage::LEN,
+        program_id,
+        &rent,
+        &stage_seed,
+    )?;
+
+    AirdropStage::pack_into_account(stage, stage_account)?;
+
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+    airdrop_data.stage_count = airdrop_data
+        .stage_count
+        .checked_add(1)
+        .ok_or(AirdropError::Overflow)?;
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+pub fn process_remove_stage_logic(
+    airdrop_config: &AccountInfo,
+    stage_account: &AccountInfo,
+    receiver: &AccountInfo,
+) -> ProgramResult {
+    debug_msg!("Close stage account");
+    close_program_account(stage_account, receiver)?;
+
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+    airdrop_data.stage_count = airdrop_data
+        .stage_count
+        .checked_sub(1)
+        .ok_or(AirdropError::Overflow)?;
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+pub fn process_sweep_user_account_logic(
+    airdrop_config: &AccountInfo,
+    user_data_account: &AccountInfo,
+    rent_receiver: &AccountInfo,
+) -> ProgramResult {
+    debug_msg!("Close user data account");
+    close_program_account(user_data_account, rent_receiver)?;
+
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+    airdrop_data.airdrop_users = airdrop_data.airdrop_users.saturating_sub(1);
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+pub fn process_create_escrow_logic<'a>(
+    escrow_account: &'a AccountInfo<'a>,
+    payer: &'a AccountInfo<'a>,
+    rent: Rent,
+    program_id: &Pubkey,
+    escrow: AirdropEscrow,
+) -> ProgramResult {
+    debug_msg!("Create escrow account");
+    let purchase_index_seed = escrow.purchase_index.to_le_bytes();
+    let escrow_bump_seed = [escrow.bump];
+    let escrow_seed = escrow_seeds(&escrow.airdrop, &purchase_index_seed, &escrow_bump_seed);
+
+    create_pda_account(
+        payer,
+        escrow_account,
+        AirdropEscrow::LEN,
+        program_id,
+        &rent,
+        &escrow_seed,
+    )?;
+
+    AirdropEscrow::pack_into_account(escrow, escrow_account)?;
+
+    msg!("Escrowed {} for {}", escrow.mint, escrow.claimant);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn process_claim_from_escrow_logic<'a>(
+    escrow_account: &'a AccountInfo<'a>,
+    escrow_token_account: &'a AccountInfo<'a>,
+    claimant: &'a AccountInfo<'a>,
+    claimant_token_account: &'a AccountInfo<'a>,
+    mint: &'a AccountInfo<'a>,
+    payer: &'a AccountInfo<'a>,
+    rent_account: &'a AccountInfo<'a>,
+    system_program: &'a AccountInfo<'a>,
+    token_program: &'a AccountInfo<'a>,
+) -> ProgramResult {
+    if claimant_token_account.lamports() == 0 {
+        debug_msg!("Initialize claimant token account");
+        invoke(
+            &spl_associated_token_account::instruction::create_associated_token_account(
+                claimant.key,
+                claimant.key,
+                mint.key,
+            ),
+            &[
+                claimant.clone(),
+                claimant_token_account.clone(),
+                claimant.clone(),
+                mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                rent_account.clone(),
+            ],
+        )?;
+    }
+
+    release_escrow(
+        escrow_account,
+        escrow_token_account,
+        claimant_token_account,
+        payer,
+        token_program,
+    )
+}
+
+pub fn process_sweep_escrow_logic<'a>(
+    escrow_account: &'a AccountInfo<'a>,
+    escrow_token_account: &'a AccountInfo<'a>,
+    destination: &'a AccountInfo<'a>,
+    payer: &'a AccountInfo<'a>,
+    token_program: &'a AccountInfo<'a>,
+) -> ProgramResult {
+    release_escrow(
+        escrow_account,
+        escrow_token_account,
+        destination,
+        payer,
+        token_program,
+    )
+}
+
+/// Moves escrowed token to destination, then closes escrow token account and escrow
+/// with their rent going to payer of the mint
+fn release_escrow<'a>(
+    escrow_account: &'a AccountInfo<'a>,
+    escrow_token_account: &'a AccountInfo<'a>,
+    destination: &'a AccountInfo<'a>,
+    payer: &'a AccountInfo<'a>,
+    token_program: &'a AccountInfo<'a>,
+) -> ProgramResult {
+    let escrow = AirdropEscrow::unpack_initialized_from_account(escrow_account)?;
+    let amount = spl_token::state::Account::unpack(&escrow_token_account.data.borrow())?.amount;
+
+    let purchase_index_seed = escrow.purchase_index.to_le_bytes();
+    let escrow_bump_seed = [escrow.bump];
+    let escrow_seed = escrow_seeds(&escrow.airdrop, &purchase_index_seed, &escrow_bump_seed);
+
+    debug_msg!("Transfer escrowed token");
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            escrow_token_account.key,
+            destination.key,
+            escrow_account.key,
+            &[],
+            amount,
+        )?,
         &[
-            metadata.clone(),
-            mint.clone(),
-            mint_authority.clone(),
+            escrow_token_account.clone(),
+            destination.clone(),
+            escrow_account.clone(),
+        ],
+        &[&escrow_seed],
+    )?;
+
+    debug_msg!("Close escrow token account");
+    invoke_signed(
+        &spl_token::instruction::close_account(
+            token_program.key,
+            escrow_token_account.key,
+            payer.key,
+            escrow_account.key,
+            &[],
+        )?,
+        &[
+            escrow_token_account.clone(),
             payer.clone(),
-            mint_authority.clone(),
-            system_program.clone(),
-            rent_account.clone(),
+            escrow_account.clone(),
         ],
-        &[mint_authority_seed],
+        &[&escrow_seed],
     )?;
 
-    msg!("Mint to user");
-    // Mint one token to user
+    debug_msg!("Close escrow account");
+    close_program_account(escrow_account, payer)?;
+
+    msg!("Released {} to {}", escrow.mint, destination.key);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn process_deposit_preminted_logic<'a>(
+    airdrop_config: &AccountInfo,
+    airdrop_authority: &'a AccountInfo<'a>,
+    vault: &'a AccountInfo<'a>,
+    vault_token_account: &'a AccountInfo<'a>,
+    mint: &'a AccountInfo<'a>,
+    source_token_account: &'a AccountInfo<'a>,
+    fee_payer: &'a AccountInfo<'a>,
+    rent_account: &'a AccountInfo<'a>,
+    rent: Rent,
+    program_id: &Pubkey,
+    vault_bump: u8,
+    system_program: &'a AccountInfo<'a>,
+    token_program: &'a AccountInfo<'a>,
+) -> ProgramResult {
+    let vault_bump_seed = [vault_bump];
+    let vault_seed = vault_seeds(airdrop_config.key, &vault_bump_seed);
+
+    let mut vault_data = match vault.data_is_empty() {
+        true => {
+            debug_msg!("Create vault account");
+            create_pda_account(
+                fee_payer,
+                vault,
+                PremintVault::LEN,
+                program_id,
+                &rent,
+                &vault_seed,
+            )?;
+
+            PremintVault {
+                initialized: true,
+                airdrop: *airdrop_config.key,
+                bump: vault_bump,
+                cursor: 0,
+                len: 0,
+            }
+        }
+        false => PremintVault::unpack_from_account(vault)?,
+    };
+
+    debug_msg!("Grow vault for deposited mint");
+    let len = vault_data
+        .len
+        .checked_add(1)
+        .ok_or(AirdropError::Overflow)?;
+    let space = PremintVault::space(len);
+    let required_lamports = rent.minimum_balance(space).saturating_sub(vault.lamports());
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(fee_payer.key, vault.key, required_lamports),
+            &[fee_payer.clone(), vault.clone()],
+        )?;
+    }
+
+    vault.realloc(space, false)?;
+    vault.data.borrow_mut()[PremintVault::space(vault_data.len)..space]
+        .copy_from_slice(mint.key.as_ref());
+
+    vault_data.len = len;
+    PremintVault::pack_into_account(vault_data, vault)?;
+
+    if vault_token_account.lamports() == 0 {
+        debug_msg!("Initialize vault token account");
+        invoke(
+            &spl_associated_token_account::instruction::create_associated_token_account(
+                fee_payer.key,
+                vault.key,
+                mint.key,
+            ),
+            &[
+                fee_payer.clone(),
+                vault_token_account.clone(),
+                vault.clone(),
+                mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                rent_account.clone(),
+            ],
+        )?;
+    }
+
+    debug_msg!("Transfer NFT to vault");
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            source_token_account.key,
+            vault_token_account.key,
+            airdrop_authority.key,
+            &[],
+            1,
+        )?,
+        &[
+            source_token_account.clone(),
+            vault_token_account.clone(),
+            airdrop_authority.clone(),
+        ],
+    )?;
+
+    msg!("Deposited {} at {}", mint.key, len - 1);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn process_claim_preminted_logic<'a>(
+    airdrop_config: &'a AccountInfo<'a>,
+    user_data_account: &'a AccountInfo<'a>,
+    user: &'a AccountInfo<'a>,
+    user_token_account: &'a AccountInfo<'a>,
+    vault: &'a AccountInfo<'a>,
+    vault_token_account: &'a AccountInfo<'a>,
+    mint: &'a AccountInfo<'a>,
+    payer: &'a AccountInfo<'a>,
+    airdrop_authority: &'a AccountInfo<'a>,
+    revenue_wallet: &'a AccountInfo<'a>,
+    revenue_split_wallets: &[&'a AccountInfo<'a>],
+    stage: Option<&'a AccountInfo<'a>>,
+    clock: Clock,
+    price: u64,
+    rent_account: &'a AccountInfo<'a>,
+    system_program: &'a AccountInfo<'a>,
+    token_program: &'a AccountInfo<'a>,
+) -> ProgramResult {
+    let airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+    let mut vault_data = PremintVault::unpack_from_account(vault)?;
+
+    let vault_bump_seed = [vault_data.bump];
+    let vault_seed = vault_seeds(airdrop_config.key, &vault_bump_seed);
+
+    if user_token_account.lamports() == 0 {
+        debug_msg!("Initialize user token account");
+        invoke(
+            &spl_associated_token_account::instruction::create_associated_token_account(
+                payer.key, user.key, mint.key,
+            ),
+            &[
+                payer.clone(),
+                user_token_account.clone(),
+                user.clone(),
+                mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                rent_account.clone(),
+            ],
+        )?;
+    }
+
+    debug_msg!("Transfer NFT from vault");
     invoke_signed(
-        &spl_token::instruction::mint_to(
-            &spl_token::id(),
-            mint.key,
+        &spl_token::instruction::transfer(
+            token_program.key,
+            vault_token_account.key,
             user_token_account.key,
-            mint_authority.key,
+            vault.key,
             &[],
             1,
         )?,
         &[
-            mint.clone(),
+            vault_token_account.clone(),
             user_token_account.clone(),
-            mint_authority.clone(),
+            vault.clone(),
         ],
-        &[mint_authority_seed],
+        &[&vault_seed],
     )?;
 
-    msg!("Update metadata");
-    // Mark NFT as sold and transfer update authority
+    // Authority deposited the NFT, so rent of vault token account returns to it
+    debug_msg!("Close vault token account");
     invoke_signed(
-        &metaplex_token_metadata::instruction::update_metadata_accounts(
-            metaplex_token_metadata::id(),
-            *metadata.key,
-            *mint_authority.key,
-            Some(*admin.key),
-            None,
-            Some(true),
-        ),
-        &[metadata.clone(), mint_authority.clone()],
-        &[mint_authority_seed],
+        &spl_token::instruction::close_account(
+            token_program.key,
+            vault_token_account.key,
+            airdrop_authority.key,
+            vault.key,
+            &[],
+        )?,
+        &[
+            vault_token_account.clone(),
+            airdrop_authority.clone(),
+            vault.clone(),
+        ],
+        &[&vault_seed],
+    )?;
+
+    vault_data.cursor += 1;
+    PremintVault::pack_into_account(vault_data, vault)?;
+
+    let price_payer = match airdrop_data.charge_payer {
+        true => payer,
+        false => user,
+    };
+
+    collect_price(
+        &airdrop_data,
+        price,
+        price_payer,
+        None,
+        None,
+        revenue_wallet,
+        revenue_split_wallets,
     )?;
 
-    msg!("Revoke mint authority");
-    // Revoke mint authority
+    record_mint(
+        airdrop_config,
+        &airdrop_data,
+        Some(user_data_account),
+        stage,
+        &clock,
+        price,
+        false,
+    )?;
+
+    set_mint_return_data(airdrop_data.airdrop_index, mint.key, price);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn process_withdraw_unclaimed_logic<'a>(
+    airdrop_config: &AccountInfo,
+    vault: &'a AccountInfo<'a>,
+    vault_token_account: &'a AccountInfo<'a>,
+    destination: &'a AccountInfo<'a>,
+    rent_receiver: &'a AccountInfo<'a>,
+    queue_index: u32,
+    rent: Rent,
+    token_program: &'a AccountInfo<'a>,
+) -> ProgramResult {
+    let mut vault_data = PremintVault::unpack_from_account(vault)?;
+
+    let vault_bump_seed = [vault_data.bump];
+    let vault_seed = vault_seeds(airdrop_config.key, &vault_bump_seed);
+
+    debug_msg!("Transfer NFT from vault");
     invoke_signed(
-        &spl_token::instruction::set_authority(
-            &spl_token::id(),
-            mint.key,
-            None,
-            spl_token::instruction::AuthorityType::MintTokens,
-            mint_authority.key,
+        &spl_token::instruction::transfer(
+            token_program.key,
+            vault_token_account.key,
+            destination.key,
+            vault.key,
             &[],
+            1,
         )?,
-        &[mint.clone(), mint_authority.clone()],
-        &[mint_authority_seed],
+        &[
+            vault_token_account.clone(),
+            destination.clone(),
+            vault.clone(),
+        ],
+        &[&vault_seed],
     )?;
 
-    msg!("Transfer SOL");
-    // Transfer SOL to revenue wallet
-    invoke(
-        &system_instruction::transfer(payer.key, revenue_wallet.key, airdrop_data.price),
-        &[payer.clone(), revenue_wallet.clone()],
+    debug_msg!("Close vault token account");
+    invoke_signed(
+        &spl_token::instruction::close_account(
+            token_program.key,
+            vault_token_account.key,
+            rent_receiver.key,
+            vault.key,
+            &[],
+        )?,
+        &[
+            vault_token_account.clone(),
+            rent_receiver.clone(),
+            vault.clone(),
+        ],
+        &[&vault_seed],
     )?;
 
-    msg!("Write changes to program accounts");
-    airdrop_data.airdrop_index += 1;
+    debug_msg!("Swap remove mint from queue");
+    let len = vault_data.len - 1;
+    vault.data.borrow_mut().copy_within(
+        PremintVault::space(len)..PremintVault::space(vault_data.len),
+        PremintVault::space(queue_index),
+    );
+
+    vault_data.len = len;
+    PremintVault::pack_into_account(vault_data, vault)?;
+
+    debug_msg!("Shrink vault");
+    let space = PremintVault::space(len);
+    vault.realloc(space, false)?;
+
+    let excess_lamports = vault.lamports().saturating_sub(rent.minimum_balance(space));
+    **vault.lamports.borrow_mut() -= excess_lamports;
+    **rent_receiver.lamports.borrow_mut() = rent_receiver
+        .lamports()
+        .checked_add(excess_lamports)
+        .ok_or(AirdropError::Overflow)?;
+
+    msg!("Withdrawn unclaimed mint at {}", queue_index);
+
+    Ok(())
+}
+
+/// Moves all lamports of program owned account to receiver and wipes its data.
+/// Runtime removes account with no lamports after transaction
+fn close_program_account(account: &AccountInfo, receiver: &AccountInfo) -> ProgramResult {
+    **receiver.lamports.borrow_mut() = receiver
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(AirdropError::Overflow)?;
+    **account.lamports.borrow_mut() = 0;
+    account.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+/// Stage allowlist leaf of user wallet
+pub fn allowlist_leaf(user: &Pubkey) -> [u8; 32] {
+    hashv(&[user.as_ref()]).to_bytes()
+}
+
+/// Checks merkle proof of leaf. Node pairs are sorted before hashing,
+/// so proof needs no left or right markers
+pub fn verify_merkle_proof(leaf: [u8; 32], proof: &[[u8; 32]], root: &[u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+
+    computed == *root
+}
+
+pub fn process_reveal_logic(airdrop_config: &AccountInfo) -> ProgramResult {
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Check if airdrop is already revealed");
+    if airdrop_data.revealed {
+        return Err(AirdropError::AlreadyRevealed.into());
+    }
+
+    airdrop_data.revealed = true;
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+/// Minted token must have configured decimals and quantity, and nobody can mint more of it or freeze it
+/// Master edition of 1/1 NFT holds mint authority instead of it being revoked
+fn assert_mint_locked(
+    mint: &AccountInfo,
+    airdrop_data: &AirdropConfig,
+    master_edition: &Pubkey,
+) -> ProgramResult {
+    let mint_data = spl_token::state::Mint::unpack(&mint.data.borrow())?;
+
+    let locked_authority = match airdrop_data.creates_master_edition() {
+        true => COption::Some(*master_edition),
+        false => COption::None,
+    };
+
+    if mint_data.mint_authority != locked_authority
+        || mint_data.freeze_authority.is_some()
+        || mint_data.supply != airdrop_data.mint_quantity
+        || mint_data.decimals != airdrop_data.expected_decimals
+    {
+        return Err(AirdropError::InvalidMint.into());
+    }
+
+    Ok(())
+}
+
+/// Protocol share of price. Rounded down, so rounding dust stays with the project
+pub fn calculate_protocol_fee(price: u64, fee_bps: u16) -> Result<u64, ProgramError> {
+    let fee = (price as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(AirdropError::Overflow)?
+        / 10000;
+
+    u64::try_from(fee).map_err(|_| AirdropError::Overflow.into())
+}
+
+/// Splits price between used revenue splits. First split receives rounding remainder
+pub fn calculate_revenue_shares(price: u64, splits: &[RevenueSplit]) -> Vec<u64> {
+    let mut shares: Vec<u64> = splits
+        .iter()
+        .filter(|s| s.is_used())
+        .map(|s| (price as u128 * s.basis_points as u128 / 10000) as u64)
+        .collect();
+
+    let distributed: u64 = shares.iter().sum();
+
+    if let Some(first) = shares.first_mut() {
+        *first += price - distributed;
+    }
+
+    shares
+}
+
+pub fn process_increase_supply_logic(
+    airdrop_config: &AccountInfo,
+    additional: u64,
+) -> ProgramResult {
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Increase airdrop supply");
+    airdrop_data.airdrop_amount = airdrop_data
+        .airdrop_amount
+        .checked_add(additional)
+        .ok_or(AirdropError::Overflow)?;
+
+    if airdrop_data.airdrop_amount > MAX_AIRDROP_AMOUNT {
+        return Err(AirdropError::InvalidSupply.into());
+    }
+
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+pub fn process_update_revenue_wallet_logic(
+    airdrop_config: &AccountInfo,
+    revenue_wallet: Pubkey,
+) -> ProgramResult {
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    msg!(
+        "Revenue wallet {} -> {}",
+        airdrop_data.revenues_wallet,
+        revenue_wallet
+    );
+    airdrop_data.revenues_wallet = revenue_wallet;
+
     AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+pub fn process_update_window_logic(
+    airdrop_config: &AccountInfo,
+    start_ts: i64,
+    end_ts: i64,
+) -> ProgramResult {
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    debug_msg!("Update mint window");
+    airdrop_data.start_ts = start_ts;
+    airdrop_data.end_ts = end_ts;
+
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+pub fn process_set_user_blocked_logic(
+    user_data_account: &AccountInfo,
+    blocked: bool,
+) -> ProgramResult {
     let mut user_data = AirdropUserData::unpack_from_account(user_data_account)?;
-    user_data.mints_amount += 1;
-    user_data.locked_till = (clock.unix_timestamp + 21600_i64) as u64; // Current time plus 6 hours
+
+    msg!("UserBlocked {} {}", user_data.user, blocked);
+    user_data.blocked = blocked;
+
     AirdropUserData::pack_into_account(user_data, user_data_account)?;
 
     Ok(())
 }
 
-fn str_from_u8_nul_utf8(utf8_src: &[u8]) -> Result<&str, std::str::Utf8Error> {
+/// Rewrites cached bumps with re-derived ones
+pub fn process_recompute_bumps_logic(
+    airdrop_config: &AccountInfo,
+    mint_authority_bump: u8,
+    user_data_accounts: &[(&AccountInfo, u8)],
+) -> ProgramResult {
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    msg!(
+        "MintAuthorityBump {} {}",
+        airdrop_data.mint_authority_bump,
+        mint_authority_bump
+    );
+    airdrop_data.mint_authority_bump = mint_authority_bump;
+
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    for (user_data_account, bump) in user_data_accounts {
+        let mut user_data = AirdropUserData::unpack_from_account(user_data_account)?;
+        user_data.bump = *bump;
+        AirdropUserData::pack_into_account(user_data, user_data_account)?;
+    }
+
+    Ok(())
+}
+
+pub fn process_finalize_logic(airdrop_config: &AccountInfo, lock: bool) -> ProgramResult {
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    airdrop_data.finalized = true;
+    airdrop_data.final_minted = airdrop_data.airdrop_index;
+    airdrop_data.final_gifted = airdrop_data.gifted_count;
+    airdrop_data.final_registered = airdrop_data.registered_count;
+    airdrop_data.final_revenue = airdrop_data.total_revenue;
+
+    // Nobody can sign for the default key, so locked config can't be changed at all
+    if lock {
+        airdrop_data.airdrop_authority = Pubkey::default();
+    }
+
+    msg!(
+        "Finalized minted {} gifted {} registered {} revenue {}",
+        airdrop_data.final_minted,
+        airdrop_data.final_gifted,
+        airdrop_data.final_registered,
+        airdrop_data.final_revenue
+    );
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+pub fn process_deactivate_logic(airdrop_config: &AccountInfo) -> ProgramResult {
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    msg!("Airdrop deactivated");
+    airdrop_data.is_active = false;
+
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+pub fn process_draw_raffle_logic(
+    airdrop_config: &AccountInfo,
+    slot_hashes: &AccountInfo,
+    winners_count: u64,
+) -> ProgramResult {
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+    let data = slot_hashes.data.borrow();
+
+    // Slot hashes are stored as a vector of (slot, hash), most recent first
+    if data.len() < 8 + 8 + 32 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let recent_hash = array_ref![data, 8 + 8, 32];
+    airdrop_data.raffle_seed = hashv(&[recent_hash, airdrop_config.key.as_ref()]).to_bytes();
+    airdrop_data.winners_count = winners_count;
+
+    msg!(
+        "RaffleDrawn {} of {}",
+        winners_count,
+        airdrop_data.registered_count
+    );
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+/// Whether user registered at `registration_index` is selected by the
+/// raffle. Selection depends only on the seed, so it is reproducible off-chain
+pub fn is_raffle_winner(
+    raffle_seed: &[u8; 32],
+    registration_index: u64,
+    entrants: u64,
+    winners_count: u64,
+) -> bool {
+    if entrants == 0 {
+        return false;
+    }
+
+    let ticket = hashv(&[raffle_seed, &registration_index.to_le_bytes()]);
+    let ticket_number = u64::from_le_bytes(*array_ref![ticket.as_ref(), 0, 8]);
+
+    ticket_number % entrants < winners_count
+}
+
+// Pyth price account layout, version 2 `PriceAccount` of pyth-sdk-solana
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+const PYTH_VERSION: u32 = 2;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_PRICE_ACCOUNT_LEN: usize = 3312;
+
+/// Fields up to and including aggregate price, 32 publisher components follow them
+const PYTH_PRICE_HEADER_LEN: usize = 240;
+
+/// Reads aggregate price and its exponent from Pyth price account.
+/// Price must be trading, positive and published within `max_age_slots` of `current_slot`
+pub fn read_pyth_price(
+    data: &[u8],
+    current_slot: u64,
+    max_age_slots: u64,
+) -> Result<(i64, i32), ProgramError> {
+    if data.len() < PYTH_PRICE_ACCOUNT_LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let header = array_ref![data, 0, PYTH_PRICE_HEADER_LEN];
+
+    let (
+        magic,
+        version,
+        account_type,
+        _size,
+        _price_type,
+        expo,
+        _num,
+        _num_qt,
+        _last_slot,
+        _valid_slot,
+        _ema_price,
+        _ema_conf,
+        _timestamp,
+        _min_pub_and_derived,
+        _product,
+        _next,
+        _prev_slot,
+        _prev_price,
+        _prev_conf,
+        _prev_timestamp,
+        agg_price,
+        _agg_conf,
+        agg_status,
+        _agg_corp_act,
+        agg_pub_slot,
+    ) = array_refs![
+        header, 4, 4, 4, 4, 4, 4, 4, 4, 8, 8, 24, 24, 8, 8, 32, 32, 8, 8, 8, 8, 8, 8, 4, 4, 8
+    ];
+
+    // Mapping and product accounts share the magic, price is not read from them
+    if u32::from_le_bytes(*magic) != PYTH_MAGIC
+        || u32::from_le_bytes(*version) != PYTH_VERSION
+        || u32::from_le_bytes(*account_type) != PYTH_PRICE_ACCOUNT_TYPE
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let expo = i32::from_le_bytes(*expo);
+    let price = i64::from_le_bytes(*agg_price);
+    let status = u32::from_le_bytes(*agg_status);
+    let publish_slot = u64::from_le_bytes(*agg_pub_slot);
+
+    if status != PYTH_STATUS_TRADING
+        || price <= 0
+        || current_slot.saturating_sub(publish_slot) > max_age_slots
+    {
+        return Err(AirdropError::StaleOracle.into());
+    }
+
+    Ok((price, expo))
+}
+
+/// Lamports worth `price_usd_cents` at SOL price of `sol_price * 10^expo` USD, rounded up.
+/// None on overflow or non-positive price
+pub fn usd_cents_to_lamports(price_usd_cents: u64, sol_price: i64, expo: i32) -> Option<u64> {
+    let sol_price = u128::try_from(sol_price).ok().filter(|&price| price > 0)?;
+
+    // lamports = cents * 10^9 / 100 / (sol_price * 10^expo)
+    let scale = 7i32.checked_sub(expo)?;
+    let (numerator, denominator) = match scale >= 0 {
+        true => (
+            (price_usd_cents as u128).checked_mul(10u128.checked_pow(scale as u32)?)?,
+            sol_price,
+        ),
+        false => (
+            price_usd_cents as u128,
+            sol_price.checked_mul(10u128.checked_pow(scale.unsigned_abs())?)?,
+        ),
+    };
+
+    let lamports = numerator.checked_add(denominator - 1)? / denominator;
+    u64::try_from(lamports).ok()
+}
+
+pub fn process_set_usd_price_logic(
+    airdrop_config: &AccountInfo,
+    price_usd_cents: u64,
+    pyth_price_account: Pubkey,
+    max_price_age_slots: u64,
+) -> ProgramResult {
+    let mut airdrop_data = AirdropConfig::unpack_from_account(airdrop_config)?;
+
+    msg!(
+        "USD price {} cents, feed {}",
+        price_usd_cents,
+        pyth_price_account
+    );
+    airdrop_data.price_usd_cents = price_usd_cents;
+    airdrop_data.pyth_price_account = pyth_price_account;
+    airdrop_data.max_price_age_slots = max_price_age_slots;
+
+    AirdropConfig::pack_into_account(airdrop_data, airdrop_config)?;
+
+    Ok(())
+}
+
+/// Share of remaining supply one transaction may mint, at least one mint
+pub fn fair_mint_limit(remaining_supply: u64, fairness_bps: u16) -> u64 {
+    (remaining_supply as u128 * fairness_bps as u128 / 10_000).max(1) as u64
+}
+
+pub fn process_preview_mint_logic(
+    airdrop_data: &AirdropConfig,
+    price: u64,
+    estimated_rent_lamports: u64,
+) -> ProgramResult {
+    let remaining_supply = airdrop_data.airdrop_amount - airdrop_data.airdrop_index;
+
+    // User may mint again only after timeout, so allowance is limited to a single transaction
+    let remaining_allowance = match airdrop_data.max_mints_per_tx {
+        0 => remaining_supply,
+        max => remaining_supply.min(max as u64),
+    };
+    let remaining_allowance = match airdrop_data.fairness_bps {
+        0 => remaining_allowance,
+        bps => remaining_allowance.min(fair_mint_limit(remaining_supply, bps)),
+    };
+
+    let preview = MintPreview {
+        effective_price: price,
+        estimated_rent_lamports,
+        remaining_supply,
+        remaining_allowance,
+    };
+    let mut preview_bytes = [0u8; MintPreview::LEN];
+    preview.pack_into_slice(&mut preview_bytes);
+    set_return_data(&preview_bytes);
+
+    Ok(())
+}
+
+/// Derives pseudo-random trait id from the most recent slot hash and mint index.
+/// Slot hash is known to the slot leader, so it is not cryptographically secure
+/// and must only be used for cosmetic traits. Mint key is picked by minter and is
+/// left out of the seed, otherwise trait could be chosen by grinding mint keypairs
+pub fn derive_trait_id(
+    slot_hashes: &AccountInfo,
+    airdrop_index: u64,
+    trait_count: u8,
+) -> Result<u8, ProgramError> {
+    let data = slot_hashes.data.borrow();
+
+    // Slot hashes are stored as a vector of (slot, hash), most recent first
+    if data.len() < 8 + 8 + 32 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let recent_hash = array_ref![data, 8 + 8, 32];
+    let seed = hashv(&[recent_hash, &airdrop_index.to_le_bytes()]);
+    let seed_number = u64::from_le_bytes(*array_ref![seed.as_ref(), 0, 8]);
+
+    Ok((seed_number % trait_count as u64) as u8)
+}
+
+/// Creates program owned PDA. Lamports sent to PDA address beforehand
+/// can't block creation, as account is then allocated and assigned instead
+fn create_pda_account<'a>(
+    payer: &'a AccountInfo<'a>,
+    account: &'a AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    rent: &Rent,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let lamports = rent.minimum_balance(space);
+
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                lamports,
+                space as u64,
+                owner,
+            ),
+            &[payer.clone(), account.clone()],
+            &[seeds],
+        );
+    }
+
+    let required_lamports = lamports.saturating_sub(account.lamports());
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, required_lamports),
+            &[payer.clone(), account.clone()],
+        )?;
+    }
+
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        std::slice::from_ref(account),
+        &[seeds],
+    )?;
+
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        std::slice::from_ref(account),
+        &[seeds],
+    )
+}
+
+/// Reads NUL padded string. None if it is not valid UTF-8 or has NUL before other bytes
+pub fn str_from_u8_nul_utf8(utf8_src: &[u8]) -> Option<&str> {
     let nul_range_end = utf8_src
         .iter()
         .position(|&c| c == b'\0')
         .unwrap_or(utf8_src.len()); // default to length if no `\0` present
-    ::std::str::from_utf8(&utf8_src[0..nul_range_end])
+
+    if utf8_src[nul_range_end..].iter().any(|&c| c != b'\0') {
+        return None;
+    }
+
+    ::std::str::from_utf8(&utf8_src[0..nul_range_end]).ok()
 }
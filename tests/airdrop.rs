@@ -0,0 +1,5642 @@
+mod common;
+
+use common::*;
+use premint_airdrop::{
+    error::AirdropError,
+    instruction::AddStageArgs,
+    pda::{find_airdrop_config, find_airdrop_user_data, find_metadata, find_mint_authority},
+    return_data::MintPreview,
+    state::{
+        AirdropConfig, AirdropStage, AirdropUserData, MAX_AIRDROP_AMOUNT, MAX_URI_LENGTH,
+        PLATFORM_FEE_LAMPORTS, PLATFORM_WALLET,
+    },
+    util::{allowlist_leaf, is_raffle_winner, mint_rent_lamports},
+};
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, instruction::Instruction,
+    program::invoke_signed, program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction,
+};
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+#[tokio::test]
+async fn initialize_airdrop_creates_config() {
+    let mut ctx = start().await;
+    let airdrop = create_airdrop(&mut ctx, AirdropArgs::default()).await;
+
+    let account = ctx
+        .banks_client
+        .get_account(airdrop.config)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, premint_airdrop::id());
+
+    let config = AirdropConfig::unpack_unchecked(&account.data).unwrap();
+    assert!(config.initialized);
+    assert_eq!(config.airdrop_authority, airdrop.authority.pubkey());
+    assert_eq!(config.airdrop_amount, 10);
+    assert_eq!(config.airdrop_index, 0);
+    assert_eq!(config.revenues_wallet, airdrop.revenue_wallet);
+    assert_eq!(config.admin_account, airdrop.admin);
+    assert_eq!(config.name, padded(b"katts"));
+    assert!(!config.revealed);
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_underfunded_fee_payer() {
+    let mut ctx = start().await;
+    let authority = Keypair::new();
+    let revenue_wallet = Keypair::new().pubkey();
+    fund(&mut ctx, &revenue_wallet, 1_000_000_000).await;
+
+    let args = AirdropArgs::default();
+    let (config, _) = find_airdrop_config(&authority.pubkey(), &args.name);
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let fee_payer = Keypair::new();
+    fund(
+        &mut ctx,
+        &fee_payer.pubkey(),
+        rent.minimum_balance(AirdropConfig::LEN) + rent.minimum_balance(0) - 1,
+    )
+    .await;
+
+    let instruction = initialize_airdrop_instruction(
+        &config,
+        &authority.pubkey(),
+        &revenue_wallet,
+        &Pubkey::new_unique(),
+        &fee_payer.pubkey(),
+        &args,
+    );
+
+    let error = process(&mut ctx, &[instruction], &[&fee_payer, &authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InsufficientFunds as u32)
+    );
+}
+
+#[cfg(not(feature = "platform-fee"))]
+#[tokio::test]
+async fn initialize_airdrop_charges_no_platform_fee_by_default() {
+    let mut ctx = start().await;
+    create_airdrop(&mut ctx, AirdropArgs::default()).await;
+
+    assert_eq!(PLATFORM_FEE_LAMPORTS, 0);
+    assert_eq!(
+        ctx.banks_client.get_balance(PLATFORM_WALLET).await.unwrap(),
+        0
+    );
+}
+
+#[cfg(feature = "platform-fee")]
+#[tokio::test]
+async fn initialize_airdrop_pays_platform_fee() {
+    let mut ctx = start().await;
+    create_airdrop(&mut ctx, AirdropArgs::default()).await;
+
+    assert_eq!(
+        ctx.banks_client.get_balance(PLATFORM_WALLET).await.unwrap(),
+        PLATFORM_FEE_LAMPORTS
+    );
+}
+
+#[cfg(feature = "platform-fee")]
+#[tokio::test]
+async fn initialize_airdrop_rejects_fee_payer_short_of_platform_fee() {
+    let mut ctx = start().await;
+    let authority = Keypair::new();
+    let revenue_wallet = Keypair::new().pubkey();
+    fund(&mut ctx, &revenue_wallet, 1_000_000_000).await;
+
+    let args = AirdropArgs::default();
+    let (config, _) = find_airdrop_config(&authority.pubkey(), &args.name);
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let fee_payer = Keypair::new();
+    fund(
+        &mut ctx,
+        &fee_payer.pubkey(),
+        rent.minimum_balance(AirdropConfig::LEN) + rent.minimum_balance(0) + PLATFORM_FEE_LAMPORTS
+            - 1,
+    )
+    .await;
+
+    let instruction = initialize_airdrop_instruction(
+        &config,
+        &authority.pubkey(),
+        &revenue_wallet,
+        &Pubkey::new_unique(),
+        &fee_payer.pubkey(),
+        &args,
+    );
+
+    let error = process(&mut ctx, &[instruction], &[&fee_payer, &authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InsufficientFunds as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_wrong_mint_authority() {
+    let mut ctx = start().await;
+    let config = Keypair::new();
+    let revenue_wallet = Keypair::new().pubkey();
+    fund(&mut ctx, &revenue_wallet, 1_000_000_000).await;
+
+    let payer = ctx.payer.pubkey();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let mut instruction = initialize_airdrop_instruction(
+        &config.pubkey(),
+        &Pubkey::new_unique(),
+        &revenue_wallet,
+        &Pubkey::new_unique(),
+        &payer,
+        &AirdropArgs::default(),
+    );
+    instruction.accounts[2].pubkey = Pubkey::new_unique();
+
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &config.pubkey(),
+            rent.minimum_balance(AirdropConfig::LEN),
+            AirdropConfig::LEN as u64,
+            &premint_airdrop::id(),
+        ),
+        instruction,
+    ];
+
+    let error = process(&mut ctx, &instructions, &[&config])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidMintAuthorityPda as u32)
+    );
+}
+
+/// Initializes airdrop in account allocated by the same transaction with given revenue wallet
+async fn initialize_airdrop_with_revenue_wallet(
+    ctx: &mut ProgramTestContext,
+    config: &Keypair,
+    revenue_wallet: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let payer = ctx.payer.pubkey();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &config.pubkey(),
+            rent.minimum_balance(AirdropConfig::LEN),
+            AirdropConfig::LEN as u64,
+            &premint_airdrop::id(),
+        ),
+        initialize_airdrop_instruction(
+            &config.pubkey(),
+            &Pubkey::new_unique(),
+            revenue_wallet,
+            &Pubkey::new_unique(),
+            &payer,
+            &AirdropArgs::default(),
+        ),
+    ];
+
+    process(ctx, &instructions, &[config]).await
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_revenue_wallet_owned_by_other_program() {
+    let mut program_test = program_test();
+    let revenue_wallet = Keypair::new().pubkey();
+    program_test.add_account(
+        revenue_wallet,
+        token_account(&Pubkey::new_unique(), &Pubkey::new_unique(), 0),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let error = initialize_airdrop_with_revenue_wallet(&mut ctx, &Keypair::new(), &revenue_wallet)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidRevenueWallet as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_program_address_as_revenue_wallet() {
+    let mut ctx = start().await;
+    let config = Keypair::new();
+    let revenue_wallets = [
+        find_mint_authority(&config.pubkey()).0,
+        find_airdrop_user_data(&config.pubkey(), &Pubkey::new_unique()).0,
+        find_airdrop_config(&Pubkey::new_unique(), &AirdropArgs::default().name).0,
+    ];
+
+    for revenue_wallet in revenue_wallets {
+        let error = initialize_airdrop_with_revenue_wallet(&mut ctx, &config, &revenue_wallet)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            airdrop_error(error),
+            Some(AirdropError::InvalidRevenueWallet as u32)
+        );
+    }
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_fee_payer_as_revenue_wallet() {
+    let mut ctx = start().await;
+    let payer = ctx.payer.pubkey();
+
+    let error = initialize_airdrop_with_revenue_wallet(&mut ctx, &Keypair::new(), &payer)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidRevenueWallet as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_accepts_unfunded_wallet_as_revenue_wallet() {
+    let mut ctx = start().await;
+    let config = Keypair::new();
+
+    initialize_airdrop_with_revenue_wallet(&mut ctx, &config, &Keypair::new().pubkey())
+        .await
+        .unwrap();
+    assert!(get_config(&mut ctx, &config.pubkey()).await.initialized);
+}
+
+#[tokio::test]
+async fn initialize_airdrop_requires_authority_signature_for_derived_config() {
+    let mut ctx = start().await;
+    let authority = Pubkey::new_unique();
+    let revenue_wallet = Keypair::new().pubkey();
+    fund(&mut ctx, &revenue_wallet, 1_000_000_000).await;
+
+    let args = AirdropArgs::default();
+    let (config, _) = find_airdrop_config(&authority, &args.name);
+    let payer = ctx.payer.pubkey();
+    let mut instruction = initialize_airdrop_instruction(
+        &config,
+        &authority,
+        &revenue_wallet,
+        &Pubkey::new_unique(),
+        &payer,
+        &args,
+    );
+    // Anyone else trying to take the name
+    instruction.accounts[1].is_signer = false;
+
+    let error = process(&mut ctx, &[instruction], &[]).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::SignerRequired as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn initialize_airdrop_with_prefunded_config() {
+    let args = AirdropArgs::default();
+    let authority = Keypair::new();
+    let (config, _) = find_airdrop_config(&authority.pubkey(), &args.name);
+    let (mint_authority, _) = find_mint_authority(&config);
+
+    // Stray lamports sent to derived addresses before initialization
+    let mut program_test = program_test();
+    program_test.add_account(config, system_account(1));
+    program_test.add_account(mint_authority, system_account(1));
+    let mut ctx = program_test.start_with_context().await;
+    let airdrop = create_pda_airdrop_with_authority(&mut ctx, authority, args).await;
+
+    let account = ctx
+        .banks_client
+        .get_account(airdrop.config)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, premint_airdrop::id());
+    let config = AirdropConfig::unpack_unchecked(&account.data).unwrap();
+    assert!(config.initialized);
+    assert_eq!(config.airdrop_authority, airdrop.authority.pubkey());
+
+    let account = ctx
+        .banks_client
+        .get_account(mint_authority)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, premint_airdrop::id());
+}
+
+#[tokio::test]
+async fn register_user_twice_fails() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let payer = ctx.payer.pubkey();
+    let instruction = register_user_instruction(
+        &airdrop,
+        &airdrop.user_data(&user.pubkey()),
+        &user.pubkey(),
+        &payer,
+    );
+
+    let error = process(&mut ctx, &[instruction], &[]).await.unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+#[tokio::test]
+async fn register_user_idempotent_skips_registered_user() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    let user_data_before = ctx
+        .banks_client
+        .get_account(airdrop.user_data(&user.pubkey()))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let payer = ctx.payer.pubkey();
+    let instruction = register_user_idempotent_instruction(&airdrop, &user.pubkey(), &payer);
+    process(&mut ctx, &[instruction], &[]).await.unwrap();
+
+    let user_data_after = ctx
+        .banks_client
+        .get_account(airdrop.user_data(&user.pubkey()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user_data_before.data, user_data_after.data);
+    assert_eq!(get_config(&mut ctx, &airdrop.config).await.airdrop_users, 0);
+}
+
+/// Airdrop re-initialized at the same address, user data of the previous one is stale
+async fn start_with_stale_user(idempotent: bool) -> (ProgramTestContext, TestAirdrop, Keypair) {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_locked_user(&mut program_test, &airdrop, u64::MAX);
+    let mut ctx = program_test.start_with_context().await;
+    update_config(&mut ctx, &airdrop, |config| {
+        config.nonce = 7;
+        config.registered_count = 3;
+    })
+    .await;
+
+    let error = process(
+        &mut ctx,
+        &[can_mint_instruction(&airdrop, &user.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::StaleUserAccount as u32)
+    );
+
+    let payer = ctx.payer.pubkey();
+    let instruction = match idempotent {
+        true => register_user_idempotent_instruction(&airdrop, &user.pubkey(), &payer),
+        false => register_user_instruction(
+            &airdrop,
+            &airdrop.user_data(&user.pubkey()),
+            &user.pubkey(),
+            &payer,
+        ),
+    };
+    process(&mut ctx, &[instruction], &[]).await.unwrap();
+
+    (ctx, airdrop, user)
+}
+
+async fn assert_user_data_restamped(
+    ctx: &mut ProgramTestContext,
+    airdrop: &TestAirdrop,
+    user: &Keypair,
+) {
+    let user_data = get_user_data(ctx, &airdrop.user_data(&user.pubkey())).await;
+    assert_eq!(user_data.nonce, 7);
+    assert_eq!(user_data.locked_till, 0);
+    assert_eq!(user_data.mints_amount, 0);
+    assert_eq!(user_data.registration_index, 3);
+
+    let config = get_config(ctx, &airdrop.config).await;
+    assert_eq!(config.registered_count, 4);
+    assert_eq!(config.airdrop_users, 1);
+
+    // Second instruction keeps transaction different from the rejected one
+    process(
+        ctx,
+        &[
+            can_mint_instruction(airdrop, &user.pubkey()),
+            can_mint_instruction(airdrop, &user.pubkey()),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn register_user_reinitializes_stale_user_data() {
+    let (mut ctx, airdrop, user) = start_with_stale_user(false).await;
+
+    assert_user_data_restamped(&mut ctx, &airdrop, &user).await;
+}
+
+#[tokio::test]
+async fn register_user_idempotent_reinitializes_stale_user_data() {
+    let (mut ctx, airdrop, user) = start_with_stale_user(true).await;
+
+    assert_user_data_restamped(&mut ctx, &airdrop, &user).await;
+}
+
+#[tokio::test]
+async fn register_user_idempotent_rejects_corrupt_user_data() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = Keypair::new();
+    let mut data = vec![0; AirdropUserData::LEN];
+    data[0] = 2;
+    program_test.add_account(
+        airdrop.user_data(&user.pubkey()),
+        solana_sdk::account::Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: premint_airdrop::id(),
+            ..solana_sdk::account::Account::default()
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let payer = ctx.payer.pubkey();
+    let instruction = register_user_idempotent_instruction(&airdrop, &user.pubkey(), &payer);
+    let error = process(&mut ctx, &[instruction], &[]).await.unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn register_user_rejects_payer_short_of_registration_fee() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            registration_fee: 5_000_000_000,
+            ..AirdropArgs::default()
+        },
+    );
+    let payer = Keypair::new();
+    program_test.add_account(payer.pubkey(), system_account(1_000_000_000));
+    let mut ctx = program_test.start_with_context().await;
+
+    let user = Pubkey::new_unique();
+    let instruction =
+        register_user_instruction(&airdrop, &airdrop.user_data(&user), &user, &payer.pubkey());
+
+    let error = process(&mut ctx, &[instruction], &[&payer])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InsufficientFunds as u32)
+    );
+}
+
+#[tokio::test]
+async fn register_user_rejects_other_fee_receiver() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            registration_fee: 10_000_000,
+            ..AirdropArgs::default()
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let user = Pubkey::new_unique();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        register_user_instruction(&airdrop, &airdrop.user_data(&user), &user, &payer);
+    instruction.accounts[6] = AccountMeta::new(payer, false);
+
+    let error = process(&mut ctx, &[instruction], &[]).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn register_user_pays_registration_fee_to_revenue_wallet() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            registration_fee: 10_000_000,
+            ..AirdropArgs::default()
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+    let revenue_before = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+
+    let user = Pubkey::new_unique();
+    register_user(&mut ctx, &airdrop, &user).await.unwrap();
+
+    let revenue_after = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+    assert_eq!(revenue_after, revenue_before + 10_000_000);
+    assert_eq!(get_config(&mut ctx, &airdrop.config).await.airdrop_users, 1);
+}
+
+#[tokio::test]
+async fn register_user_rejects_wrong_pda() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let payer = ctx.payer.pubkey();
+    let instruction = register_user_instruction(
+        &airdrop,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &payer,
+    );
+
+    let error = process(&mut ctx, &[instruction], &[]).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidUserDataPda as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_requires_user_signature() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), false, &payer);
+
+    let error = process(&mut ctx, &[instruction], &[&mint, &airdrop.authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::UserSignerRequired as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_requires_airdrop_authority_signature() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[14].is_signer = false;
+
+    let error = process(&mut ctx, &[instruction], &[&mint, &user])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::SignerRequired as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_requires_payer_signature() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let payer = Pubkey::new_unique();
+    program_test.add_account(payer, system_account(1_000_000_000));
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[13].is_signer = false;
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::SignerRequired as u32)
+    );
+
+    for (wallet, lamports) in [(payer, 1_000_000_000), (user.pubkey(), 10_000_000_000)] {
+        assert_eq!(
+            ctx.banks_client.get_balance(wallet).await.unwrap(),
+            lamports
+        );
+    }
+}
+
+#[tokio::test]
+async fn mint_one_rejects_foreign_authority_signature() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let impostor = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[14].pubkey = impostor.pubkey();
+
+    let error = process(&mut ctx, &[instruction], &[&mint, &user, &impostor])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_wrongly_derived_user_data() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let user = Keypair::new();
+    fund(&mut ctx, &user.pubkey(), 1_000_000_000).await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[1].pubkey = Pubkey::new_unique();
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidUserDataPda as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_registers_fresh_wallet() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = Keypair::new();
+    fund(&mut ctx, &user.pubkey(), 10_000_000_000).await;
+
+    mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+
+    let account = ctx
+        .banks_client
+        .get_account(airdrop.user_data(&user.pubkey()))
+        .await
+        .unwrap()
+        .unwrap();
+    let data = AirdropUserData::unpack_unchecked(&account.data).unwrap();
+    assert!(data.initialized);
+    assert_eq!(data.user, user.pubkey());
+    assert_eq!(data.mints_amount, 1);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_registers_wallet_with_prefunded_user_data() {
+    let args = AirdropArgs::default();
+    let authority = Keypair::new();
+    let user = Keypair::new();
+    let (config, _) = premint_airdrop::pda::find_airdrop_config(&authority.pubkey(), &args.name);
+    let (user_data, _) = premint_airdrop::pda::find_airdrop_user_data(&config, &user.pubkey());
+
+    // Stray lamport sent to user data address before the first mint
+    let mut program_test = program_test();
+    program_test.add_account(user_data, system_account(1));
+    program_test.add_account(user.pubkey(), system_account(10_000_000_000));
+    let mut ctx = program_test.start_with_context().await;
+    let airdrop = create_pda_airdrop_with_authority(&mut ctx, authority, args).await;
+
+    mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+
+    let data = get_user_data(&mut ctx, &user_data).await;
+    assert!(data.initialized);
+    assert_eq!(data.user, user.pubkey());
+    assert_eq!(data.mints_amount, 1);
+}
+
+/// Integrator program wrapping MintOne. Forwards instruction data and accounts to airdrop
+/// program, the last account is airdrop program itself and is not forwarded
+#[cfg(feature = "test-bpf")]
+fn process_mint_wrapper(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let (_, forwarded) = accounts.split_last().unwrap();
+    let instruction = Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        data,
+        forwarded
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+    );
+
+    solana_program::program::invoke(&instruction, accounts)
+}
+
+/// Mints through wrapper program, so airdrop program runs above transaction level
+#[cfg(feature = "test-bpf")]
+async fn mint_through_wrapper(deny_cpi_mint: bool) -> Result<(), BanksClientError> {
+    let wrapper = Pubkey::new_unique();
+    let mut program_test = program_test();
+    program_test.add_builtin_program(
+        "mint_wrapper",
+        wrapper,
+        solana_program_test::processor!(process_mint_wrapper).unwrap(),
+    );
+    let mut ctx = program_test.start_with_context().await;
+    let airdrop = create_pda_airdrop(
+        &mut ctx,
+        AirdropArgs {
+            deny_cpi_mint,
+            ..AirdropArgs::default()
+        },
+    )
+    .await;
+    let user = Keypair::new();
+    fund(&mut ctx, &user.pubkey(), 10_000_000_000).await;
+    register_user(&mut ctx, &airdrop, &user.pubkey())
+        .await
+        .unwrap();
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.program_id = wrapper;
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(premint_airdrop::id(), false));
+
+    process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_rejects_cpi_when_denied() {
+    let error = mint_through_wrapper(true).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::CpiNotAllowed as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_allows_cpi_by_default() {
+    mint_through_wrapper(false).await.unwrap();
+}
+
+#[cfg(feature = "protocol-fee")]
+#[tokio::test]
+async fn mint_one_rejects_wrong_protocol_treasury() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    fund(&mut ctx, &user.pubkey(), 1_000_000_000).await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    assert_eq!(
+        instruction.accounts[20].pubkey,
+        premint_airdrop::state::PROTOCOL_TREASURY
+    );
+    instruction.accounts[20].pubkey = Pubkey::new_unique();
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+}
+
+const MIN_PAYER_BALANCE: u64 = 1_000_000_000;
+
+/// Mints with separate payer holding `payer_balance` lamports
+async fn mint_with_payer_balance(
+    ctx: &mut ProgramTestContext,
+    airdrop: &TestAirdrop,
+    user: &Keypair,
+    payer_balance: u64,
+) -> Result<(), BanksClientError> {
+    let payer = Keypair::new();
+    fund(ctx, &payer.pubkey(), payer_balance).await;
+
+    let mint = Keypair::new();
+    let instruction = mint_one_instruction(
+        airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        true,
+        &payer.pubkey(),
+    );
+
+    process(
+        ctx,
+        &[instruction],
+        &[&mint, user, &payer, &airdrop.authority],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn mint_one_rejects_payer_below_min_balance() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            min_payer_balance: MIN_PAYER_BALANCE,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    fund(&mut ctx, &user.pubkey(), 1_000_000_000).await;
+
+    let error = mint_with_payer_balance(&mut ctx, &airdrop, &user, MIN_PAYER_BALANCE - 1)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InsufficientStake as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_payer_that_can_not_afford_rent() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    fund(&mut ctx, &user.pubkey(), 1_000_000_000).await;
+
+    let error = mint_with_payer_balance(&mut ctx, &airdrop, &user, 1_000_000)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::PayerCannotAffordRent as u32)
+    );
+}
+
+/// Rent of accounts minted for a user without token account plus price
+async fn mint_cost(ctx: &mut ProgramTestContext, airdrop: &TestAirdrop) -> u64 {
+    let config = get_config(ctx, &airdrop.config).await;
+    mint_rent_lamports(&Rent::default(), &config, true).unwrap() + config.price
+}
+
+#[tokio::test]
+async fn mint_one_rejects_charged_payer_that_can_not_afford_price() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            charge_payer: true,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let cost = mint_cost(&mut ctx, &airdrop).await;
+    let error = mint_with_payer_balance(&mut ctx, &airdrop, &user, cost - 1)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::PayerCannotAffordRent as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_user_paying_own_mint_that_can_not_afford_price() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let cost = mint_cost(&mut ctx, &airdrop).await;
+    ctx.set_account(&user.pubkey(), &system_account(cost - 1).into());
+
+    let mint = Keypair::new();
+    let instruction = mint_one_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        true,
+        &user.pubkey(),
+    );
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::PayerCannotAffordRent as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_accepts_payer_at_min_balance() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(
+        &mut ctx,
+        AirdropArgs {
+            min_payer_balance: MIN_PAYER_BALANCE,
+            ..AirdropArgs::default()
+        },
+    )
+    .await;
+    let user = Keypair::new();
+    fund(&mut ctx, &user.pubkey(), 10_000_000_000).await;
+    register_user(&mut ctx, &airdrop, &user.pubkey())
+        .await
+        .unwrap();
+
+    mint_with_payer_balance(&mut ctx, &airdrop, &user, MIN_PAYER_BALANCE)
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn register_user_creates_user_data() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = Keypair::new();
+
+    let user_data = register_user(&mut ctx, &airdrop, &user.pubkey())
+        .await
+        .unwrap();
+
+    let account = ctx
+        .banks_client
+        .get_account(user_data)
+        .await
+        .unwrap()
+        .unwrap();
+    let data = AirdropUserData::unpack_unchecked(&account.data).unwrap();
+    assert!(data.initialized);
+    assert_eq!(data.airdrop, airdrop.config);
+    assert_eq!(data.user, user.pubkey());
+    assert_eq!(data.mints_amount, 0);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn register_user_assigns_registration_order() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+
+    for expected_index in 0..3 {
+        let user_data = register_user(&mut ctx, &airdrop, &Pubkey::new_unique())
+            .await
+            .unwrap();
+        let account = ctx
+            .banks_client
+            .get_account(user_data)
+            .await
+            .unwrap()
+            .unwrap();
+        let data = AirdropUserData::unpack_unchecked(&account.data).unwrap();
+        assert_eq!(data.registration_index, expected_index);
+    }
+
+    assert_eq!(
+        get_config(&mut ctx, &airdrop.config).await.registered_count,
+        3
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn batch_init_users_creates_user_data() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let users = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let payer = ctx.payer.pubkey();
+    let instruction =
+        batch_init_users_instruction(&airdrop, &airdrop.authority.pubkey(), &payer, &users);
+    process(&mut ctx, &[instruction], &[&airdrop.authority])
+        .await
+        .unwrap();
+
+    for (index, user) in users.iter().enumerate() {
+        let data = get_user_data(&mut ctx, &airdrop.user_data(user)).await;
+        assert!(data.initialized);
+        assert_eq!(data.airdrop, airdrop.config);
+        assert_eq!(data.user, *user);
+        assert_eq!(data.registration_index, index as u64);
+    }
+    assert_eq!(
+        get_config(&mut ctx, &airdrop.config).await.registered_count,
+        2
+    );
+}
+
+#[tokio::test]
+async fn batch_init_users_skips_registered_users() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let users = [
+        add_user(&mut program_test, &airdrop),
+        add_user(&mut program_test, &airdrop),
+    ];
+    let mut ctx = program_test.start_with_context().await;
+    let users: Vec<Pubkey> = users.iter().map(|user| user.pubkey()).collect();
+
+    let mut before = Vec::new();
+    for user in &users {
+        before.push(user_data_bytes(&mut ctx, &airdrop, user).await);
+    }
+
+    let payer = ctx.payer.pubkey();
+    let instruction =
+        batch_init_users_instruction(&airdrop, &airdrop.authority.pubkey(), &payer, &users);
+    process(&mut ctx, &[instruction], &[&airdrop.authority])
+        .await
+        .unwrap();
+
+    for (user, before) in users.iter().zip(before) {
+        assert_eq!(user_data_bytes(&mut ctx, &airdrop, user).await, before);
+    }
+    assert_eq!(
+        get_config(&mut ctx, &airdrop.config).await.registered_count,
+        0
+    );
+}
+
+async fn user_data_bytes(
+    ctx: &mut ProgramTestContext,
+    airdrop: &TestAirdrop,
+    user: &Pubkey,
+) -> Vec<u8> {
+    ctx.banks_client
+        .get_account(airdrop.user_data(user))
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+}
+
+#[tokio::test]
+async fn batch_init_users_rejects_odd_account_count() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let payer = ctx.payer.pubkey();
+    let mut instruction = batch_init_users_instruction(
+        &airdrop,
+        &airdrop.authority.pubkey(),
+        &payer,
+        &[user.pubkey()],
+    );
+    instruction.accounts.pop();
+
+    let error = process(&mut ctx, &[instruction], &[&airdrop.authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+}
+
+#[tokio::test]
+async fn batch_init_users_rejects_non_authority_signer() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let impostor = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = batch_init_users_instruction(
+        &airdrop,
+        &impostor.pubkey(),
+        &payer,
+        &[Pubkey::new_unique()],
+    );
+
+    let error = process(&mut ctx, &[instruction], &[&impostor])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn register_user_with_prefunded_user_data() {
+    let args = AirdropArgs::default();
+    let authority = Keypair::new();
+    let user = Keypair::new();
+    let (config, _) = premint_airdrop::pda::find_airdrop_config(&authority.pubkey(), &args.name);
+    let (user_data, _) = premint_airdrop::pda::find_airdrop_user_data(&config, &user.pubkey());
+
+    // Stray lamport sent to user data address before registration
+    let mut program_test = program_test();
+    program_test.add_account(user_data, system_account(1));
+    let mut ctx = program_test.start_with_context().await;
+    let airdrop = create_pda_airdrop_with_authority(&mut ctx, authority, args).await;
+
+    let user_data = register_user(&mut ctx, &airdrop, &user.pubkey())
+        .await
+        .unwrap();
+
+    let account = ctx
+        .banks_client
+        .get_account(user_data)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, premint_airdrop::id());
+    let data = AirdropUserData::unpack_unchecked(&account.data).unwrap();
+    assert!(data.initialized);
+    assert_eq!(data.user, user.pubkey());
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn airdrops_with_different_names_are_independent() {
+    let mut ctx = start().await;
+    let authority = Keypair::new();
+    let spring = create_pda_airdrop_with_authority(
+        &mut ctx,
+        Keypair::from_bytes(&authority.to_bytes()).unwrap(),
+        AirdropArgs {
+            name: padded(b"spring"),
+            ..AirdropArgs::default()
+        },
+    )
+    .await;
+    let autumn = create_pda_airdrop_with_authority(
+        &mut ctx,
+        authority,
+        AirdropArgs {
+            name: padded(b"autumn"),
+            ..AirdropArgs::default()
+        },
+    )
+    .await;
+    assert_ne!(spring.config, autumn.config);
+    assert_ne!(spring.mint_authority, autumn.mint_authority);
+
+    let user = Keypair::new();
+    fund(&mut ctx, &user.pubkey(), 1_000_000_000).await;
+    let spring_user = register_user(&mut ctx, &spring, &user.pubkey())
+        .await
+        .unwrap();
+    mint_one(&mut ctx, &spring, &user).await.unwrap();
+
+    let spring_config = get_config(&mut ctx, &spring.config).await;
+    let autumn_config = get_config(&mut ctx, &autumn.config).await;
+    assert_eq!(spring_config.name, padded(b"spring"));
+    assert_eq!(autumn_config.name, padded(b"autumn"));
+    assert_eq!(spring_config.airdrop_index, 1);
+    assert_eq!(spring_config.airdrop_users, 1);
+    assert_eq!(autumn_config.airdrop_index, 0);
+    assert_eq!(autumn_config.airdrop_users, 0);
+
+    let (autumn_user, _) =
+        premint_airdrop::pda::find_airdrop_user_data(&autumn.config, &user.pubkey());
+    assert_ne!(spring_user, autumn_user);
+    assert!(ctx
+        .banks_client
+        .get_account(autumn_user)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_mints_token_and_pays_price() {
+    let mut ctx = start().await;
+    let args = AirdropArgs::default();
+    let price = args.price;
+    let airdrop = create_pda_airdrop(&mut ctx, args).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    let revenue_before = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+
+    let mint = mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+
+    let token_account =
+        spl_associated_token_account::get_associated_token_address(&user.pubkey(), &mint);
+    let account = ctx
+        .banks_client
+        .get_account(token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let token = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(token.amount, 1);
+
+    let revenue_after = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+    assert_eq!(revenue_after - revenue_before, price);
+
+    let account = ctx
+        .banks_client
+        .get_account(airdrop.config)
+        .await
+        .unwrap()
+        .unwrap();
+    let config = AirdropConfig::unpack_unchecked(&account.data).unwrap();
+    assert_eq!(config.airdrop_index, 1);
+
+    let account = ctx
+        .banks_client
+        .get_account(airdrop.user_data(&user.pubkey()))
+        .await
+        .unwrap()
+        .unwrap();
+    let data = AirdropUserData::unpack_unchecked(&account.data).unwrap();
+    assert_eq!(data.mints_amount, 1);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_locks_minted_token() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    let mint = mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+
+    // Master edition of 1/1 token holds mint authority
+    let account = ctx.banks_client.get_account(mint).await.unwrap().unwrap();
+    let mint_data = spl_token::state::Mint::unpack(&account.data).unwrap();
+    assert_eq!(
+        mint_data.mint_authority,
+        Some(premint_airdrop::pda::find_master_edition(&mint)).into()
+    );
+    assert!(mint_data.freeze_authority.is_none());
+    assert_eq!(mint_data.supply, 1);
+    assert_eq!(mint_data.decimals, 0);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_mints_quantity_with_expected_decimals() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        expected_decimals: 2,
+        mint_quantity: 250,
+        ..AirdropArgs::default()
+    };
+    let airdrop = create_pda_airdrop(&mut ctx, args).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    let mint = mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+
+    let account = ctx.banks_client.get_account(mint).await.unwrap().unwrap();
+    let mint_data = spl_token::state::Mint::unpack(&account.data).unwrap();
+    assert!(mint_data.mint_authority.is_none());
+    assert_eq!(mint_data.decimals, 2);
+    assert_eq!(mint_data.supply, 250);
+
+    let token_account =
+        spl_associated_token_account::get_associated_token_address(&user.pubkey(), &mint);
+    let account = ctx
+        .banks_client
+        .get_account(token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let token = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(token.amount, 250);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_free_mint_takes_read_only_revenue_wallet() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        price: 0,
+        ..AirdropArgs::default()
+    };
+    let airdrop = create_pda_airdrop(&mut ctx, args).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+    let revenue_before = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[16].is_writable = false;
+
+    process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let revenue_after = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+    assert_eq!(revenue_after, revenue_before);
+    assert_eq!(get_config(&mut ctx, &airdrop.config).await.airdrop_index, 1);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_gift_mints_to_recipient() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+    let recipient = Pubkey::new_unique();
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction =
+        mint_one_gift_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), &recipient, &payer);
+
+    process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let token_account =
+        spl_associated_token_account::get_associated_token_address(&recipient, &mint.pubkey());
+    let account = ctx
+        .banks_client
+        .get_account(token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let token = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(token.owner, recipient);
+    assert_eq!(token.amount, 1);
+
+    let account = ctx
+        .banks_client
+        .get_account(airdrop.user_data(&user.pubkey()))
+        .await
+        .unwrap()
+        .unwrap();
+    let data = AirdropUserData::unpack_unchecked(&account.data).unwrap();
+    assert_eq!(data.mints_amount, 1);
+}
+
+#[tokio::test]
+async fn mint_one_gift_rejects_off_curve_recipient() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_one_gift_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        &airdrop.mint_authority,
+        &payer,
+    );
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidRecipient as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_marks_primary_sale_happened() {
+    use metaplex_token_metadata::{
+        state::{Key, Metadata, MAX_METADATA_LEN},
+        utils::try_from_slice_checked,
+    };
+
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    let mint = mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+
+    let metadata = premint_airdrop::pda::find_metadata(&mint);
+    let account = ctx
+        .banks_client
+        .get_account(metadata)
+        .await
+        .unwrap()
+        .unwrap();
+    let metadata: Metadata =
+        try_from_slice_checked(&account.data, Key::MetadataV1, MAX_METADATA_LEN).unwrap();
+    assert!(metadata.primary_sale_happened);
+    assert_eq!(metadata.update_authority, airdrop.admin);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_creates_master_edition() {
+    use metaplex_token_metadata::{
+        state::{Key, MasterEditionV2, MAX_MASTER_EDITION_LEN},
+        utils::try_from_slice_checked,
+    };
+
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    let mint = mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+
+    let master_edition = premint_airdrop::pda::find_master_edition(&mint);
+    let account = ctx
+        .banks_client
+        .get_account(master_edition)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, metaplex_token_metadata::id());
+    let edition: MasterEditionV2 =
+        try_from_slice_checked(&account.data, Key::MasterEditionV2, MAX_MASTER_EDITION_LEN)
+            .unwrap();
+    assert_eq!(edition.supply, 0);
+    assert_eq!(edition.max_supply, Some(0));
+}
+
+#[tokio::test]
+async fn mint_one_rejects_third_party_token_account() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[4].pubkey = spl_associated_token_account::get_associated_token_address(
+        &Pubkey::new_unique(),
+        &mint.pubkey(),
+    );
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidTokenAccountPda as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_wrong_metadata_account() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[5].pubkey = Pubkey::new_unique();
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidMetadataPda as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_wrong_master_edition_account() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[19].pubkey =
+        premint_airdrop::pda::find_master_edition(&Pubkey::new_unique());
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidMasterEditionPda as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_wrong_mint_authority() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[6].pubkey = Pubkey::new_unique();
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidMintAuthorityPda as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_wrong_token_metadata_program() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[12].pubkey = Pubkey::new_unique();
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidProgramAccount as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_payer_as_revenue_wallet() {
+    let mut program_test = program_test();
+    let revenue_wallet = Keypair::new();
+    let airdrop = add_airdrop_with_revenue_wallet(
+        &mut program_test,
+        AirdropArgs::default(),
+        revenue_wallet.pubkey(),
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let instruction = mint_one_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        true,
+        &revenue_wallet.pubkey(),
+    );
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority, &revenue_wallet],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::DuplicateAccount as u32)
+    );
+}
+
+#[tokio::test]
+async fn user_can_mint_after_cooldown() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let locked_till = 1_000_000;
+    let user = add_locked_user(&mut program_test, &airdrop, locked_till);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mut clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = locked_till as i64 - 1;
+    ctx.set_sysvar(&clock);
+
+    let instruction = can_mint_instruction(&airdrop, &user.pubkey());
+    let error = process(&mut ctx, std::slice::from_ref(&instruction), &[])
+        .await
+        .unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::UserTimeout as u32));
+
+    // Cooldown is over once it fully elapsed since last mint
+    clock.unix_timestamp = locked_till as i64;
+    ctx.set_sysvar(&clock);
+
+    // Different fee payer signature keeps transaction unique
+    let payer = Keypair::new();
+    fund(&mut ctx, &payer.pubkey(), 1_000_000_000).await;
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn user_can_mint_in_same_second_without_cooldown() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            mint_cooldown_secs: 0,
+            ..AirdropArgs::default()
+        },
+    );
+    let locked_till = 1_000_000;
+    // Mint at `locked_till` with zero cooldown leaves user locked till the same second
+    let user = add_locked_user(&mut program_test, &airdrop, locked_till);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mut clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = locked_till as i64;
+    ctx.set_sysvar(&clock);
+
+    let instruction = can_mint_instruction(&airdrop, &user.pubkey());
+    process(&mut ctx, &[instruction], &[]).await.unwrap();
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn user_mints_back_to_back_without_cooldown() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(
+        &mut ctx,
+        AirdropArgs {
+            mint_cooldown_secs: 0,
+            ..AirdropArgs::default()
+        },
+    )
+    .await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+    mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.airdrop_index, 2);
+}
+
+#[tokio::test]
+async fn mints_are_gated_by_updated_window() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let now = 1_000_000;
+    let mut clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = now;
+    ctx.set_sysvar(&clock);
+
+    process(
+        &mut ctx,
+        &[update_window_instruction(&airdrop, now + 100, now + 200)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.start_ts, now + 100);
+    assert_eq!(config.end_ts, now + 200);
+
+    let error = process(
+        &mut ctx,
+        &[can_mint_instruction(&airdrop, &user.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::MintWindowClosed as u32)
+    );
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::MintWindowClosed as u32)
+    );
+
+    // Window is moved to include current time
+    process(
+        &mut ctx,
+        &[
+            update_window_instruction(&airdrop, now - 100, now + 100),
+            can_mint_instruction(&airdrop, &user.pubkey()),
+        ],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    clock.unix_timestamp = now + 100;
+    ctx.set_sysvar(&clock);
+
+    let error = process(
+        &mut ctx,
+        &[can_mint_instruction(&airdrop, &user.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::MintWindowClosed as u32)
+    );
+}
+
+#[tokio::test]
+async fn update_window_rejects_end_before_start() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let error = process(
+        &mut ctx,
+        &[update_window_instruction(&airdrop, 200, 100)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidWindow as u32)
+    );
+}
+
+#[tokio::test]
+async fn update_window_requires_airdrop_authority() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let impostor = Keypair::new();
+    let mut instruction = update_window_instruction(&airdrop, 0, 0);
+    instruction.accounts[1].pubkey = impostor.pubkey();
+
+    let error = process(&mut ctx, &[instruction], &[&impostor])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+}
+
+#[tokio::test]
+async fn update_revenue_wallet_stores_wallet() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let revenue_wallet = Pubkey::new_unique();
+    process(
+        &mut ctx,
+        &[update_revenue_wallet_instruction(&airdrop, &revenue_wallet)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.revenues_wallet, revenue_wallet);
+}
+
+#[tokio::test]
+async fn update_revenue_wallet_rejects_default_and_program_accounts() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    for revenue_wallet in [Pubkey::default(), airdrop.config, airdrop.mint_authority] {
+        let error = process(
+            &mut ctx,
+            &[update_revenue_wallet_instruction(&airdrop, &revenue_wallet)],
+            &[&airdrop.authority],
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            airdrop_error(error),
+            Some(AirdropError::InvalidRevenueWallet as u32)
+        );
+    }
+}
+
+#[tokio::test]
+async fn update_revenue_wallet_requires_airdrop_authority() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let impostor = Keypair::new();
+    let mut instruction = update_revenue_wallet_instruction(&airdrop, &impostor.pubkey());
+    instruction.accounts[1].pubkey = impostor.pubkey();
+
+    let error = process(&mut ctx, &[instruction], &[&impostor])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_pays_updated_revenue_wallet() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        mint_cooldown_secs: 0,
+        ..AirdropArgs::default()
+    };
+    let price = args.price;
+    let mut airdrop = create_pda_airdrop(&mut ctx, args).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+    let old_wallet = airdrop.revenue_wallet;
+    let new_wallet = Pubkey::new_unique();
+    fund(&mut ctx, &new_wallet, 1_000_000_000).await;
+
+    let old_before = ctx.banks_client.get_balance(old_wallet).await.unwrap();
+    mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+    let old_after = ctx.banks_client.get_balance(old_wallet).await.unwrap();
+    assert_eq!(old_after - old_before, price);
+
+    process(
+        &mut ctx,
+        &[update_revenue_wallet_instruction(&airdrop, &new_wallet)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+    airdrop.revenue_wallet = new_wallet;
+
+    mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+    let new_after = ctx.banks_client.get_balance(new_wallet).await.unwrap();
+    assert_eq!(new_after - 1_000_000_000, price);
+    assert_eq!(
+        ctx.banks_client.get_balance(old_wallet).await.unwrap(),
+        old_after
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_fairness_above_whole_supply() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        fairness_bps: 10_001,
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::BadInstructionArgument as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_open_mint_raffle() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        raffle: true,
+        require_registration: false,
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::BadInstructionArgument as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_zero_supply() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        airdrop_amount: 0,
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidSupply as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_supply_above_ceiling() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        airdrop_amount: premint_airdrop::state::MAX_AIRDROP_AMOUNT + 1,
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidSupply as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_stores_token_shape() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        expected_decimals: 2,
+        mint_quantity: 250,
+        ..AirdropArgs::default()
+    };
+
+    let airdrop = create_airdrop(&mut ctx, args).await;
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.expected_decimals, 2);
+    assert_eq!(config.mint_quantity, 250);
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_zero_mint_quantity() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        mint_quantity: 0,
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::BadInstructionArgument as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_too_many_decimals() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        expected_decimals: premint_airdrop::state::MAX_DECIMALS + 1,
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::BadInstructionArgument as u32)
+    );
+}
+
+#[cfg(not(feature = "no-price-floor"))]
+#[tokio::test]
+async fn initialize_airdrop_rejects_price_below_floor() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        price: 1_000,
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::SuspiciousPrice as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_allows_free_mint() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        price: 0,
+        ..AirdropArgs::default()
+    };
+
+    let airdrop = create_airdrop(&mut ctx, args).await;
+    assert_eq!(get_config(&mut ctx, &airdrop.config).await.price, 0);
+}
+
+#[cfg(feature = "no-price-ceiling")]
+#[tokio::test]
+async fn initialize_airdrop_rejects_price_overflowing_batch() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        price: u64::MAX / 2,
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidPrice as u32)
+    );
+}
+
+#[cfg(not(feature = "no-price-ceiling"))]
+#[tokio::test]
+async fn initialize_airdrop_rejects_price_above_ceiling() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        price: premint_airdrop::state::MAX_PRICE + 1,
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidPrice as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_invalid_symbol() {
+    for symbol in [*b"KA TT\0\0\0", *b"KA\0TT\0\0\0", *b"K\xc3\xa9TT\0\0\0"] {
+        let mut ctx = start().await;
+        let args = AirdropArgs {
+            symbol,
+            ..AirdropArgs::default()
+        };
+
+        let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+        assert_eq!(
+            airdrop_error(error),
+            Some(AirdropError::InvalidSymbol as u32)
+        );
+    }
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_split_utf8_base_uri() {
+    let mut ctx = start().await;
+    let mut base_uri = vec![b'a'; 32];
+    base_uri[31] = 0xc3;
+    let args = AirdropArgs {
+        base_uri,
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidMetadataPrefix as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_stores_long_base_uri() {
+    let mut ctx = start().await;
+    let base_uri = format!("https://arweave.net/{}", "a".repeat(150)).into_bytes();
+    let airdrop = create_airdrop(
+        &mut ctx,
+        AirdropArgs {
+            base_uri: base_uri.clone(),
+            ..AirdropArgs::default()
+        },
+    )
+    .await;
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.base_uri(), &base_uri[..]);
+}
+
+#[tokio::test]
+async fn initialize_airdrop_accepts_fixed_layout() {
+    let mut ctx = start().await;
+    let airdrop = create_airdrop(
+        &mut ctx,
+        AirdropArgs {
+            fixed_layout: true,
+            ..AirdropArgs::default()
+        },
+    )
+    .await;
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.base_uri(), b"https://example.com/katts");
+    assert_eq!(config.symbol, padded(b"KATT"));
+    assert_eq!(config.price, 100_000_000);
+}
+
+#[tokio::test]
+async fn initialize_airdrop_fixed_layout_defaults_newer_fields() {
+    let mut ctx = start().await;
+    let airdrop = create_airdrop(
+        &mut ctx,
+        AirdropArgs {
+            fixed_layout: true,
+            mint_quantity: 5,
+            deny_cpi_mint: true,
+            min_payer_balance: 1_000,
+            ..AirdropArgs::default()
+        },
+    )
+    .await;
+
+    // Legacy layout does not carry them, so program defaults apply
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.mint_quantity, 1);
+    assert!(!config.deny_cpi_mint);
+    assert_eq!(config.min_payer_balance, 0);
+    assert!(config.require_registration);
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_too_long_base_uri() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        base_uri: vec![b'a'; MAX_URI_LENGTH + 1],
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(airdrop_error(error), Some(AirdropError::UriTooLong as u32));
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_empty_symbol() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        symbol: [0; 8],
+        ..AirdropArgs::default()
+    };
+
+    let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidMetadataParams as u32)
+    );
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_empty_base_uri() {
+    // Legacy layout pads it into an all-zero 32 byte prefix
+    for fixed_layout in [false, true].iter() {
+        let mut ctx = start().await;
+        let args = AirdropArgs {
+            base_uri: vec![],
+            fixed_layout: *fixed_layout,
+            ..AirdropArgs::default()
+        };
+
+        let error = try_create_airdrop(&mut ctx, args).await.err().unwrap();
+        assert_eq!(
+            airdrop_error(error),
+            Some(AirdropError::InvalidMetadataParams as u32)
+        );
+    }
+}
+
+#[tokio::test]
+async fn initialize_airdrop_accepts_shortest_metadata_params() {
+    let mut ctx = start().await;
+    let airdrop = create_airdrop(
+        &mut ctx,
+        AirdropArgs {
+            base_uri: b"a".to_vec(),
+            symbol: *b"K\0\0\0\0\0\0\0",
+            ..AirdropArgs::default()
+        },
+    )
+    .await;
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.base_uri(), b"a");
+    assert_eq!(&config.symbol, b"K\0\0\0\0\0\0\0");
+}
+
+#[tokio::test]
+async fn mint_one_rejects_self_referral() {
+    let mut program_test = program_test();
+    let args = AirdropArgs {
+        referral_bps: 1000,
+        ..AirdropArgs::default()
+    };
+    let airdrop = add_airdrop(&mut program_test, args);
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction
+        .accounts
+        .push(AccountMeta::new(user.pubkey(), false));
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidReferrer as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_pays_referral_reward() {
+    let mut ctx = start().await;
+    let args = AirdropArgs {
+        referral_bps: 1000,
+        ..AirdropArgs::default()
+    };
+    let price = args.price;
+    let airdrop = create_pda_airdrop(&mut ctx, args).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+    let referrer = Pubkey::new_unique();
+    fund(&mut ctx, &referrer, 1_000_000_000).await;
+
+    let revenue_before = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts.push(AccountMeta::new(referrer, false));
+    process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let revenue_after = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+    let referrer_balance = ctx.banks_client.get_balance(referrer).await.unwrap();
+    assert_eq!(referrer_balance - 1_000_000_000, price / 10);
+    assert_eq!(revenue_after - revenue_before, price - price / 10);
+}
+
+#[tokio::test]
+async fn initialize_airdrop_rejects_wrong_system_program() {
+    let mut ctx = start().await;
+    let config = Keypair::new();
+    let revenue_wallet = Keypair::new().pubkey();
+    fund(&mut ctx, &revenue_wallet, 1_000_000_000).await;
+
+    let payer = ctx.payer.pubkey();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let mut instruction = initialize_airdrop_instruction(
+        &config.pubkey(),
+        &Pubkey::new_unique(),
+        &revenue_wallet,
+        &Pubkey::new_unique(),
+        &payer,
+        &AirdropArgs::default(),
+    );
+    instruction.accounts[7].pubkey = spl_token::id();
+
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &config.pubkey(),
+            rent.minimum_balance(AirdropConfig::LEN),
+            AirdropConfig::LEN as u64,
+            &premint_airdrop::id(),
+        ),
+        instruction,
+    ];
+
+    let error = process(&mut ctx, &instructions, &[&config])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidProgramAccount as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_locked_user_without_bot_tax() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_locked_user(&mut program_test, &airdrop, i64::MAX as u64);
+    let mut ctx = program_test.start_with_context().await;
+
+    let error = mint_one(&mut ctx, &airdrop, &user).await.unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::UserTimeout as u32));
+}
+
+#[tokio::test]
+async fn mint_one_charges_bot_tax_from_locked_user() {
+    let mut program_test = program_test();
+    let bot_tax = 10_000_000;
+    let args = AirdropArgs {
+        bot_tax_lamports: bot_tax,
+        ..AirdropArgs::default()
+    };
+    let airdrop = add_airdrop(&mut program_test, args);
+    let user = add_locked_user(&mut program_test, &airdrop, i64::MAX as u64);
+    let mut ctx = program_test.start_with_context().await;
+
+    let revenue_before = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+
+    let mint = mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+
+    let revenue_after = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+    assert_eq!(revenue_after - revenue_before, bot_tax);
+
+    // Nothing is minted and supply is untouched
+    assert!(ctx.banks_client.get_account(mint).await.unwrap().is_none());
+    let account = ctx
+        .banks_client
+        .get_account(airdrop.config)
+        .await
+        .unwrap()
+        .unwrap();
+    let config = AirdropConfig::unpack_unchecked(&account.data).unwrap();
+    assert_eq!(config.airdrop_index, 0);
+}
+
+#[tokio::test]
+async fn mint_one_bot_tax_keeps_hard_failures() {
+    let mut program_test = program_test();
+    let args = AirdropArgs {
+        bot_tax_lamports: 10_000_000,
+        ..AirdropArgs::default()
+    };
+    let airdrop = add_airdrop(&mut program_test, args);
+    let user = add_locked_user(&mut program_test, &airdrop, i64::MAX as u64);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction.accounts[7].pubkey = spl_token::id();
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidProgramAccount as u32)
+    );
+}
+
+const BOT_TAX: u64 = 10_000_000;
+
+fn bot_taxed_airdrop(program_test: &mut ProgramTest, stage_count: u8) -> TestAirdrop {
+    add_airdrop(
+        program_test,
+        AirdropArgs {
+            bot_tax_lamports: BOT_TAX,
+            stage_count,
+            ..AirdropArgs::default()
+        },
+    )
+}
+
+/// Adds bot taxed airdrop with single stage and registered user
+fn bot_taxed_stage(
+    program_test: &mut ProgramTest,
+    stage: impl FnOnce(&TestAirdrop) -> AirdropStage,
+) -> (TestAirdrop, Keypair, Pubkey) {
+    let airdrop = bot_taxed_airdrop(program_test, 1);
+    let user = add_user(program_test, &airdrop);
+    let stage = add_stage(program_test, stage(&airdrop));
+
+    (airdrop, user, stage)
+}
+
+/// Mints once and asserts that payer is charged bot tax and nothing is minted
+async fn assert_bot_taxed(
+    ctx: &mut ProgramTestContext,
+    airdrop: &TestAirdrop,
+    user: &Keypair,
+    stage: Option<&Pubkey>,
+) {
+    let revenue_before = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = match stage {
+        Some(stage) => mint_one_in_stage_instruction(
+            airdrop,
+            &mint.pubkey(),
+            &user.pubkey(),
+            &payer,
+            stage,
+            &[],
+        ),
+        None => mint_one_instruction(airdrop, &mint.pubkey(), &user.pubkey(), true, &payer),
+    };
+    process(ctx, &[instruction], &[&mint, user, &airdrop.authority])
+        .await
+        .unwrap();
+
+    let revenue_after = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+    assert_eq!(revenue_after - revenue_before, BOT_TAX);
+    assert!(ctx
+        .banks_client
+        .get_account(mint.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(get_config(ctx, &airdrop.config).await.airdrop_index, 0);
+}
+
+#[tokio::test]
+async fn mint_one_charges_bot_tax_outside_mint_window() {
+    let mut program_test = program_test();
+    let airdrop = bot_taxed_airdrop(&mut program_test, 0);
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    update_config(&mut ctx, &airdrop, |config| config.end_ts = 1).await;
+
+    assert_bot_taxed(&mut ctx, &airdrop, &user, None).await;
+}
+
+#[tokio::test]
+async fn mint_one_charges_bot_tax_in_inactive_stage() {
+    let mut program_test = program_test();
+    let (airdrop, user, stage) = bot_taxed_stage(&mut program_test, |airdrop| AirdropStage {
+        start_ts: i64::MAX - 1,
+        ..open_stage(airdrop, 0)
+    });
+    let mut ctx = program_test.start_with_context().await;
+
+    assert_bot_taxed(&mut ctx, &airdrop, &user, Some(&stage)).await;
+}
+
+#[tokio::test]
+async fn mint_one_charges_bot_tax_from_user_not_on_allowlist() {
+    let mut program_test = program_test();
+    let (airdrop, user, stage) = bot_taxed_stage(&mut program_test, |airdrop| AirdropStage {
+        merkle_root: allowlist_leaf(&Pubkey::new_unique()),
+        ..open_stage(airdrop, 0)
+    });
+    let mut ctx = program_test.start_with_context().await;
+
+    assert_bot_taxed(&mut ctx, &airdrop, &user, Some(&stage)).await;
+}
+
+#[tokio::test]
+async fn mint_one_charges_bot_tax_from_user_over_stage_limit() {
+    let mut program_test = program_test();
+    let (airdrop, user, stage) = bot_taxed_stage(&mut program_test, |airdrop| AirdropStage {
+        per_user_limit: 1,
+        ..open_stage(airdrop, 0)
+    });
+    let mut ctx = program_test.start_with_context().await;
+    update_user_data(&mut ctx, &airdrop, &user.pubkey(), |data| {
+        data.mints_amount = 1
+    })
+    .await;
+
+    assert_bot_taxed(&mut ctx, &airdrop, &user, Some(&stage)).await;
+}
+
+#[tokio::test]
+async fn mint_one_bot_tax_keeps_hard_stage_failures() {
+    let mut program_test = program_test();
+    let (airdrop, user, stage) = bot_taxed_stage(&mut program_test, |airdrop| AirdropStage {
+        minted: 10,
+        ..open_stage(airdrop, 0)
+    });
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_one_in_stage_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        &payer,
+        &stage,
+        &[],
+    );
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::OutOfSupply as u32));
+}
+
+#[tokio::test]
+async fn sweep_mint_authority_empties_program_owned_pda() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    program_test.add_account(
+        airdrop.mint_authority,
+        solana_sdk::account::Account {
+            lamports: 5_000_000,
+            owner: premint_airdrop::id(),
+            ..solana_sdk::account::Account::default()
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    process(
+        &mut ctx,
+        &[sweep_mint_authority_instruction(&airdrop)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    assert!(ctx
+        .banks_client
+        .get_account(airdrop.mint_authority)
+        .await
+        .unwrap()
+        .is_none());
+    let revenue = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+    assert_eq!(revenue, 1_000_000_000 + 5_000_000);
+}
+
+#[tokio::test]
+async fn sweep_mint_authority_empties_hand_funded_pda() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+    fund(&mut ctx, &airdrop.mint_authority, 1_000_000).await;
+
+    process(
+        &mut ctx,
+        &[sweep_mint_authority_instruction(&airdrop)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let mint_authority = ctx
+        .banks_client
+        .get_balance(airdrop.mint_authority)
+        .await
+        .unwrap();
+    assert_eq!(mint_authority, 0);
+    let revenue = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+    assert_eq!(revenue, 1_000_000_000 + 1_000_000);
+}
+
+#[tokio::test]
+async fn sweep_mint_authority_rejects_other_destination() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+    fund(&mut ctx, &airdrop.mint_authority, 1_000_000).await;
+
+    let mut instruction = sweep_mint_authority_instruction(&airdrop);
+    instruction.accounts[3].pubkey = ctx.payer.pubkey();
+
+    let error = process(&mut ctx, &[instruction], &[&airdrop.authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+}
+
+const GOVERNANCE_SEED: &[u8] = b"governance";
+
+/// Governance-like program owning airdrop through its PDA. Forwards instruction data
+/// to airdrop program with accounts `[airdrop config, governance PDA]`, signing for the PDA
+fn process_governance(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (authority, bump) = Pubkey::find_program_address(&[GOVERNANCE_SEED], program_id);
+    let instruction = Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        data,
+        vec![
+            AccountMeta::new(*accounts[0].key, false),
+            AccountMeta::new_readonly(authority, true),
+        ],
+    );
+
+    invoke_signed(&instruction, accounts, &[&[GOVERNANCE_SEED, &[bump]]])
+}
+
+#[tokio::test]
+async fn pda_authority_updates_window_through_cpi() {
+    let governance = Pubkey::new_unique();
+    let (authority, _) = Pubkey::find_program_address(&[GOVERNANCE_SEED], &governance);
+
+    let mut program_test = program_test();
+    program_test.add_program(
+        "governance",
+        governance,
+        solana_program_test::processor!(process_governance),
+    );
+    let config = add_airdrop_with_authority(&mut program_test, AirdropArgs::default(), &authority);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mut data = vec![9];
+    data.extend_from_slice(&100i64.to_le_bytes());
+    data.extend_from_slice(&200i64.to_le_bytes());
+    let instruction = Instruction::new_with_bytes(
+        governance,
+        &data,
+        vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(authority, false),
+            AccountMeta::new_readonly(premint_airdrop::id(), false),
+        ],
+    );
+
+    process(&mut ctx, &[instruction], &[]).await.unwrap();
+
+    let config = get_config(&mut ctx, &config).await;
+    assert_eq!(config.start_ts, 100);
+    assert_eq!(config.end_ts, 200);
+}
+
+fn stage_args(stage_index: u8, start_ts: i64, end_ts: i64) -> AddStageArgs {
+    AddStageArgs {
+        stage_index,
+        price: 50_000_000,
+        supply_cap: 5,
+        start_ts,
+        end_ts,
+        merkle_root: [0; 32],
+        per_user_limit: 0,
+    }
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn add_stage_creates_stage() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let payer = ctx.payer.pubkey();
+
+    process(
+        &mut ctx,
+        &[add_stage_instruction(
+            &airdrop,
+            &payer,
+            &stage_args(0, 100, 200),
+            &[],
+        )],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let (stage, bump) = premint_airdrop::pda::find_stage(&airdrop.config, 0);
+    let account = ctx.banks_client.get_account(stage).await.unwrap().unwrap();
+    assert_eq!(account.owner, premint_airdrop::id());
+    let stage = AirdropStage::unpack(&account.data).unwrap();
+    assert_eq!(stage.airdrop, airdrop.config);
+    assert_eq!(stage.bump, bump);
+    assert_eq!(stage.price, 50_000_000);
+    assert_eq!(stage.start_ts, 100);
+    assert_eq!(stage.end_ts, 200);
+    assert_eq!(get_config(&mut ctx, &airdrop.config).await.stage_count, 1);
+}
+
+#[tokio::test]
+async fn add_stage_rejects_overlapping_window() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            stage_count: 1,
+            ..AirdropArgs::default()
+        },
+    );
+    let existing = add_stage(&mut program_test, open_stage(&airdrop, 0));
+    let mut ctx = program_test.start_with_context().await;
+    let payer = ctx.payer.pubkey();
+
+    let instruction =
+        add_stage_instruction(&airdrop, &payer, &stage_args(1, 100, 200), &[existing]);
+
+    let error = process(&mut ctx, &[instruction], &[&airdrop.authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::StageOverlap as u32)
+    );
+}
+
+#[tokio::test]
+async fn add_stage_requires_every_existing_stage() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            stage_count: 1,
+            ..AirdropArgs::default()
+        },
+    );
+    add_stage(
+        &mut program_test,
+        AirdropStage {
+            start_ts: 0,
+            end_ts: 100,
+            ..open_stage(&airdrop, 0)
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+    let payer = ctx.payer.pubkey();
+
+    let instruction = add_stage_instruction(&airdrop, &payer, &stage_args(1, 50, 200), &[]);
+
+    let error = process(&mut ctx, &[instruction], &[&airdrop.authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+}
+
+#[tokio::test]
+async fn remove_stage_returns_rent() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            stage_count: 1,
+            ..AirdropArgs::default()
+        },
+    );
+    let stage = add_stage(&mut program_test, open_stage(&airdrop, 0));
+    let mut ctx = program_test.start_with_context().await;
+    let receiver = Pubkey::new_unique();
+    let stage_lamports = ctx.banks_client.get_balance(stage).await.unwrap();
+
+    process(
+        &mut ctx,
+        &[remove_stage_instruction(&airdrop, &stage, &receiver)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    assert!(ctx.banks_client.get_account(stage).await.unwrap().is_none());
+    assert_eq!(
+        ctx.banks_client.get_balance(receiver).await.unwrap(),
+        stage_lamports
+    );
+    assert_eq!(get_config(&mut ctx, &airdrop.config).await.stage_count, 0);
+}
+
+#[tokio::test]
+async fn remove_stage_rejects_stage_with_mints() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            stage_count: 1,
+            ..AirdropArgs::default()
+        },
+    );
+    let stage = add_stage(
+        &mut program_test,
+        AirdropStage {
+            minted: 1,
+            ..open_stage(&airdrop, 0)
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+    let receiver = ctx.payer.pubkey();
+
+    let error = process(
+        &mut ctx,
+        &[remove_stage_instruction(&airdrop, &stage, &receiver)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::StageInUse as u32));
+}
+
+#[tokio::test]
+async fn mint_one_rejects_inactive_stage() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            stage_count: 1,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let stage = add_stage(
+        &mut program_test,
+        AirdropStage {
+            start_ts: i64::MAX - 1,
+            ..open_stage(&airdrop, 0)
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_one_in_stage_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        &payer,
+        &stage,
+        &[],
+    );
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::StageNotActive as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_stage_of_other_airdrop() {
+    let mut program_test = program_test();
+    let [airdrop, other] = [(); 2].map(|_| {
+        add_airdrop(
+            &mut program_test,
+            AirdropArgs {
+                stage_count: 1,
+                ..AirdropArgs::default()
+            },
+        )
+    });
+    let user = add_user(&mut program_test, &airdrop);
+    let stage = add_stage(&mut program_test, open_stage(&other, 0));
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_one_in_stage_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        &payer,
+        &stage,
+        &[],
+    );
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidStagePda as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_user_not_on_allowlist() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            stage_count: 1,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let stage = add_stage(
+        &mut program_test,
+        AirdropStage {
+            merkle_root: allowlist_leaf(&Pubkey::new_unique()),
+            ..open_stage(&airdrop, 0)
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_one_in_stage_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        &payer,
+        &stage,
+        &[],
+    );
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::NotAllowlisted as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_in_stage_charges_stage_price() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            stage_count: 1,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let stage = add_stage(
+        &mut program_test,
+        AirdropStage {
+            price: 30_000_000,
+            merkle_root: allowlist_leaf(&user.pubkey()),
+            ..open_stage(&airdrop, 0)
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_one_in_stage_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        &payer,
+        &stage,
+        &[],
+    );
+
+    process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(airdrop.revenue_wallet)
+            .await
+            .unwrap(),
+        1_000_000_000 + 30_000_000
+    );
+    let account = ctx.banks_client.get_account(stage).await.unwrap().unwrap();
+    assert_eq!(AirdropStage::unpack(&account.data).unwrap().minted, 1);
+}
+
+#[tokio::test]
+async fn sweep_user_account_returns_rent_after_grace_period() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let user_data = airdrop.user_data(&user.pubkey());
+    let rent = ctx.banks_client.get_balance(user_data).await.unwrap();
+    let authority_before = ctx
+        .banks_client
+        .get_balance(airdrop.authority.pubkey())
+        .await
+        .unwrap();
+
+    // Window that ended at the start of unix time is long past grace period
+    process(
+        &mut ctx,
+        &[
+            update_window_instruction(&airdrop, 0, 1),
+            sweep_user_account_instruction(&airdrop, &user.pubkey()),
+        ],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    assert!(ctx
+        .banks_client
+        .get_account(user_data)
+        .await
+        .unwrap()
+        .is_none());
+    let authority_after = ctx
+        .banks_client
+        .get_balance(airdrop.authority.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(authority_after, authority_before + rent);
+}
+
+#[tokio::test]
+async fn sweep_user_account_rejects_open_airdrop() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let error = process(
+        &mut ctx,
+        &[sweep_user_account_instruction(&airdrop, &user.pubkey())],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::AirdropNotEnded as u32)
+    );
+}
+
+#[tokio::test]
+async fn sweep_user_account_rejects_within_grace_period() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    let now = ctx
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+
+    let error = process(
+        &mut ctx,
+        &[
+            update_window_instruction(&airdrop, 0, now - 100),
+            sweep_user_account_instruction(&airdrop, &user.pubkey()),
+        ],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::AirdropNotEnded as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_to_escrow_rejects_escrow_of_other_index() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_to_escrow_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        &payer,
+        &Pubkey::new_unique(),
+        1,
+    );
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidEscrowPda as u32)
+    );
+}
+
+/// Compute units and logs of CanMint run by `<program_name>.so` build
+#[cfg(feature = "test-bpf")]
+async fn can_mint_compute(program_name: &str) -> (u64, Vec<String>) {
+    let mut program_test = program_test_of_build(program_name);
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    simulate_compute(
+        &mut ctx,
+        can_mint_instruction(&airdrop, &user.pubkey()),
+        &[],
+    )
+    .await
+}
+
+/// Compares release build with `premint_airdrop_verbose.so`, built by
+/// `cargo build-bpf --features verbose-logs` and renamed in the deploy directory
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn silent_build_saves_compute_of_debug_logs() {
+    let debug_log = "Program log: Check mint window";
+    let (silent_units, silent_logs) = can_mint_compute("premint_airdrop").await;
+    let (verbose_units, verbose_logs) = can_mint_compute("premint_airdrop_verbose").await;
+
+    assert!(!silent_logs.iter().any(|log| log == debug_log));
+    assert!(verbose_logs.iter().any(|log| log == debug_log));
+    assert!(silent_logs
+        .iter()
+        .any(|log| log == "Program log: User can mint"));
+
+    // Every debug log costs at least the log syscall base cost
+    let debug_logs = (verbose_logs.len() - silent_logs.len()) as u64;
+    assert!(verbose_units >= silent_units + debug_logs * 100);
+    println!(
+        "CanMint compute units: silent {}, verbose {}, {} debug logs",
+        silent_units, verbose_units, debug_logs
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_sets_mint_return_data() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    let data = simulate_return_data(&mut ctx, instruction, &[&mint, &user, &airdrop.authority])
+        .await
+        .unwrap();
+
+    let return_data =
+        premint_airdrop::return_data::MintReturnData::unpack_from_slice(&data).unwrap();
+    assert_eq!(return_data.index, 0);
+    assert_eq!(return_data.mint, mint.pubkey());
+    assert_eq!(return_data.price_paid, 100_000_000);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_to_escrow_sets_mint_return_data_after_escrow_is_created() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_to_escrow_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        &payer,
+        &Pubkey::new_unique(),
+        0,
+    );
+    let data = simulate_return_data(&mut ctx, instruction, &[&mint, &user, &airdrop.authority])
+        .await
+        .unwrap();
+
+    // Escrow is created by CPI after the mint, which must not clear return data
+    let return_data =
+        premint_airdrop::return_data::MintReturnData::unpack_from_slice(&data).unwrap();
+    assert_eq!(return_data.index, 0);
+    assert_eq!(return_data.mint, mint.pubkey());
+    assert_eq!(return_data.price_paid, 100_000_000);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_to_escrow_with_prefunded_escrow() {
+    let args = AirdropArgs::default();
+    let authority = Keypair::new();
+    let (config, _) = premint_airdrop::pda::find_airdrop_config(&authority.pubkey(), &args.name);
+    let (escrow, _) = premint_airdrop::pda::find_escrow(&config, 0);
+
+    // Stray lamport sent to escrow address of next minted index
+    let mut program_test = program_test();
+    program_test.add_account(escrow, system_account(1));
+    let mut ctx = program_test.start_with_context().await;
+    let airdrop = create_pda_airdrop_with_authority(&mut ctx, authority, args).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+    let claimant = Pubkey::new_unique();
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_to_escrow_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        &payer,
+        &claimant,
+        0,
+    );
+    process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let account = ctx.banks_client.get_account(escrow).await.unwrap().unwrap();
+    assert_eq!(account.owner, premint_airdrop::id());
+    let state = premint_airdrop::state::AirdropEscrow::unpack(&account.data).unwrap();
+    assert_eq!(state.claimant, claimant);
+    assert_eq!(state.mint, mint.pubkey());
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_to_escrow_then_claim() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+    let claimant = Keypair::new();
+    fund(&mut ctx, &claimant.pubkey(), 1_000_000_000).await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_to_escrow_instruction(
+        &airdrop,
+        &mint.pubkey(),
+        &user.pubkey(),
+        &payer,
+        &claimant.pubkey(),
+        0,
+    );
+    process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let (escrow, _) = premint_airdrop::pda::find_escrow(&airdrop.config, 0);
+    let escrow = TestEscrow {
+        escrow,
+        mint: mint.pubkey(),
+        payer,
+        purchase_index: 0,
+    };
+    let account = ctx
+        .banks_client
+        .get_account(escrow.escrow)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = premint_airdrop::state::AirdropEscrow::unpack(&account.data).unwrap();
+    assert_eq!(state.claimant, claimant.pubkey());
+    assert_eq!(state.mint, mint.pubkey());
+
+    process(
+        &mut ctx,
+        &[claim_from_escrow_instruction(
+            &airdrop,
+            &escrow,
+            &claimant.pubkey(),
+        )],
+        &[&claimant],
+    )
+    .await
+    .unwrap();
+
+    let claimant_token_account = spl_associated_token_account::get_associated_token_address(
+        &claimant.pubkey(),
+        &mint.pubkey(),
+    );
+    let account = ctx
+        .banks_client
+        .get_account(claimant_token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount,
+        1
+    );
+    assert!(ctx
+        .banks_client
+        .get_account(escrow.escrow)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn claim_from_escrow_transfers_token_and_returns_rent() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let claimant = Keypair::new();
+    program_test.add_account(claimant.pubkey(), system_account(1_000_000_000));
+    let escrow = add_escrow(&mut program_test, &airdrop, &claimant.pubkey(), 0, 0);
+    let claimant_token_account = spl_associated_token_account::get_associated_token_address(
+        &claimant.pubkey(),
+        &escrow.mint,
+    );
+    program_test.add_account(
+        claimant_token_account,
+        token_account(&escrow.mint, &claimant.pubkey(), 0),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let escrow_rent = ctx.banks_client.get_balance(escrow.escrow).await.unwrap()
+        + ctx
+            .banks_client
+            .get_balance(escrow.token_account())
+            .await
+            .unwrap();
+    let payer_before = ctx.banks_client.get_balance(escrow.payer).await.unwrap();
+
+    process(
+        &mut ctx,
+        &[claim_from_escrow_instruction(
+            &airdrop,
+            &escrow,
+            &claimant.pubkey(),
+        )],
+        &[&claimant],
+    )
+    .await
+    .unwrap();
+
+    let account = ctx
+        .banks_client
+        .get_account(claimant_token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount,
+        1
+    );
+
+    for closed in [escrow.escrow, escrow.token_account()] {
+        assert!(ctx
+            .banks_client
+            .get_account(closed)
+            .await
+            .unwrap()
+            .is_none());
+    }
+    let payer_after = ctx.banks_client.get_balance(escrow.payer).await.unwrap();
+    assert_eq!(payer_after, payer_before + escrow_rent);
+}
+
+#[tokio::test]
+async fn claim_from_escrow_rejects_other_signer() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let escrow = add_escrow(&mut program_test, &airdrop, &Pubkey::new_unique(), 0, 0);
+    let thief = Keypair::new();
+    program_test.add_account(thief.pubkey(), system_account(1_000_000_000));
+    let mut ctx = program_test.start_with_context().await;
+
+    let error = process(
+        &mut ctx,
+        &[claim_from_escrow_instruction(
+            &airdrop,
+            &escrow,
+            &thief.pubkey(),
+        )],
+        &[&thief],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::NotEscrowClaimant as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn sweep_escrow_moves_token_after_timeout() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            escrow_timeout_secs: 86_400,
+            ..AirdropArgs::default()
+        },
+    );
+    // Escrow created at the start of unix time is long past timeout
+    let escrow = add_escrow(&mut program_test, &airdrop, &Pubkey::new_unique(), 3, 0);
+    let destination = Pubkey::new_unique();
+    program_test.add_account(
+        destination,
+        token_account(&escrow.mint, &airdrop.authority.pubkey(), 0),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    process(
+        &mut ctx,
+        &[sweep_escrow_instruction(&airdrop, &escrow, &destination)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let account = ctx
+        .banks_client
+        .get_account(destination)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount,
+        1
+    );
+    assert!(ctx
+        .banks_client
+        .get_account(escrow.escrow)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn sweep_escrow_rejects_before_timeout() {
+    for (escrow_timeout_secs, created_ts) in [(86_400, i64::MAX / 2), (0, 0)] {
+        let mut program_test = program_test();
+        let airdrop = add_airdrop(
+            &mut program_test,
+            AirdropArgs {
+                escrow_timeout_secs,
+                ..AirdropArgs::default()
+            },
+        );
+        let escrow = add_escrow(
+            &mut program_test,
+            &airdrop,
+            &Pubkey::new_unique(),
+            0,
+            created_ts,
+        );
+        let destination = Pubkey::new_unique();
+        program_test.add_account(
+            destination,
+            token_account(&escrow.mint, &airdrop.authority.pubkey(), 0),
+        );
+        let mut ctx = program_test.start_with_context().await;
+
+        let error = process(
+            &mut ctx,
+            &[sweep_escrow_instruction(&airdrop, &escrow, &destination)],
+            &[&airdrop.authority],
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            airdrop_error(error),
+            Some(AirdropError::EscrowNotExpired as u32)
+        );
+    }
+}
+
+#[tokio::test]
+async fn deposit_preminted_rejects_non_nft_mint() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mint = Pubkey::new_unique();
+    program_test.add_account(mint, mint_account(5, 0));
+    let mut ctx = program_test.start_with_context().await;
+
+    let payer = ctx.payer.pubkey();
+    let error = process(
+        &mut ctx,
+        &[deposit_preminted_instruction(&airdrop, &mint, &payer)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::InvalidMint as u32));
+}
+
+#[tokio::test]
+async fn deposit_preminted_rejects_other_vault() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mint = Pubkey::new_unique();
+    program_test.add_account(mint, mint_account(1, 0));
+    let mut ctx = program_test.start_with_context().await;
+
+    let payer = ctx.payer.pubkey();
+    let mut instruction = deposit_preminted_instruction(&airdrop, &mint, &payer);
+    instruction.accounts[2] = AccountMeta::new(Pubkey::new_unique(), false);
+
+    let error = process(&mut ctx, &[instruction], &[&airdrop.authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidVaultPda as u32)
+    );
+}
+
+#[tokio::test]
+async fn claim_preminted_rejects_drained_vault() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mint = Pubkey::new_unique();
+    add_vault(&mut program_test, &airdrop, &[mint], 1);
+    let mut ctx = program_test.start_with_context().await;
+
+    let payer = ctx.payer.pubkey();
+    let error = process(
+        &mut ctx,
+        &[claim_preminted_instruction(
+            &airdrop,
+            &mint,
+            &user.pubkey(),
+            &payer,
+        )],
+        &[&user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::VaultEmpty as u32));
+}
+
+#[tokio::test]
+async fn claim_preminted_rejects_out_of_order_mint() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+    add_vault(&mut program_test, &airdrop, &mints, 0);
+    let mut ctx = program_test.start_with_context().await;
+
+    let payer = ctx.payer.pubkey();
+    let error = process(
+        &mut ctx,
+        &[claim_preminted_instruction(
+            &airdrop,
+            &mints[1],
+            &user.pubkey(),
+            &payer,
+        )],
+        &[&user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn deposit_then_claim_preminted() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mint = Pubkey::new_unique();
+    program_test.add_account(mint, mint_account(1, 0));
+    program_test.add_account(
+        spl_associated_token_account::get_associated_token_address(
+            &airdrop.authority.pubkey(),
+            &mint,
+        ),
+        token_account(&mint, &airdrop.authority.pubkey(), 1),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let payer = ctx.payer.pubkey();
+    process(
+        &mut ctx,
+        &[deposit_preminted_instruction(&airdrop, &mint, &payer)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let (vault, _) = premint_airdrop::pda::find_vault(&airdrop.config);
+    let account = ctx.banks_client.get_account(vault).await.unwrap().unwrap();
+    let vault_data =
+        premint_airdrop::state::PremintVault::unpack_from_slice(&account.data).unwrap();
+    assert_eq!((vault_data.cursor, vault_data.len), (0, 1));
+
+    process(
+        &mut ctx,
+        &[claim_preminted_instruction(
+            &airdrop,
+            &mint,
+            &user.pubkey(),
+            &payer,
+        )],
+        &[&user, &airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let account = ctx
+        .banks_client
+        .get_account(spl_associated_token_account::get_associated_token_address(
+            &user.pubkey(),
+            &mint,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount,
+        1
+    );
+    assert!(ctx
+        .banks_client
+        .get_account(spl_associated_token_account::get_associated_token_address(
+            &vault, &mint
+        ))
+        .await
+        .unwrap()
+        .is_none());
+
+    let account = ctx.banks_client.get_account(vault).await.unwrap().unwrap();
+    let vault_data =
+        premint_airdrop::state::PremintVault::unpack_from_slice(&account.data).unwrap();
+    assert_eq!(vault_data.cursor, 1);
+    assert_eq!(get_config(&mut ctx, &airdrop.config).await.airdrop_index, 1);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn claim_preminted_returns_vault_token_account_rent_to_authority() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let claim_payer = Keypair::new();
+    program_test.add_account(claim_payer.pubkey(), system_account(1_000_000_000));
+    let mint = Pubkey::new_unique();
+    program_test.add_account(mint, mint_account(1, 0));
+    program_test.add_account(
+        spl_associated_token_account::get_associated_token_address(
+            &airdrop.authority.pubkey(),
+            &mint,
+        ),
+        token_account(&mint, &airdrop.authority.pubkey(), 1),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let payer = ctx.payer.pubkey();
+    process(
+        &mut ctx,
+        &[deposit_preminted_instruction(&airdrop, &mint, &payer)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let (vault, _) = premint_airdrop::pda::find_vault(&airdrop.config);
+    let vault_token_account =
+        spl_associated_token_account::get_associated_token_address(&vault, &mint);
+    let vault_token_account_rent = ctx
+        .banks_client
+        .get_balance(vault_token_account)
+        .await
+        .unwrap();
+    let authority_before = ctx
+        .banks_client
+        .get_balance(airdrop.authority.pubkey())
+        .await
+        .unwrap();
+
+    process(
+        &mut ctx,
+        &[claim_preminted_instruction(
+            &airdrop,
+            &mint,
+            &user.pubkey(),
+            &claim_payer.pubkey(),
+        )],
+        &[&user, &airdrop.authority, &claim_payer],
+    )
+    .await
+    .unwrap();
+
+    // Claim payer only pays for user token account
+    let user_token_account_rent = Rent::default().minimum_balance(spl_token::state::Account::LEN);
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(airdrop.authority.pubkey())
+            .await
+            .unwrap(),
+        authority_before + vault_token_account_rent
+    );
+    assert_eq!(
+        ctx.banks_client
+            .get_balance(claim_payer.pubkey())
+            .await
+            .unwrap(),
+        1_000_000_000 - user_token_account_rent
+    );
+}
+
+#[tokio::test]
+async fn withdraw_unclaimed_rejects_open_window() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mint = Pubkey::new_unique();
+    add_vault(&mut program_test, &airdrop, &[mint], 0);
+    let mut ctx = program_test.start_with_context().await;
+
+    let error = process(
+        &mut ctx,
+        &[withdraw_unclaimed_instruction(
+            &airdrop,
+            &mint,
+            &Pubkey::new_unique(),
+        )],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::AirdropNotEnded as u32)
+    );
+}
+
+#[tokio::test]
+async fn withdraw_unclaimed_rejects_claimed_mint() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+    add_vault(&mut program_test, &airdrop, &mints, 1);
+    let mut ctx = program_test.start_with_context().await;
+
+    let error = process(
+        &mut ctx,
+        &[
+            update_window_instruction(&airdrop, 0, 1),
+            withdraw_unclaimed_instruction(&airdrop, &mints[0], &Pubkey::new_unique()),
+        ],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::MintNotQueued as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn withdraw_unclaimed_swap_removes_mint_and_shrinks_vault() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mints = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let vault = add_vault(&mut program_test, &airdrop, &mints, 0);
+    for mint in mints.iter() {
+        program_test.add_account(*mint, mint_account(1, 0));
+        program_test.add_account(
+            spl_associated_token_account::get_associated_token_address(&vault, mint),
+            token_account(mint, &vault, 1),
+        );
+    }
+    let destination = Pubkey::new_unique();
+    program_test.add_account(
+        destination,
+        token_account(&mints[0], &airdrop.authority.pubkey(), 0),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    process(
+        &mut ctx,
+        &[
+            update_window_instruction(&airdrop, 0, 1),
+            withdraw_unclaimed_instruction(&airdrop, &mints[0], &destination),
+        ],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let account = ctx
+        .banks_client
+        .get_account(destination)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount,
+        1
+    );
+    assert!(ctx
+        .banks_client
+        .get_account(spl_associated_token_account::get_associated_token_address(
+            &vault, &mints[0]
+        ))
+        .await
+        .unwrap()
+        .is_none());
+
+    let account = ctx.banks_client.get_account(vault).await.unwrap().unwrap();
+    assert_eq!(
+        account.data.len(),
+        premint_airdrop::state::PremintVault::space(2)
+    );
+    let vault_data =
+        premint_airdrop::state::PremintVault::unpack_from_slice(&account.data).unwrap();
+    assert_eq!(vault_data.len, 2);
+    assert_eq!(
+        &account.data[premint_airdrop::state::PremintVault::LEN..],
+        [mints[2].to_bytes(), mints[1].to_bytes()]
+            .concat()
+            .as_slice()
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_transaction_above_max_mints_per_tx() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            max_mints_per_tx: 2,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mints = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let payer = ctx.payer.pubkey();
+    let instructions: Vec<Instruction> = mints
+        .iter()
+        .map(|mint| mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer))
+        .collect();
+
+    let error = process(
+        &mut ctx,
+        &instructions,
+        &[&mints[0], &mints[1], &mints[2], &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::TooManyMintsInTransaction as u32)
+    );
+    assert_eq!(get_config(&mut ctx, &airdrop.config).await.airdrop_index, 0);
+}
+
+#[tokio::test]
+async fn mint_one_clamps_transaction_to_fair_share_near_end_of_supply() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            airdrop_amount: 100,
+            max_mints_per_tx: 10,
+            fairness_bps: 5_000,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    // 4 tokens left, so half of remaining supply allows 2 mints instead of 10
+    let mut account = ctx
+        .banks_client
+        .get_account(airdrop.config)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut config = AirdropConfig::unpack_unchecked(&account.data).unwrap();
+    config.airdrop_index = 96;
+    config.pack_into_slice(&mut account.data);
+    ctx.set_account(&airdrop.config, &account.into());
+
+    let mints = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let payer = ctx.payer.pubkey();
+    let instructions: Vec<Instruction> = mints
+        .iter()
+        .map(|mint| mint_one_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), true, &payer))
+        .collect();
+
+    let error = process(
+        &mut ctx,
+        &instructions,
+        &[&mints[0], &mints[1], &mints[2], &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::TooManyMintsInTransaction as u32)
+    );
+}
+
+/// Stores drawn raffle in config as if DrawRaffle was run with given seed
+async fn set_raffle(
+    ctx: &mut ProgramTestContext,
+    airdrop: &TestAirdrop,
+    raffle_seed: [u8; 32],
+    registered_count: u64,
+    winners_count: u64,
+) {
+    let mut account = ctx
+        .banks_client
+        .get_account(airdrop.config)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut config = AirdropConfig::unpack_unchecked(&account.data).unwrap();
+    config.raffle_seed = raffle_seed;
+    config.registered_count = registered_count;
+    config.winners_count = winners_count;
+    config.pack_into_slice(&mut account.data);
+    ctx.set_account(&airdrop.config, &account.into());
+}
+
+/// Seed for which user registered first wins a raffle of 2 entrants with 1 winner, or loses
+fn raffle_seed(first_wins: bool) -> [u8; 32] {
+    (0..=u8::MAX)
+        .map(|byte| [byte; 32])
+        .find(|seed| is_raffle_winner(seed, 0, 2, 1) == first_wins)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn draw_raffle_stores_seed_and_closes_registration() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            raffle: true,
+            ..AirdropArgs::default()
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+    ctx.warp_to_slot(10).unwrap();
+
+    process(
+        &mut ctx,
+        &[draw_raffle_instruction(&airdrop, 3)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.winners_count, 3);
+    assert_ne!(config.raffle_seed, [0; 32]);
+
+    let error = register_user(&mut ctx, &airdrop, &Keypair::new().pubkey())
+        .await
+        .unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::RaffleDrawn as u32));
+
+    ctx.warp_to_slot(20).unwrap();
+    let error = process(
+        &mut ctx,
+        &[draw_raffle_instruction(&airdrop, 3)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::RaffleDrawn as u32));
+}
+
+#[tokio::test]
+async fn draw_raffle_rejects_airdrop_without_raffle() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+    ctx.warp_to_slot(10).unwrap();
+
+    let error = process(
+        &mut ctx,
+        &[draw_raffle_instruction(&airdrop, 3)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::RaffleDisabled as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_raffle_entrant_before_draw() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            raffle: true,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let error = mint_one(&mut ctx, &airdrop, &user).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::RaffleNotDrawn as u32)
+    );
+}
+
+#[tokio::test]
+async fn mint_one_rejects_raffle_loser() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            raffle: true,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    set_raffle(&mut ctx, &airdrop, raffle_seed(false), 2, 1).await;
+
+    let error = mint_one(&mut ctx, &airdrop, &user).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::NotRaffleWinner as u32)
+    );
+}
+
+#[tokio::test]
+async fn close_user_data_refunds_raffle_loser() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            raffle: true,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    set_raffle(&mut ctx, &airdrop, raffle_seed(false), 2, 1).await;
+
+    let user_data = airdrop.user_data(&user.pubkey());
+    let rent = ctx.banks_client.get_balance(user_data).await.unwrap();
+    let user_before = ctx.banks_client.get_balance(user.pubkey()).await.unwrap();
+
+    process(
+        &mut ctx,
+        &[close_user_data_instruction(&airdrop, &user.pubkey())],
+        &[&user],
+    )
+    .await
+    .unwrap();
+
+    assert!(ctx
+        .banks_client
+        .get_account(user_data)
+        .await
+        .unwrap()
+        .is_none());
+    let user_after = ctx.banks_client.get_balance(user.pubkey()).await.unwrap();
+    assert_eq!(user_after, user_before + rent);
+}
+
+#[tokio::test]
+async fn close_user_data_rejects_raffle_winner() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            raffle: true,
+            ..AirdropArgs::default()
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    set_raffle(&mut ctx, &airdrop, raffle_seed(true), 2, 1).await;
+
+    let error = process(
+        &mut ctx,
+        &[close_user_data_instruction(&airdrop, &user.pubkey())],
+        &[&user],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::RaffleWinner as u32)
+    );
+}
+
+#[tokio::test]
+async fn closed_window_can_resume_but_deactivated_airdrop_can_not() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    // Window that ended at the start of unix time pauses minting
+    process(
+        &mut ctx,
+        &[update_window_instruction(&airdrop, 0, 1)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+    let error = mint_one(&mut ctx, &airdrop, &user).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::MintWindowClosed as u32)
+    );
+
+    process(
+        &mut ctx,
+        &[
+            update_window_instruction(&airdrop, 0, 0),
+            can_mint_instruction(&airdrop, &user.pubkey()),
+            deactivate_instruction(&airdrop),
+        ],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert!(!config.is_active);
+
+    let error = mint_one(&mut ctx, &airdrop, &user).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::AirdropEnded as u32)
+    );
+
+    for instruction in [
+        update_window_instruction(&airdrop, 0, 0),
+        deactivate_instruction(&airdrop),
+    ] {
+        let error = process(&mut ctx, &[instruction], &[&airdrop.authority])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            airdrop_error(error),
+            Some(AirdropError::AirdropEnded as u32)
+        );
+    }
+}
+
+#[tokio::test]
+async fn deactivate_rejects_wrong_authority() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let impostor = Keypair::new();
+    let mut instruction = deactivate_instruction(&airdrop);
+    instruction.accounts[1].pubkey = impostor.pubkey();
+
+    let error = process(&mut ctx, &[instruction], &[&impostor])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+}
+
+/// MintOne of airdrop priced in USD, with Pyth price account appended
+fn usd_priced_mint_one_instruction(
+    ctx: &ProgramTestContext,
+    airdrop: &TestAirdrop,
+    mint: &Keypair,
+    user: &Keypair,
+    price_feed: &Pubkey,
+) -> Instruction {
+    let payer = ctx.payer.pubkey();
+    let mut instruction =
+        mint_one_instruction(airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*price_feed, false));
+    instruction
+}
+
+#[tokio::test]
+async fn set_usd_price_stores_price_feed() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+    let price_feed = Pubkey::new_unique();
+
+    process(
+        &mut ctx,
+        &[set_usd_price_instruction(&airdrop, 5_000, &price_feed, 25)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.price_usd_cents, 5_000);
+    assert_eq!(config.pyth_price_account, price_feed);
+    assert_eq!(config.max_price_age_slots, 25);
+
+    let error = process(
+        &mut ctx,
+        &[set_usd_price_instruction(
+            &airdrop,
+            5_000,
+            &Pubkey::default(),
+            25,
+        )],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::BadInstructionArgument as u32)
+    );
+}
+
+/// Runs USD priced MintOne at slot 100 against given Pyth price account
+async fn mint_one_with_price_account(
+    price_account: Account,
+    passed_feed: Option<Pubkey>,
+) -> BanksClientError {
+    let mut program_test = program_test();
+    let price_feed = Pubkey::new_unique();
+    program_test.add_account(price_feed, price_account);
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    ctx.warp_to_slot(100).unwrap();
+
+    process(
+        &mut ctx,
+        &[set_usd_price_instruction(&airdrop, 5_000, &price_feed, 25)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let mint = Keypair::new();
+    let instruction = usd_priced_mint_one_instruction(
+        &ctx,
+        &airdrop,
+        &mint,
+        &user,
+        &passed_feed.unwrap_or(price_feed),
+    );
+
+    process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err()
+}
+
+#[tokio::test]
+async fn mint_one_rejects_stale_usd_price() {
+    let error =
+        mint_one_with_price_account(pyth_price_account(2_500_000_000, -8, 1, 50), None).await;
+    assert_eq!(airdrop_error(error), Some(AirdropError::StaleOracle as u32));
+}
+
+#[tokio::test]
+async fn mint_one_rejects_negative_usd_price() {
+    let error = mint_one_with_price_account(pyth_price_account(-1, -8, 1, 100), None).await;
+    assert_eq!(airdrop_error(error), Some(AirdropError::StaleOracle as u32));
+}
+
+#[tokio::test]
+async fn mint_one_rejects_halted_usd_price() {
+    let error =
+        mint_one_with_price_account(pyth_price_account(2_500_000_000, -8, 2, 100), None).await;
+    assert_eq!(airdrop_error(error), Some(AirdropError::StaleOracle as u32));
+}
+
+#[tokio::test]
+async fn mint_one_rejects_wrong_price_account() {
+    let error = mint_one_with_price_account(
+        pyth_price_account(2_500_000_000, -8, 1, 100),
+        Some(Pubkey::new_unique()),
+    )
+    .await;
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_charges_usd_price() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    // $50 at $25 per SOL
+    let slot = ctx.banks_client.get_root_slot().await.unwrap();
+    let price_feed = Pubkey::new_unique();
+    ctx.set_account(
+        &price_feed,
+        &pyth_price_account(2_500_000_000, -8, 1, slot).into(),
+    );
+    process(
+        &mut ctx,
+        &[set_usd_price_instruction(&airdrop, 5_000, &price_feed, 25)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let before = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+    let mint = Keypair::new();
+    let instruction = usd_priced_mint_one_instruction(&ctx, &airdrop, &mint, &user, &price_feed);
+    process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let after = ctx
+        .banks_client
+        .get_balance(airdrop.revenue_wallet)
+        .await
+        .unwrap();
+    assert_eq!(after - before, 2_000_000_000);
+}
+
+/// Rewrites airdrop config stored in test validator
+async fn update_config(
+    ctx: &mut ProgramTestContext,
+    airdrop: &TestAirdrop,
+    update: impl FnOnce(&mut AirdropConfig),
+) {
+    let mut account = ctx
+        .banks_client
+        .get_account(airdrop.config)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut config = AirdropConfig::unpack_unchecked(&account.data).unwrap();
+    update(&mut config);
+    config.pack_into_slice(&mut account.data);
+    ctx.set_account(&airdrop.config, &account.into());
+}
+
+async fn increase_supply(
+    ctx: &mut ProgramTestContext,
+    airdrop: &TestAirdrop,
+    additional: u64,
+) -> Result<(), BanksClientError> {
+    let instruction = increase_supply_instruction(airdrop, &airdrop.authority.pubkey(), additional);
+    process(ctx, &[instruction], &[&airdrop.authority]).await
+}
+
+#[tokio::test]
+async fn increase_supply_adds_to_airdrop_amount() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    increase_supply(&mut ctx, &airdrop, 5).await.unwrap();
+
+    assert_eq!(
+        get_config(&mut ctx, &airdrop.config).await.airdrop_amount,
+        15
+    );
+}
+
+#[tokio::test]
+async fn increase_supply_rejects_overflow() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+    update_config(&mut ctx, &airdrop, |config| {
+        config.airdrop_amount = u64::MAX
+    })
+    .await;
+
+    let error = increase_supply(&mut ctx, &airdrop, 1).await.unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::Overflow as u32));
+}
+
+#[tokio::test]
+async fn increase_supply_rejects_supply_above_ceiling() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let error = increase_supply(&mut ctx, &airdrop, MAX_AIRDROP_AMOUNT - 10 + 1)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidSupply as u32)
+    );
+
+    // Exactly at the ceiling is allowed
+    increase_supply(&mut ctx, &airdrop, MAX_AIRDROP_AMOUNT - 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        get_config(&mut ctx, &airdrop.config).await.airdrop_amount,
+        MAX_AIRDROP_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn increase_supply_rejects_finalized_airdrop() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+    update_config(&mut ctx, &airdrop, |config| config.finalized = true).await;
+
+    let error = increase_supply(&mut ctx, &airdrop, 1).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::AirdropFinalized as u32)
+    );
+}
+
+#[tokio::test]
+async fn increase_supply_rejects_non_authority_signer() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let impostor = Keypair::new();
+    let instruction = increase_supply_instruction(&airdrop, &impostor.pubkey(), 1);
+    let error = process(&mut ctx, &[instruction], &[&impostor])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+    assert_eq!(
+        get_config(&mut ctx, &airdrop.config).await.airdrop_amount,
+        10
+    );
+}
+
+#[tokio::test]
+async fn finalize_rejects_open_airdrop() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let error = process(
+        &mut ctx,
+        &[finalize_instruction(&airdrop, false)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::AirdropNotEnded as u32)
+    );
+}
+
+#[tokio::test]
+async fn finalize_records_statistics_and_freezes_airdrop() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    update_config(&mut ctx, &airdrop, |config| {
+        config.airdrop_index = 3;
+        config.gifted_count = 1;
+        config.registered_count = 4;
+        config.total_revenue = 3_000_000_000;
+    })
+    .await;
+
+    // Window that ended at the start of unix time
+    process(
+        &mut ctx,
+        &[
+            update_window_instruction(&airdrop, 0, 1),
+            finalize_instruction(&airdrop, false),
+        ],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert!(config.finalized);
+    assert_eq!(config.final_minted, 3);
+    assert_eq!(config.final_gifted, 1);
+    assert_eq!(config.final_registered, 4);
+    assert_eq!(config.final_revenue, 3_000_000_000);
+    assert_eq!(config.airdrop_authority, airdrop.authority.pubkey());
+
+    let error = mint_one(&mut ctx, &airdrop, &user).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::AirdropFinalized as u32)
+    );
+
+    for instruction in [
+        update_window_instruction(&airdrop, 0, 0),
+        set_usd_price_instruction(&airdrop, 5_000, &Pubkey::new_unique(), 25),
+        deactivate_instruction(&airdrop),
+        finalize_instruction(&airdrop, true),
+    ] {
+        let error = process(&mut ctx, &[instruction], &[&airdrop.authority])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            airdrop_error(error),
+            Some(AirdropError::AirdropFinalized as u32)
+        );
+    }
+}
+
+#[tokio::test]
+async fn finalize_with_lock_drops_authority_of_sold_out_airdrop() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+    update_config(&mut ctx, &airdrop, |config| {
+        config.airdrop_index = config.airdrop_amount;
+    })
+    .await;
+
+    process(
+        &mut ctx,
+        &[finalize_instruction(&airdrop, true)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert!(config.finalized);
+    assert_eq!(config.final_minted, config.airdrop_amount);
+    assert_eq!(config.airdrop_authority, Pubkey::default());
+
+    let error = process(
+        &mut ctx,
+        &[update_window_instruction(&airdrop, 0, 0)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::AirdropFinalized as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn mint_one_counts_revenue_for_finalize() {
+    let mut ctx = start().await;
+    let args = AirdropArgs::default();
+    let price = args.price;
+    let airdrop = create_pda_airdrop(&mut ctx, args).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    mint_one(&mut ctx, &airdrop, &user).await.unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.total_revenue, price);
+    assert_eq!(config.gifted_count, 0);
+}
+
+#[tokio::test]
+async fn blocked_user_can_not_mint_until_unblocked() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    let payer = ctx.payer.pubkey();
+
+    process(
+        &mut ctx,
+        &[set_user_blocked_instruction(
+            &airdrop,
+            &user.pubkey(),
+            &payer,
+            true,
+        )],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let user_data = get_user_data(&mut ctx, &airdrop.user_data(&user.pubkey())).await;
+    assert!(user_data.blocked);
+
+    let error = mint_one(&mut ctx, &airdrop, &user).await.unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::UserBlocked as u32));
+
+    process(
+        &mut ctx,
+        &[
+            set_user_blocked_instruction(&airdrop, &user.pubkey(), &payer, false),
+            can_mint_instruction(&airdrop, &user.pubkey()),
+        ],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn set_user_blocked_rejects_impersonated_authority() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    let payer = ctx.payer.pubkey();
+
+    // User tries to unblock itself by signing as authority
+    let mut instruction = set_user_blocked_instruction(&airdrop, &user.pubkey(), &payer, false);
+    instruction.accounts[1].pubkey = user.pubkey();
+
+    let error = process(&mut ctx, &[instruction], &[&user])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::WrongAccountAddress as u32)
+    );
+
+    // Authority key without its signature
+    let mut instruction = set_user_blocked_instruction(&airdrop, &user.pubkey(), &payer, true);
+    instruction.accounts[1].is_signer = false;
+
+    let error = process(&mut ctx, &[instruction], &[]).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::SignerRequired as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn set_user_blocked_creates_user_data_of_unregistered_wallet() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let wallet = Pubkey::new_unique();
+    let payer = ctx.payer.pubkey();
+
+    process(
+        &mut ctx,
+        &[set_user_blocked_instruction(
+            &airdrop, &wallet, &payer, true,
+        )],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let user_data = get_user_data(&mut ctx, &airdrop.user_data(&wallet)).await;
+    assert!(user_data.blocked);
+    assert_eq!(user_data.user, wallet);
+}
+
+#[tokio::test]
+async fn deregister_refunds_user_data_rent() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let user_data = airdrop.user_data(&user.pubkey());
+    let rent = ctx.banks_client.get_balance(user_data).await.unwrap();
+    let user_before = ctx.banks_client.get_balance(user.pubkey()).await.unwrap();
+
+    process(
+        &mut ctx,
+        &[deregister_instruction(&airdrop, &user.pubkey())],
+        &[&user],
+    )
+    .await
+    .unwrap();
+
+    assert!(ctx
+        .banks_client
+        .get_account(user_data)
+        .await
+        .unwrap()
+        .is_none());
+    let user_after = ctx.banks_client.get_balance(user.pubkey()).await.unwrap();
+    assert_eq!(user_after, user_before + rent);
+}
+
+#[tokio::test]
+async fn deregister_rejects_user_that_minted() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    update_user_data(&mut ctx, &airdrop, &user.pubkey(), |data| {
+        data.mints_amount = 1
+    })
+    .await;
+
+    let error = process(
+        &mut ctx,
+        &[deregister_instruction(&airdrop, &user.pubkey())],
+        &[&user],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::UserHasMinted as u32)
+    );
+}
+
+#[tokio::test]
+async fn deregister_rejects_blocked_user() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    update_user_data(&mut ctx, &airdrop, &user.pubkey(), |data| {
+        data.blocked = true
+    })
+    .await;
+
+    let error = process(
+        &mut ctx,
+        &[deregister_instruction(&airdrop, &user.pubkey())],
+        &[&user],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(airdrop_error(error), Some(AirdropError::UserBlocked as u32));
+}
+
+#[tokio::test]
+async fn deregister_rejects_user_data_of_other_wallet() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let other = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mut instruction = deregister_instruction(&airdrop, &user.pubkey());
+    instruction.accounts[2].pubkey = airdrop.user_data(&other.pubkey());
+
+    let error = process(&mut ctx, &[instruction], &[&user])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn deregistered_user_can_register_again() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(&mut ctx, AirdropArgs::default()).await;
+    let user = create_user(&mut ctx, &airdrop).await;
+
+    process(
+        &mut ctx,
+        &[deregister_instruction(&airdrop, &user.pubkey())],
+        &[&user],
+    )
+    .await
+    .unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.airdrop_users, 0);
+
+    register_user(&mut ctx, &airdrop, &user.pubkey())
+        .await
+        .unwrap();
+
+    let user_data = get_user_data(&mut ctx, &airdrop.user_data(&user.pubkey())).await;
+    assert!(user_data.initialized);
+    assert_eq!(user_data.mints_amount, 0);
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.airdrop_users, 1);
+}
+
+async fn update_user_data(
+    ctx: &mut ProgramTestContext,
+    airdrop: &TestAirdrop,
+    user: &Pubkey,
+    update: impl FnOnce(&mut AirdropUserData),
+) {
+    let user_data = airdrop.user_data(user);
+    let mut account = ctx
+        .banks_client
+        .get_account(user_data)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut data = AirdropUserData::unpack_unchecked(&account.data).unwrap();
+    update(&mut data);
+    data.pack_into_slice(&mut account.data);
+    ctx.set_account(&user_data, &account.into());
+}
+
+/// Open mint airdrop takes program id instead of user data account
+fn open_mint_instruction(
+    airdrop: &TestAirdrop,
+    mint: &Pubkey,
+    user: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut instruction = mint_one_instruction(airdrop, mint, user, true, payer);
+    instruction.accounts[1] = AccountMeta::new_readonly(premint_airdrop::id(), false);
+    instruction
+}
+
+#[tokio::test]
+async fn can_mint_skips_user_data_of_open_mint_airdrop() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            require_registration: false,
+            ..AirdropArgs::default()
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let user = Pubkey::new_unique();
+    let mut instruction = can_mint_instruction(&airdrop, &user);
+    instruction.accounts[1].pubkey = premint_airdrop::id();
+
+    process(&mut ctx, &[instruction], &[]).await.unwrap();
+}
+
+async fn simulate_preview(
+    ctx: &mut ProgramTestContext,
+    instruction: Instruction,
+) -> Result<MintPreview, BanksClientError> {
+    let data = simulate_return_data(ctx, instruction, &[]).await?;
+    Ok(MintPreview::unpack_from_slice(&data).unwrap())
+}
+
+#[tokio::test]
+async fn preview_mint_counts_rent_of_every_created_account() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Pubkey::new_unique();
+    let instruction = preview_mint_instruction(&airdrop, &user.pubkey(), &mint);
+    let preview = simulate_preview(&mut ctx, instruction).await.unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    assert!(config.creates_master_edition());
+    assert_eq!(
+        preview.estimated_rent_lamports,
+        mint_rent_lamports(&rent, &config, true).unwrap()
+    );
+    assert_eq!(preview.effective_price, 100_000_000);
+    assert_eq!(preview.remaining_supply, 10);
+    assert_eq!(preview.remaining_allowance, 10);
+}
+
+#[tokio::test]
+async fn preview_mint_skips_rent_of_existing_token_account() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mint = Pubkey::new_unique();
+    program_test.add_account(
+        spl_associated_token_account::get_associated_token_address(&user.pubkey(), &mint),
+        token_account(&mint, &user.pubkey(), 0),
+    );
+    let mut ctx = program_test.start_with_context().await;
+
+    let instruction = preview_mint_instruction(&airdrop, &user.pubkey(), &mint);
+    let preview = simulate_preview(&mut ctx, instruction).await.unwrap();
+
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    assert_eq!(
+        preview.estimated_rent_lamports,
+        mint_rent_lamports(&rent, &config, false).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn preview_mint_rejects_foreign_token_account() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mut instruction = preview_mint_instruction(&airdrop, &user.pubkey(), &Pubkey::new_unique());
+    instruction.accounts[6].pubkey = Pubkey::new_unique();
+
+    let error = simulate_preview(&mut ctx, instruction).await.unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidTokenAccountPda as u32)
+    );
+}
+
+#[tokio::test]
+async fn preview_mint_uses_active_stage_price() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            stage_count: 1,
+            ..AirdropArgs::default()
+        },
+    );
+    let stage = add_stage(
+        &mut program_test,
+        AirdropStage {
+            price: 42_000_000,
+            ..open_stage(&airdrop, 0)
+        },
+    );
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mut instruction = preview_mint_instruction(&airdrop, &user.pubkey(), &Pubkey::new_unique());
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(stage, false));
+    let preview = simulate_preview(&mut ctx, instruction).await.unwrap();
+
+    assert_eq!(preview.effective_price, 42_000_000);
+}
+
+#[tokio::test]
+async fn preview_mint_uses_usd_price() {
+    let mut program_test = program_test();
+    let price_feed = Pubkey::new_unique();
+    program_test.add_account(price_feed, pyth_price_account(2_500_000_000, -8, 1, 100));
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    ctx.warp_to_slot(100).unwrap();
+
+    process(
+        &mut ctx,
+        &[set_usd_price_instruction(&airdrop, 5_000, &price_feed, 25)],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let mut instruction = preview_mint_instruction(&airdrop, &user.pubkey(), &Pubkey::new_unique());
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(price_feed, false));
+    let preview = simulate_preview(&mut ctx, instruction).await.unwrap();
+
+    // 50 USD at 25 USD per SOL
+    assert_eq!(preview.effective_price, 2_000_000_000);
+}
+
+#[tokio::test]
+async fn mint_one_requires_user_data_when_registration_is_required() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = open_mint_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), &payer);
+
+    let error = process(
+        &mut ctx,
+        &[instruction],
+        &[&mint, &user, &airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidUserDataPda as u32)
+    );
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn open_mint_airdrop_mints_without_registration_or_user_limits() {
+    let mut ctx = start().await;
+    let airdrop = create_pda_airdrop(
+        &mut ctx,
+        AirdropArgs {
+            require_registration: false,
+            mint_cooldown_secs: 3_600,
+            ..AirdropArgs::default()
+        },
+    )
+    .await;
+    let user = Keypair::new();
+    fund(&mut ctx, &user.pubkey(), 10_000_000_000).await;
+    let payer = ctx.payer.pubkey();
+
+    // Cooldown of registered users does not apply, so user mints twice in a row
+    for _ in 0..2 {
+        let mint = Keypair::new();
+        let instruction = open_mint_instruction(&airdrop, &mint.pubkey(), &user.pubkey(), &payer);
+        process(
+            &mut ctx,
+            &[instruction],
+            &[&mint, &user, &airdrop.authority],
+        )
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(get_config(&mut ctx, &airdrop.config).await.airdrop_index, 2);
+    assert!(ctx
+        .banks_client
+        .get_account(airdrop.user_data(&user.pubkey()))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn can_mint_rejects_user_data_without_cached_bump_until_recomputed() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+    update_user_data(&mut ctx, &airdrop, &user.pubkey(), |data| data.bump = 0).await;
+
+    let error = process(
+        &mut ctx,
+        &[can_mint_instruction(&airdrop, &user.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidUserDataPda as u32)
+    );
+
+    process(
+        &mut ctx,
+        &[
+            recompute_bumps_instruction(&airdrop, None, &[user.pubkey()]),
+            can_mint_instruction(&airdrop, &user.pubkey()),
+        ],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+}
+
+/// Registered user whose user data has canonical bump in `bumps`
+#[cfg(feature = "test-bpf")]
+fn add_user_with_bump(
+    program_test: &mut ProgramTest,
+    airdrop: &TestAirdrop,
+    bumps: std::ops::RangeInclusive<u8>,
+) -> Keypair {
+    loop {
+        let user = Keypair::new();
+        if bumps.contains(&find_airdrop_user_data(&airdrop.config, &user.pubkey()).1) {
+            break add_registered_user(program_test, airdrop, user, 0);
+        }
+    }
+}
+
+/// Mint path checks user data with its cached bump, so it costs the same whatever the
+/// canonical bump is. `find_program_address` pays one `create_program_address` per bump
+/// tried below 255, and RecomputeBumps, which still derives, shows that cost
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn cached_user_data_bump_saves_derivation_compute() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let first_bump_user = add_user_with_bump(&mut program_test, &airdrop, 255..=255);
+    let low_bump_user = add_user_with_bump(&mut program_test, &airdrop, 0..=251);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mut can_mint_units = vec![];
+    let mut derive_units = vec![];
+    for user in [&first_bump_user, &low_bump_user] {
+        let (units, _) = simulate_compute(
+            &mut ctx,
+            can_mint_instruction(&airdrop, &user.pubkey()),
+            &[],
+        )
+        .await;
+        can_mint_units.push(units);
+
+        let instruction = recompute_bumps_instruction(&airdrop, None, &[user.pubkey()]);
+        let (units, _) = simulate_compute(&mut ctx, instruction, &[&airdrop.authority]).await;
+        derive_units.push(units);
+    }
+
+    let (_, low_bump) = find_airdrop_user_data(&airdrop.config, &low_bump_user.pubkey());
+    let extra_bumps = 255 - low_bump as u64;
+    let saved_units = derive_units[1] - derive_units[0];
+
+    assert_eq!(can_mint_units[0], can_mint_units[1]);
+    assert!(saved_units >= extra_bumps * 1500);
+    println!(
+        "CanMint: {} compute units with cached bump, derivation would add {} for {} extra bumps",
+        can_mint_units[1], saved_units, extra_bumps
+    );
+}
+
+/// Mint whose metadata, master edition and token account of `owner` have the first bump,
+/// so deriving them costs the same for every mint
+#[cfg(feature = "test-bpf")]
+fn mint_with_first_bumps(owner: &Pubkey) -> Keypair {
+    let metadata_program = metaplex_token_metadata::id();
+    let token_program = spl_token::id();
+    loop {
+        let mint = Keypair::new();
+        let mint_key = mint.pubkey();
+        let metadata_seeds = [b"metadata", metadata_program.as_ref(), mint_key.as_ref()];
+        let edition_seeds = [
+            b"metadata",
+            metadata_program.as_ref(),
+            mint_key.as_ref(),
+            b"edition",
+        ];
+        let token_account_seeds = [owner.as_ref(), token_program.as_ref(), mint_key.as_ref()];
+        let bumps = [
+            Pubkey::find_program_address(&metadata_seeds, &metadata_program).1,
+            Pubkey::find_program_address(&edition_seeds, &metadata_program).1,
+            Pubkey::find_program_address(&token_account_seeds, &spl_associated_token_account::id())
+                .1,
+        ];
+        if bumps.iter().all(|bump| *bump == 255) {
+            break mint;
+        }
+    }
+}
+
+/// Unregistered wallet whose user data has the first bump
+#[cfg(feature = "test-bpf")]
+fn wallet_with_first_bump(airdrop: &TestAirdrop) -> Pubkey {
+    loop {
+        let wallet = Pubkey::new_unique();
+        if find_airdrop_user_data(&airdrop.config, &wallet).1 == 255 {
+            break wallet;
+        }
+    }
+}
+
+/// Paid mint costs over free one only the price transfer and in place statistics, as
+/// config is unpacked once per mint. Transfer cost is taken from registration, whose fee
+/// is the only difference between two registrations. Before, every mint unpacked config
+/// three times and paid one repacked it once more
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn paid_mint_reads_config_once() {
+    let mut program_test = program_test();
+    let free = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            price: 0,
+            ..AirdropArgs::default()
+        },
+    );
+    let paid = add_airdrop(
+        &mut program_test,
+        AirdropArgs {
+            registration_fee: 1_000_000,
+            ..AirdropArgs::default()
+        },
+    );
+    let free_user = add_user(&mut program_test, &free);
+    let paid_user = add_user(&mut program_test, &paid);
+    let mut ctx = program_test.start_with_context().await;
+    let payer = ctx.payer.pubkey();
+
+    let mut mint_units = vec![];
+    for (airdrop, user) in [(&free, &free_user), (&paid, &paid_user)] {
+        let mint = mint_with_first_bumps(&user.pubkey());
+        let instruction =
+            mint_one_instruction(airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+        let (units, _) =
+            simulate_compute(&mut ctx, instruction, &[&mint, user, &airdrop.authority]).await;
+        mint_units.push(units);
+    }
+
+    let mut register_units = vec![];
+    for airdrop in [&free, &paid] {
+        let wallet = wallet_with_first_bump(airdrop);
+        let instruction =
+            register_user_instruction(airdrop, &airdrop.user_data(&wallet), &wallet, &payer);
+        let (units, _) = simulate_compute(&mut ctx, instruction, &[]).await;
+        register_units.push(units);
+    }
+
+    let price_units = mint_units[1] - mint_units[0];
+    let transfer_units = register_units[1] - register_units[0];
+
+    assert!(price_units <= transfer_units + 500);
+    println!(
+        "MintOne: {} compute units free, {} paid, price transfer costs {}",
+        mint_units[0], mint_units[1], transfer_units
+    );
+}
+
+#[tokio::test]
+async fn recompute_bumps_fixes_stale_bumps() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &airdrop);
+    let mut ctx = program_test.start_with_context().await;
+
+    update_config(&mut ctx, &airdrop, |config| config.mint_authority_bump = 0).await;
+    update_user_data(&mut ctx, &airdrop, &user.pubkey(), |data| data.bump = 0).await;
+
+    process(
+        &mut ctx,
+        &[recompute_bumps_instruction(
+            &airdrop,
+            None,
+            &[user.pubkey()],
+        )],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let (_, mint_authority_bump) = find_mint_authority(&airdrop.config);
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.mint_authority_bump, mint_authority_bump);
+
+    let (user_data, user_data_bump) = find_airdrop_user_data(&airdrop.config, &user.pubkey());
+    let account = ctx
+        .banks_client
+        .get_account(user_data)
+        .await
+        .unwrap()
+        .unwrap();
+    let data = AirdropUserData::unpack_unchecked(&account.data).unwrap();
+    assert_eq!(data.bump, user_data_bump);
+}
+
+#[tokio::test]
+async fn recompute_bumps_rejects_wrong_mint_authority() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mut ctx = program_test.start_with_context().await;
+
+    let mut instruction = recompute_bumps_instruction(&airdrop, None, &[]);
+    instruction.accounts[2].pubkey = Pubkey::new_unique();
+
+    let error = process(&mut ctx, &[instruction], &[&airdrop.authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        airdrop_error(error),
+        Some(AirdropError::InvalidMintAuthorityPda as u32)
+    );
+}
+
+/// Airdrop that has minted a token listing `creators`, stale mint authority bump is cached
+async fn start_with_minted_token(
+    creators: impl FnOnce(&TestAirdrop) -> Vec<(Pubkey, bool)>,
+) -> (ProgramTestContext, TestAirdrop, Pubkey) {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let mint = Pubkey::new_unique();
+    program_test.add_account(
+        find_metadata(&mint),
+        metadata_account(&mint, &creators(&airdrop)),
+    );
+    let mut ctx = program_test.start_with_context().await;
+    update_config(&mut ctx, &airdrop, |config| {
+        config.airdrop_index = 1;
+        config.mint_authority_bump = 0;
+    })
+    .await;
+
+    (ctx, airdrop, mint)
+}
+
+#[tokio::test]
+async fn recompute_bumps_accepts_token_verified_by_mint_authority() {
+    let (mut ctx, airdrop, mint) =
+        start_with_minted_token(|airdrop| vec![(airdrop.mint_authority, true)]).await;
+
+    process(
+        &mut ctx,
+        &[recompute_bumps_instruction(&airdrop, Some(&mint), &[])],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap();
+
+    let (_, mint_authority_bump) = find_mint_authority(&airdrop.config);
+    let config = get_config(&mut ctx, &airdrop.config).await;
+    assert_eq!(config.mint_authority_bump, mint_authority_bump);
+}
+
+#[tokio::test]
+async fn recompute_bumps_requires_minted_token_once_airdrop_minted() {
+    let (mut ctx, airdrop, _) =
+        start_with_minted_token(|airdrop| vec![(airdrop.mint_authority, true)]).await;
+
+    let error = process(
+        &mut ctx,
+        &[recompute_bumps_instruction(&airdrop, None, &[])],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+}
+
+#[tokio::test]
+async fn recompute_bumps_rejects_token_not_verified_by_mint_authority() {
+    for creators in [
+        |airdrop: &TestAirdrop| vec![(airdrop.mint_authority, false)],
+        |_: &TestAirdrop| vec![(Pubkey::new_unique(), true)],
+    ] {
+        let (mut ctx, airdrop, mint) = start_with_minted_token(creators).await;
+
+        let error = process(
+            &mut ctx,
+            &[recompute_bumps_instruction(&airdrop, Some(&mint), &[])],
+            &[&airdrop.authority],
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            airdrop_error(error),
+            Some(AirdropError::InvalidMintAuthorityPda as u32)
+        );
+    }
+}
+
+#[tokio::test]
+async fn recompute_bumps_rejects_metadata_of_other_program() {
+    let (mut ctx, airdrop, mint) =
+        start_with_minted_token(|airdrop| vec![(airdrop.mint_authority, true)]).await;
+
+    let mut account = ctx
+        .banks_client
+        .get_account(find_metadata(&mint))
+        .await
+        .unwrap()
+        .unwrap();
+    account.owner = premint_airdrop::id();
+    ctx.set_account(&find_metadata(&mint), &account.into());
+
+    let error = process(
+        &mut ctx,
+        &[recompute_bumps_instruction(&airdrop, Some(&mint), &[])],
+        &[&airdrop.authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+    );
+}
+
+#[tokio::test]
+async fn recompute_bumps_rejects_user_data_of_other_airdrop() {
+    let mut program_test = program_test();
+    let airdrop = add_airdrop(&mut program_test, AirdropArgs::default());
+    let other = add_airdrop(&mut program_test, AirdropArgs::default());
+    let user = add_user(&mut program_test, &other);
+    let mut ctx = program_test.start_with_context().await;
+
+    let mut instruction = recompute_bumps_instruction(&airdrop, None, &[]);
+    instruction
+        .accounts
+        .push(AccountMeta::new(other.user_data(&user.pubkey()), false));
+
+    let error = process(&mut ctx, &[instruction], &[&airdrop.authority])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
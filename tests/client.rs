@@ -0,0 +1,597 @@
+#![cfg(feature = "client")]
+
+use premint_airdrop::{
+    client,
+    instruction::{
+        deserialize_instruction_data, AddStageArgs, AirdropInstruction, InitializeAirdropArgs,
+    },
+    state::{AirdropConfig, AirdropUserData, RevenueSplit, MAX_REVENUE_SPLITS, MAX_URI_LENGTH},
+};
+use solana_program::{
+    instruction::AccountMeta, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+};
+
+fn config() -> AirdropConfig {
+    AirdropConfig {
+        initialized: true,
+        airdrop_authority: Pubkey::new_unique(),
+        airdrop_index: 3,
+        airdrop_amount: 10,
+        base_uri_len: 3,
+        base_uri: [1; MAX_URI_LENGTH],
+        symbol: [2; 8],
+        airdrop_users: 4,
+        revenues_wallet: Pubkey::new_unique(),
+        admin_account: Pubkey::new_unique(),
+        price: 100,
+        revealed: false,
+        placeholder_uri: [3; 32],
+        auto_lock: true,
+        revenue_splits: [RevenueSplit::default(); MAX_REVENUE_SPLITS],
+        charge_payer: false,
+        mint_authority_bump: 254,
+        max_mints_per_tx: 1,
+        nonce: 42,
+        trait_count: 5,
+        mint_cooldown_secs: 60,
+        referral_bps: 0,
+        bot_tax_lamports: 0,
+        name: *b"season-1\0\0\0\0\0\0\0\0",
+        start_ts: 0,
+        end_ts: 0,
+        stage_count: 0,
+        expected_decimals: 0,
+        escrow_timeout_secs: 0,
+        mint_quantity: 1,
+        registration_fee: 0,
+        registered_count: 0,
+        fairness_bps: 0,
+        raffle: false,
+        raffle_seed: [0; 32],
+        winners_count: 0,
+        is_active: true,
+        price_usd_cents: 0,
+        pyth_price_account: Pubkey::default(),
+        max_price_age_slots: 0,
+        finalized: false,
+        gifted_count: 0,
+        total_revenue: 0,
+        final_minted: 0,
+        final_gifted: 0,
+        final_registered: 0,
+        final_revenue: 0,
+        require_registration: true,
+        deny_cpi_mint: false,
+        min_payer_balance: 0,
+    }
+}
+
+#[test]
+fn airdrop_config_decodes_packed_data() {
+    let config = config();
+    let mut data = vec![0; AirdropConfig::LEN];
+    config.pack_into_slice(&mut data);
+
+    let decoded = AirdropConfig::try_deserialize(&data).unwrap();
+    assert_eq!(decoded.airdrop_authority, config.airdrop_authority);
+    assert_eq!(decoded.airdrop_index, 3);
+    assert_eq!(decoded.price, 100);
+    assert_eq!(decoded.nonce, 42);
+    assert_eq!(decoded.base_uri(), &[1; 3]);
+}
+
+#[test]
+fn airdrop_config_rejects_wrong_length() {
+    assert_eq!(
+        AirdropConfig::try_deserialize(&[0; 10]).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+}
+
+#[test]
+fn airdrop_config_rejects_uninitialized() {
+    assert!(AirdropConfig::try_deserialize(&vec![0; AirdropConfig::LEN]).is_err());
+}
+
+#[test]
+fn user_data_decodes_packed_data() {
+    let user_data = AirdropUserData {
+        initialized: true,
+        airdrop: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+        mints_amount: 2,
+        locked_till: 1000,
+        bump: 253,
+        nonce: 42,
+        registration_index: 17,
+        blocked: false,
+    };
+    let mut data = vec![0; AirdropUserData::LEN];
+    user_data.pack_into_slice(&mut data);
+
+    let decoded = AirdropUserData::try_deserialize(&data).unwrap();
+    assert_eq!(decoded.user, user_data.user);
+    assert_eq!(decoded.mints_amount, 2);
+    assert_eq!(decoded.locked_till, 1000);
+    assert_eq!(decoded.registration_index, 17);
+}
+
+#[test]
+fn user_data_rejects_undersized_buffer() {
+    assert_eq!(
+        AirdropUserData::try_deserialize(&[1; 10]).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+}
+
+#[test]
+fn initialize_airdrop_builder_round_trips() {
+    let args = InitializeAirdropArgs {
+        airdrop_amount: 10,
+        base_uri: format!("https://arweave.net/{}", "a".repeat(100)).into_bytes(),
+        symbol: [2; 8],
+        price: 100,
+        placeholder_uri: [0; 32],
+        auto_lock: true,
+        revenue_splits: [RevenueSplit::default(); MAX_REVENUE_SPLITS],
+        charge_payer: true,
+        max_mints_per_tx: 2,
+        name: [4; 16],
+        trait_count: 7,
+        mint_cooldown_secs: 60,
+        referral_bps: 500,
+        bot_tax_lamports: 10_000_000,
+        expected_decimals: 2,
+        escrow_timeout_secs: 86_400,
+        mint_quantity: 500,
+        registration_fee: 5_000_000,
+        fairness_bps: 2_500,
+        raffle: true,
+        require_registration: false,
+        deny_cpi_mint: true,
+        min_payer_balance: 2_000_000_000,
+    };
+    let authority = Pubkey::new_unique();
+    let (airdrop, _) = client::find_airdrop_config(&authority, &args.name);
+    let instruction = client::initialize_airdrop(
+        &authority,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &args,
+    );
+    assert_eq!(instruction.accounts[0].pubkey, airdrop);
+
+    match deserialize_instruction_data(&instruction.data).unwrap() {
+        AirdropInstruction::InitializeAirdrop(parsed) => {
+            assert_eq!(parsed.airdrop_amount, 10);
+            assert_eq!(parsed.base_uri, args.base_uri);
+            assert_eq!(parsed.price, 100);
+            assert!(parsed.auto_lock);
+            assert!(parsed.charge_payer);
+            assert_eq!(parsed.max_mints_per_tx, 2);
+            assert_eq!(parsed.name, [4; 16]);
+            assert_eq!(parsed.trait_count, 7);
+            assert_eq!(parsed.mint_cooldown_secs, 60);
+            assert_eq!(parsed.referral_bps, 500);
+            assert_eq!(parsed.bot_tax_lamports, 10_000_000);
+            assert_eq!(parsed.expected_decimals, 2);
+            assert_eq!(parsed.escrow_timeout_secs, 86_400);
+            assert_eq!(parsed.mint_quantity, 500);
+            assert_eq!(parsed.registration_fee, 5_000_000);
+            assert_eq!(parsed.fairness_bps, 2_500);
+            assert!(parsed.raffle);
+            assert!(!parsed.require_registration);
+            assert!(parsed.deny_cpi_mint);
+            assert_eq!(parsed.min_payer_balance, 2_000_000_000);
+        }
+        _ => panic!("Wrong instruction"),
+    }
+}
+
+#[test]
+fn mint_one_builder_includes_split_wallets() {
+    let mut config = config();
+    config.revenue_splits[0] = RevenueSplit {
+        wallet: Pubkey::new_unique(),
+        basis_points: 10000,
+    };
+    let airdrop = Pubkey::new_unique();
+
+    let instruction = client::mint_one(
+        &airdrop,
+        &config,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        Some(&Pubkey::new_unique()),
+    );
+
+    // Referrer is skipped, as airdrop has no referral cut
+    assert_eq!(instruction.accounts.len(), 21);
+    assert_eq!(
+        instruction.accounts[20].pubkey,
+        config.revenue_splits[0].wallet
+    );
+    assert!(instruction.accounts[3].is_signer);
+}
+
+#[test]
+fn mint_one_builder_passes_placeholder_for_open_mint() {
+    let mut config = config();
+    config.require_registration = false;
+
+    let instruction = client::mint_one(
+        &Pubkey::new_unique(),
+        &config,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        None,
+    );
+
+    assert_eq!(instruction.accounts[1].pubkey, premint_airdrop::id());
+    assert!(!instruction.accounts[1].is_writable);
+    assert_eq!(
+        client::validate_mint_accounts(&instruction.accounts),
+        Ok(())
+    );
+}
+
+#[test]
+fn mint_one_builder_passes_master_edition() {
+    let mint = Pubkey::new_unique();
+
+    let instruction = client::mint_one(
+        &Pubkey::new_unique(),
+        &config(),
+        &Pubkey::new_unique(),
+        &mint,
+        &Pubkey::new_unique(),
+        None,
+    );
+
+    assert_eq!(
+        instruction.accounts[19].pubkey,
+        client::find_master_edition(&mint)
+    );
+    assert!(instruction.accounts[19].is_writable);
+}
+
+#[test]
+fn mint_one_builder_passes_read_only_revenue_wallet_for_free_mint() {
+    let mut config = config();
+    config.price = 0;
+
+    let instruction = client::mint_one(
+        &Pubkey::new_unique(),
+        &config,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        None,
+    );
+
+    assert_eq!(instruction.accounts[16].pubkey, config.revenues_wallet);
+    assert!(!instruction.accounts[16].is_writable);
+}
+
+#[test]
+fn mint_one_gift_builder_passes_recipient() {
+    let config = config();
+    let recipient = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    let instruction = client::mint_one_gift(
+        &Pubkey::new_unique(),
+        &config,
+        &Pubkey::new_unique(),
+        &recipient,
+        &mint,
+        &Pubkey::new_unique(),
+        None,
+    );
+
+    assert_eq!(
+        instruction.accounts[4].pubkey,
+        spl_associated_token_account::get_associated_token_address(&recipient, &mint)
+    );
+    assert_eq!(instruction.accounts[20].pubkey, recipient);
+    match deserialize_instruction_data(&instruction.data).unwrap() {
+        AirdropInstruction::MintOne(parsed) => {
+            assert!(parsed.gift);
+            assert!(parsed.proof.is_empty());
+        }
+        _ => panic!("Wrong instruction"),
+    }
+}
+
+#[test]
+fn mint_to_escrow_builder_passes_escrow_and_claimant() {
+    let mut config = config();
+    config.airdrop_index = 7;
+    let airdrop = Pubkey::new_unique();
+    let claimant = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    let instruction = client::mint_to_escrow(
+        &airdrop,
+        &config,
+        &Pubkey::new_unique(),
+        &claimant,
+        &mint,
+        &Pubkey::new_unique(),
+        None,
+    );
+
+    let (escrow, _) = client::find_escrow(&airdrop, 7);
+    assert_eq!(
+        instruction.accounts[4].pubkey,
+        spl_associated_token_account::get_associated_token_address(&escrow, &mint)
+    );
+    assert_eq!(instruction.accounts[20].pubkey, escrow);
+    match deserialize_instruction_data(&instruction.data).unwrap() {
+        AirdropInstruction::MintToEscrow(parsed) => {
+            assert_eq!(parsed.claimant, claimant);
+            assert!(parsed.proof.is_empty());
+        }
+        _ => panic!("Wrong instruction"),
+    }
+}
+
+#[test]
+fn mint_one_in_stage_builder_passes_stage_and_proof() {
+    let config = config();
+    let airdrop = Pubkey::new_unique();
+
+    let instruction = client::mint_one_in_stage(
+        &airdrop,
+        &config,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        None,
+        2,
+        &[[7; 32], [8; 32]],
+    );
+
+    assert_eq!(instruction.accounts.len(), 21);
+    assert_eq!(
+        instruction.accounts[20].pubkey,
+        client::find_stage(&airdrop, 2).0
+    );
+    match deserialize_instruction_data(&instruction.data).unwrap() {
+        AirdropInstruction::MintOne(parsed) => assert_eq!(parsed.proof, vec![[7; 32], [8; 32]]),
+        _ => panic!("Wrong instruction"),
+    }
+}
+
+#[test]
+fn add_stage_builder_round_trips() {
+    let args = AddStageArgs {
+        stage_index: 1,
+        price: 5,
+        supply_cap: 6,
+        start_ts: 100,
+        end_ts: 200,
+        merkle_root: [9; 32],
+        per_user_limit: 2,
+    };
+    let existing = Pubkey::new_unique();
+
+    let instruction = client::add_stage(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &[existing],
+        &args,
+    );
+
+    assert_eq!(instruction.accounts[6].pubkey, existing);
+    match deserialize_instruction_data(&instruction.data).unwrap() {
+        AirdropInstruction::AddStage(parsed) => {
+            assert_eq!(parsed.stage_index, 1);
+            assert_eq!(parsed.price, 5);
+            assert_eq!(parsed.supply_cap, 6);
+            assert_eq!(parsed.start_ts, 100);
+            assert_eq!(parsed.end_ts, 200);
+            assert_eq!(parsed.merkle_root, [9; 32]);
+            assert_eq!(parsed.per_user_limit, 2);
+        }
+        _ => panic!("Wrong instruction"),
+    }
+}
+
+#[test]
+fn update_window_builder_round_trips() {
+    let instruction = client::update_window(&Pubkey::new_unique(), &Pubkey::new_unique(), 100, 200);
+
+    match deserialize_instruction_data(&instruction.data).unwrap() {
+        AirdropInstruction::UpdateWindow(parsed) => {
+            assert_eq!(parsed.start_ts, 100);
+            assert_eq!(parsed.end_ts, 200);
+        }
+        _ => panic!("Wrong instruction"),
+    }
+}
+
+#[test]
+fn initialize_airdrop_user_builders_set_idempotent_flag() {
+    let (airdrop, user, payer) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    for (instruction, expected) in [
+        (
+            client::initialize_airdrop_user(&airdrop, &config(), &user, &payer),
+            false,
+        ),
+        (
+            client::initialize_airdrop_user_idempotent(&airdrop, &config(), &user, &payer),
+            true,
+        ),
+    ] {
+        match deserialize_instruction_data(&instruction.data).unwrap() {
+            AirdropInstruction::InitializeAirdropUser(parsed) => {
+                assert_eq!(parsed.idempotent, expected)
+            }
+            _ => panic!("Wrong instruction"),
+        }
+    }
+}
+
+#[test]
+fn claim_preminted_builder_appends_stage_and_proof() {
+    let config = config();
+    let airdrop = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let proof = [[5; 32], [6; 32]];
+
+    let instruction = client::claim_preminted(
+        &airdrop,
+        &config,
+        &Pubkey::new_unique(),
+        &mint,
+        &Pubkey::new_unique(),
+        Some((2, &proof)),
+    );
+
+    let (vault, _) = client::find_vault(&airdrop);
+    assert_eq!(instruction.accounts[4].pubkey, vault);
+    assert_eq!(
+        instruction.accounts[8],
+        AccountMeta::new(config.airdrop_authority, true)
+    );
+    assert_eq!(
+        instruction.accounts[5].pubkey,
+        spl_associated_token_account::get_associated_token_address(&vault, &mint)
+    );
+    assert_eq!(
+        instruction.accounts.last().unwrap().pubkey,
+        client::find_stage(&airdrop, 2).0
+    );
+    match deserialize_instruction_data(&instruction.data).unwrap() {
+        AirdropInstruction::ClaimPreminted(parsed) => assert_eq!(parsed.proof, proof),
+        _ => panic!("Wrong instruction"),
+    }
+}
+
+#[test]
+fn withdraw_unclaimed_builder_targets_vault_token_account() {
+    let airdrop = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    let instruction = client::withdraw_unclaimed(
+        &airdrop,
+        &Pubkey::new_unique(),
+        &mint,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+    );
+
+    let (vault, _) = client::find_vault(&airdrop);
+    assert_eq!(instruction.accounts[2].pubkey, vault);
+    assert_eq!(
+        instruction.accounts[3].pubkey,
+        spl_associated_token_account::get_associated_token_address(&vault, &mint)
+    );
+    assert!(matches!(
+        deserialize_instruction_data(&instruction.data).unwrap(),
+        AirdropInstruction::WithdrawUnclaimed(_)
+    ));
+}
+
+#[test]
+fn initialize_airdrop_user_passes_revenue_wallet_only_with_fee() {
+    let mut config = config();
+    let airdrop = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+
+    let instruction =
+        client::initialize_airdrop_user(&airdrop, &config, &user, &Pubkey::new_unique());
+    assert_eq!(instruction.accounts.len(), 6);
+
+    config.registration_fee = 10_000_000;
+    let instruction =
+        client::initialize_airdrop_user(&airdrop, &config, &user, &Pubkey::new_unique());
+    assert_eq!(instruction.accounts.len(), 7);
+    assert_eq!(instruction.accounts[6].pubkey, config.revenues_wallet);
+    assert!(instruction.accounts[6].is_writable);
+}
+
+#[test]
+fn validate_mint_accounts_accepts_built_mint_one() {
+    let mut config = config();
+    config.referral_bps = 500;
+    let airdrop = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+
+    for referrer in [None, Some(&Pubkey::new_unique())] {
+        let instruction = client::mint_one(
+            &airdrop,
+            &config,
+            &user,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            referrer,
+        );
+        assert_eq!(
+            client::validate_mint_accounts(&instruction.accounts),
+            Ok(())
+        );
+    }
+}
+
+#[test]
+fn validate_mint_accounts_rejects_wrong_order() {
+    let instruction = client::mint_one(
+        &Pubkey::new_unique(),
+        &config(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        None,
+    );
+
+    // Sysvars swapped
+    let mut accounts = instruction.accounts.clone();
+    accounts.swap(8, 9);
+    assert_eq!(
+        client::validate_mint_accounts(&accounts),
+        Err("Account 8 must be clock sysvar")
+    );
+
+    // Payer and airdrop authority swapped
+    let mut accounts = instruction.accounts.clone();
+    accounts.swap(13, 14);
+    assert_eq!(
+        client::validate_mint_accounts(&accounts),
+        Err("Payer must be signer and writeable")
+    );
+
+    // Mint missing
+    let mut accounts = instruction.accounts.clone();
+    accounts.remove(2);
+    assert!(client::validate_mint_accounts(&accounts).is_err());
+
+    assert_eq!(
+        client::validate_mint_accounts(&instruction.accounts[..18]),
+        Err("Not enough accounts")
+    );
+}
+
+#[test]
+fn derive_user_data_pdas_matches_individual_derivation() {
+    let airdrop = Pubkey::new_unique();
+    let users: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+
+    let pdas = client::derive_user_data_pdas(&airdrop, &users);
+
+    assert_eq!(pdas.len(), users.len());
+    for (user, pda) in users.iter().zip(pdas) {
+        assert_eq!(pda, client::find_airdrop_user_data(&airdrop, user));
+    }
+    assert!(client::derive_user_data_pdas(&airdrop, &[]).is_empty());
+}
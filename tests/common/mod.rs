@@ -0,0 +1,1582 @@
+#![allow(dead_code)]
+
+use premint_airdrop::{
+    instruction::AddStageArgs,
+    pda::{
+        find_airdrop_config, find_airdrop_user_data, find_escrow, find_master_edition,
+        find_metadata, find_mint_authority, find_stage, find_vault,
+    },
+    state::{
+        AirdropConfig, AirdropEscrow, AirdropStage, AirdropUserData, PremintVault, RevenueSplit,
+        MAX_REVENUE_SPLITS, PLATFORM_FEE_LAMPORTS, PLATFORM_WALLET, PROTOCOL_FEE_BPS,
+        PROTOCOL_TREASURY,
+    },
+};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::{get_return_data, invoke},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program, sysvar,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+/// Processors of this program and token metadata tie account slice and account data
+/// lifetimes together, as BPF entrypoint does. Builtin processors receive them separately
+fn narrow_lifetimes<'b, 'c: 'b>(accounts: &'b [AccountInfo<'c>]) -> &'b [AccountInfo<'b>] {
+    // SAFETY: account data outlives the slice, and `AccountInfo` is only read through it
+    unsafe { std::mem::transmute(accounts) }
+}
+
+fn process_airdrop(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    premint_airdrop::processor::process_instruction(program_id, narrow_lifetimes(accounts), data)
+}
+
+fn process_token_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    metaplex_token_metadata::processor::process_instruction(
+        program_id,
+        narrow_lifetimes(accounts),
+        data,
+    )
+}
+
+/// Program test with airdrop and token metadata programs.
+/// Token and associated token programs are bundled with program test.
+///
+/// Native processors can not resize accounts in CPI, so anything creating accounts from
+/// the program (PDA config, user registration, minting) needs `test-bpf` feature with
+/// `premint_airdrop.so` and `metaplex_token_metadata.so` in `BPF_OUT_DIR`
+pub fn program_test() -> ProgramTest {
+    with_test_programs(ProgramTest::new(
+        "premint_airdrop",
+        premint_airdrop::id(),
+        processor!(process_airdrop),
+    ))
+}
+
+/// Program test running `<program_name>.so` build of airdrop program, so builds with
+/// different features can be compared
+#[cfg(feature = "test-bpf")]
+pub fn program_test_of_build(program_name: &str) -> ProgramTest {
+    with_test_programs(ProgramTest::new(program_name, premint_airdrop::id(), None))
+}
+
+fn with_test_programs(mut program_test: ProgramTest) -> ProgramTest {
+    program_test.add_program(
+        "metaplex_token_metadata",
+        metaplex_token_metadata::id(),
+        processor!(process_token_metadata),
+    );
+
+    program_test.add_builtin_program(
+        "return_data_probe",
+        return_data_probe_id(),
+        processor!(process_return_data_probe).unwrap(),
+    );
+
+    program_test
+}
+
+/// Log prefix return data probe reports hex encoded return data with
+const RETURN_DATA_LOG: &str = "Program log: Return data ";
+
+/// Forwards instruction to the program passed as the last account and fails after it
+/// succeeds, logging its return data. Banks client of this version has no simulate call,
+/// but reports logs of failed preflight simulation
+pub fn return_data_probe_id() -> Pubkey {
+    Pubkey::new_from_array([0xad; 32])
+}
+
+fn process_return_data_probe(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let (program, forwarded) = accounts.split_last().unwrap();
+    let instruction = Instruction::new_with_bytes(
+        *program.key,
+        data,
+        forwarded
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+    );
+
+    invoke(&instruction, accounts)?;
+
+    let return_data = match get_return_data() {
+        Some((program_id, data)) if program_id == *program.key => data,
+        _ => vec![],
+    };
+    let hex: String = return_data.iter().map(|b| format!("{:02x}", b)).collect();
+    msg!("Return data {}", hex);
+
+    Err(ProgramError::Custom(u32::MAX))
+}
+
+/// Simulates instruction and returns data it set. Error of the instruction itself is
+/// returned as is, so [`airdrop_error`] reads it
+pub async fn simulate_return_data(
+    ctx: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<Vec<u8>, BanksClientError> {
+    let error = simulate_probe(ctx, instruction, signers).await;
+
+    if let BanksClientError::SimulationError { logs, .. } = &error {
+        if let Some(hex) = logs
+            .iter()
+            .find_map(|log| log.strip_prefix(RETURN_DATA_LOG))
+        {
+            return Ok((0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect());
+        }
+    }
+
+    Err(error)
+}
+
+/// Simulates succeeding instruction and returns compute units it consumed with its logs
+#[cfg(feature = "test-bpf")]
+pub async fn simulate_compute(
+    ctx: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> (u64, Vec<String>) {
+    match simulate_probe(ctx, instruction, signers).await {
+        BanksClientError::SimulationError {
+            logs,
+            units_consumed,
+            ..
+        } if logs.iter().any(|log| log.starts_with(RETURN_DATA_LOG)) => (units_consumed, logs),
+        error => panic!("instruction failed: {}", error),
+    }
+}
+
+async fn simulate_probe(
+    ctx: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> BanksClientError {
+    let mut probe = instruction.clone();
+    probe.program_id = return_data_probe_id();
+    probe
+        .accounts
+        .push(AccountMeta::new_readonly(instruction.program_id, false));
+
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        &[probe],
+        Some(&ctx.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+
+    ctx.banks_client
+        .process_transaction_with_preflight(transaction)
+        .await
+        .unwrap_err()
+}
+
+pub async fn start() -> ProgramTestContext {
+    program_test().start_with_context().await
+}
+
+pub struct AirdropArgs {
+    pub airdrop_amount: u64,
+    pub base_uri: Vec<u8>,
+    pub symbol: [u8; 8],
+    pub price: u64,
+    pub placeholder_uri: [u8; 32],
+    pub auto_lock: bool,
+    pub revenue_splits: [RevenueSplit; MAX_REVENUE_SPLITS],
+    pub charge_payer: bool,
+    pub max_mints_per_tx: u8,
+    pub name: [u8; 16],
+    pub trait_count: u8,
+    pub mint_cooldown_secs: u64,
+    pub referral_bps: u16,
+    pub bot_tax_lamports: u64,
+    pub expected_decimals: u8,
+    pub escrow_timeout_secs: u64,
+    pub mint_quantity: u64,
+    pub registration_fee: u64,
+    pub fairness_bps: u16,
+    pub raffle: bool,
+    pub require_registration: bool,
+    pub deny_cpi_mint: bool,
+    pub min_payer_balance: u64,
+    /// Send legacy instruction with base URI padded to 32 bytes
+    pub fixed_layout: bool,
+    /// Stages of injected airdrop. Stage accounts are added separately
+    pub stage_count: u8,
+}
+
+impl Default for AirdropArgs {
+    fn default() -> Self {
+        AirdropArgs {
+            airdrop_amount: 10,
+            base_uri: b"https://example.com/katts".to_vec(),
+            symbol: padded(b"KATT"),
+            price: 100_000_000,
+            placeholder_uri: padded(b"https://example.com/hidden.json"),
+            auto_lock: false,
+            revenue_splits: [RevenueSplit::default(); MAX_REVENUE_SPLITS],
+            charge_payer: false,
+            max_mints_per_tx: 0,
+            name: padded(b"katts"),
+            trait_count: 0,
+            mint_cooldown_secs: 21600,
+            referral_bps: 0,
+            bot_tax_lamports: 0,
+            expected_decimals: 0,
+            escrow_timeout_secs: 0,
+            mint_quantity: 1,
+            registration_fee: 0,
+            fairness_bps: 0,
+            raffle: false,
+            require_registration: true,
+            deny_cpi_mint: false,
+            min_payer_balance: 0,
+            fixed_layout: false,
+            stage_count: 0,
+        }
+    }
+}
+
+impl AirdropArgs {
+    pub fn pack(&self) -> Vec<u8> {
+        if self.fixed_layout {
+            return self.pack_fixed();
+        }
+
+        let mut data = vec![10];
+        data.extend_from_slice(&self.airdrop_amount.to_le_bytes());
+        data.extend_from_slice(&self.pack_fields());
+        data.push(self.base_uri.len() as u8);
+        data.extend_from_slice(&self.base_uri);
+        data
+    }
+
+    /// Legacy layout with base URI padded to 32 bytes. Fields after
+    /// `bot_tax_lamports` are not part of it and get program defaults
+    pub fn pack_fixed(&self) -> Vec<u8> {
+        let mut data = vec![1];
+        data.extend_from_slice(&self.airdrop_amount.to_le_bytes());
+        data.extend_from_slice(&padded::<32>(&self.base_uri));
+        data.extend_from_slice(&self.pack_legacy_fields());
+        data
+    }
+
+    fn pack_fields(&self) -> Vec<u8> {
+        let mut data = self.pack_legacy_fields();
+        data.push(self.expected_decimals);
+        data.extend_from_slice(&self.escrow_timeout_secs.to_le_bytes());
+        data.extend_from_slice(&self.mint_quantity.to_le_bytes());
+        data.extend_from_slice(&self.registration_fee.to_le_bytes());
+        data.extend_from_slice(&self.fairness_bps.to_le_bytes());
+        data.push(self.raffle as u8);
+        data.push(self.require_registration as u8);
+        data.push(self.deny_cpi_mint as u8);
+        data.extend_from_slice(&self.min_payer_balance.to_le_bytes());
+        data
+    }
+
+    fn pack_legacy_fields(&self) -> Vec<u8> {
+        let mut data = vec![];
+        data.extend_from_slice(&self.symbol);
+        data.extend_from_slice(&self.price.to_le_bytes());
+        data.extend_from_slice(&self.placeholder_uri);
+        data.push(self.auto_lock as u8);
+        for split in self.revenue_splits.iter() {
+            data.extend_from_slice(split.wallet.as_ref());
+            data.extend_from_slice(&split.basis_points.to_le_bytes());
+        }
+        data.push(self.charge_payer as u8);
+        data.push(self.max_mints_per_tx);
+        data.extend_from_slice(&self.name);
+        data.push(self.trait_count);
+        data.extend_from_slice(&self.mint_cooldown_secs.to_le_bytes());
+        data.extend_from_slice(&self.referral_bps.to_le_bytes());
+        data.extend_from_slice(&self.bot_tax_lamports.to_le_bytes());
+        data
+    }
+}
+
+/// Copies string into a zero padded array, as program stores strings
+pub fn padded<const N: usize>(src: &[u8]) -> [u8; N] {
+    let mut dst = [0u8; N];
+    dst[..src.len()].copy_from_slice(src);
+    dst
+}
+
+/// Accounts of an initialized airdrop
+pub struct TestAirdrop {
+    pub config: Pubkey,
+    pub authority: Keypair,
+    pub mint_authority: Pubkey,
+    pub revenue_wallet: Pubkey,
+    pub admin: Pubkey,
+    pub revenue_splits: [RevenueSplit; MAX_REVENUE_SPLITS],
+    pub registration_fee: u64,
+}
+
+impl TestAirdrop {
+    pub fn user_data(&self, user: &Pubkey) -> Pubkey {
+        find_airdrop_user_data(&self.config, user).0
+    }
+}
+
+pub async fn get_config(ctx: &mut ProgramTestContext, config: &Pubkey) -> AirdropConfig {
+    let account = ctx
+        .banks_client
+        .get_account(*config)
+        .await
+        .unwrap()
+        .unwrap();
+    AirdropConfig::unpack_unchecked(&account.data).unwrap()
+}
+
+pub async fn get_user_data(ctx: &mut ProgramTestContext, user_data: &Pubkey) -> AirdropUserData {
+    let account = ctx
+        .banks_client
+        .get_account(*user_data)
+        .await
+        .unwrap()
+        .unwrap();
+    AirdropUserData::unpack_unchecked(&account.data).unwrap()
+}
+
+pub async fn process(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = ctx.banks_client.get_latest_blockhash().await?;
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&ctx.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+
+    ctx.banks_client.process_transaction(transaction).await
+}
+
+/// Funds system account so it can hold lamports without violating rent
+pub async fn fund(ctx: &mut ProgramTestContext, wallet: &Pubkey, lamports: u64) {
+    let payer = ctx.payer.pubkey();
+    process(
+        ctx,
+        &[system_instruction::transfer(&payer, wallet, lamports)],
+        &[],
+    )
+    .await
+    .unwrap();
+}
+
+pub fn initialize_airdrop_instruction(
+    config: &Pubkey,
+    authority: &Pubkey,
+    revenue_wallet: &Pubkey,
+    admin: &Pubkey,
+    fee_payer: &Pubkey,
+    args: &AirdropArgs,
+) -> Instruction {
+    let (mint_authority, _) = find_mint_authority(config);
+    // Authority claims the name by signing when program creates config
+    let is_pda = *config == find_airdrop_config(authority, &args.name).0;
+
+    let mut accounts = vec![
+        AccountMeta::new(*config, false),
+        AccountMeta::new_readonly(*authority, is_pda),
+        AccountMeta::new(mint_authority, false),
+        AccountMeta::new_readonly(*revenue_wallet, false),
+        AccountMeta::new_readonly(*admin, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(*fee_payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    if PLATFORM_FEE_LAMPORTS != 0 {
+        accounts.push(AccountMeta::new(PLATFORM_WALLET, false));
+    }
+
+    Instruction::new_with_bytes(premint_airdrop::id(), &args.pack(), accounts)
+}
+
+/// Initializes airdrop in account allocated by the same transaction.
+/// Works with native processors, as program does not have to create config itself
+pub async fn create_airdrop(ctx: &mut ProgramTestContext, args: AirdropArgs) -> TestAirdrop {
+    try_create_airdrop(ctx, args).await.unwrap()
+}
+
+pub async fn try_create_airdrop(
+    ctx: &mut ProgramTestContext,
+    args: AirdropArgs,
+) -> Result<TestAirdrop, BanksClientError> {
+    let config = Keypair::new();
+    let authority = Keypair::new();
+    let revenue_wallet = Keypair::new().pubkey();
+    let admin = Keypair::new().pubkey();
+
+    fund(ctx, &revenue_wallet, 1_000_000_000).await;
+    for split in args.revenue_splits.iter().filter(|s| s.is_used()) {
+        fund(ctx, &split.wallet, 1_000_000_000).await;
+    }
+
+    let payer = ctx.payer.pubkey();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &config.pubkey(),
+            rent.minimum_balance(AirdropConfig::LEN),
+            AirdropConfig::LEN as u64,
+            &premint_airdrop::id(),
+        ),
+        initialize_airdrop_instruction(
+            &config.pubkey(),
+            &authority.pubkey(),
+            &revenue_wallet,
+            &admin,
+            &payer,
+            &args,
+        ),
+    ];
+
+    process(ctx, &instructions, &[&config]).await?;
+
+    Ok(TestAirdrop {
+        config: config.pubkey(),
+        mint_authority: find_mint_authority(&config.pubkey()).0,
+        authority,
+        revenue_wallet,
+        admin,
+        revenue_splits: args.revenue_splits,
+        registration_fee: args.registration_fee,
+    })
+}
+
+/// Initializes airdrop in config derived from authority and name. Needs `test-bpf`
+pub async fn create_pda_airdrop(ctx: &mut ProgramTestContext, args: AirdropArgs) -> TestAirdrop {
+    create_pda_airdrop_with_authority(ctx, Keypair::new(), args).await
+}
+
+/// Same as [`create_pda_airdrop`] for a given authority. Needs `test-bpf`
+pub async fn create_pda_airdrop_with_authority(
+    ctx: &mut ProgramTestContext,
+    authority: Keypair,
+    args: AirdropArgs,
+) -> TestAirdrop {
+    let revenue_wallet = Keypair::new().pubkey();
+    let admin = Keypair::new().pubkey();
+    let (config, _) = find_airdrop_config(&authority.pubkey(), &args.name);
+
+    fund(ctx, &revenue_wallet, 1_000_000_000).await;
+    fund(ctx, &authority.pubkey(), 1_000_000_000).await;
+    for split in args.revenue_splits.iter().filter(|s| s.is_used()) {
+        fund(ctx, &split.wallet, 1_000_000_000).await;
+    }
+
+    let payer = ctx.payer.pubkey();
+    let instruction = initialize_airdrop_instruction(
+        &config,
+        &authority.pubkey(),
+        &revenue_wallet,
+        &admin,
+        &payer,
+        &args,
+    );
+
+    process(ctx, &[instruction], &[&authority]).await.unwrap();
+
+    TestAirdrop {
+        config,
+        mint_authority: find_mint_authority(&config).0,
+        authority,
+        revenue_wallet,
+        admin,
+        revenue_splits: args.revenue_splits,
+        registration_fee: args.registration_fee,
+    }
+}
+
+pub fn register_user_instruction(
+    airdrop: &TestAirdrop,
+    user_data: &Pubkey,
+    user: &Pubkey,
+    fee_payer: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*user_data, false),
+        AccountMeta::new_readonly(*user, false),
+        AccountMeta::new(airdrop.config, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(*fee_payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    if airdrop.registration_fee > 0 {
+        accounts.push(AccountMeta::new(airdrop.revenue_wallet, false));
+    }
+
+    Instruction::new_with_bytes(premint_airdrop::id(), &[2], accounts)
+}
+
+pub fn register_user_idempotent_instruction(
+    airdrop: &TestAirdrop,
+    user: &Pubkey,
+    fee_payer: &Pubkey,
+) -> Instruction {
+    let mut instruction =
+        register_user_instruction(airdrop, &airdrop.user_data(user), user, fee_payer);
+    instruction.data.push(1);
+    instruction
+}
+
+pub async fn register_user(
+    ctx: &mut ProgramTestContext,
+    airdrop: &TestAirdrop,
+    user: &Pubkey,
+) -> Result<Pubkey, BanksClientError> {
+    let user_data = airdrop.user_data(user);
+    let payer = ctx.payer.pubkey();
+    let instruction = register_user_instruction(airdrop, &user_data, user, &payer);
+
+    process(ctx, &[instruction], &[]).await?;
+
+    Ok(user_data)
+}
+
+pub fn mint_one_instruction(
+    airdrop: &TestAirdrop,
+    mint: &Pubkey,
+    user: &Pubkey,
+    user_signs: bool,
+    payer: &Pubkey,
+) -> Instruction {
+    let user_token_account = spl_associated_token_account::get_associated_token_address(user, mint);
+    let metadata = find_metadata(mint);
+
+    let mut accounts = vec![
+        AccountMeta::new(airdrop.config, false),
+        AccountMeta::new(airdrop.user_data(user), false),
+        AccountMeta::new(*mint, true),
+        AccountMeta::new(*user, user_signs),
+        AccountMeta::new(user_token_account, false),
+        AccountMeta::new(metadata, false),
+        AccountMeta::new(airdrop.mint_authority, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(metaplex_token_metadata::id(), false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+        AccountMeta::new_readonly(airdrop.admin, false),
+        AccountMeta::new(airdrop.revenue_wallet, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        AccountMeta::new(find_master_edition(mint), false),
+    ];
+
+    for split in airdrop.revenue_splits.iter().filter(|s| s.is_used()) {
+        accounts.push(AccountMeta::new(split.wallet, false));
+    }
+
+    if PROTOCOL_FEE_BPS != 0 {
+        accounts.push(AccountMeta::new(PROTOCOL_TREASURY, false));
+    }
+
+    Instruction::new_with_bytes(premint_airdrop::id(), &[3], accounts)
+}
+
+/// Mints token into recipient wallet, counted against user
+pub fn mint_one_gift_instruction(
+    airdrop: &TestAirdrop,
+    mint: &Pubkey,
+    user: &Pubkey,
+    recipient: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let mut instruction = mint_one_instruction(airdrop, mint, user, true, payer);
+    instruction.accounts[4].pubkey =
+        spl_associated_token_account::get_associated_token_address(recipient, mint);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*recipient, false));
+    instruction.data.push(1);
+
+    instruction
+}
+
+/// Mints in stage. Proof is empty when stage has no allowlist
+pub fn mint_one_in_stage_instruction(
+    airdrop: &TestAirdrop,
+    mint: &Pubkey,
+    user: &Pubkey,
+    payer: &Pubkey,
+    stage: &Pubkey,
+    proof: &[[u8; 32]],
+) -> Instruction {
+    let mut instruction = mint_one_instruction(airdrop, mint, user, true, payer);
+    instruction.accounts.push(AccountMeta::new(*stage, false));
+    for node in proof {
+        instruction.data.extend_from_slice(node);
+    }
+
+    instruction
+}
+
+pub fn add_stage_instruction(
+    airdrop: &TestAirdrop,
+    payer: &Pubkey,
+    args: &AddStageArgs,
+    existing_stages: &[Pubkey],
+) -> Instruction {
+    let (stage, _) = find_stage(&airdrop.config, args.stage_index);
+
+    let mut data = vec![12, args.stage_index];
+    data.extend_from_slice(&args.price.to_le_bytes());
+    data.extend_from_slice(&args.supply_cap.to_le_bytes());
+    data.extend_from_slice(&args.start_ts.to_le_bytes());
+    data.extend_from_slice(&args.end_ts.to_le_bytes());
+    data.extend_from_slice(&args.merkle_root);
+    data.extend_from_slice(&args.per_user_limit.to_le_bytes());
+
+    let mut accounts = vec![
+        AccountMeta::new(airdrop.config, false),
+        AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+        AccountMeta::new(stage, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for existing in existing_stages {
+        accounts.push(AccountMeta::new_readonly(*existing, false));
+    }
+
+    Instruction::new_with_bytes(premint_airdrop::id(), &data, accounts)
+}
+
+pub fn remove_stage_instruction(
+    airdrop: &TestAirdrop,
+    stage: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[13],
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+            AccountMeta::new(*stage, false),
+            AccountMeta::new(*receiver, false),
+        ],
+    )
+}
+
+pub fn sweep_user_account_instruction(airdrop: &TestAirdrop, user: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[14],
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new(airdrop.authority.pubkey(), true),
+            AccountMeta::new(airdrop.user_data(user), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+pub fn can_mint_instruction(airdrop: &TestAirdrop, user: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[5],
+        vec![
+            AccountMeta::new_readonly(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.user_data(user), false),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Stage and Pyth price accounts are pushed by the caller when airdrop needs them
+pub fn preview_mint_instruction(
+    airdrop: &TestAirdrop,
+    user: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[7],
+        vec![
+            AccountMeta::new_readonly(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.user_data(user), false),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(
+                spl_associated_token_account::get_associated_token_address(user, mint),
+                false,
+            ),
+        ],
+    )
+}
+
+pub fn update_revenue_wallet_instruction(
+    airdrop: &TestAirdrop,
+    revenue_wallet: &Pubkey,
+) -> Instruction {
+    let mut data = vec![21];
+    data.extend_from_slice(revenue_wallet.as_ref());
+
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &data,
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+        ],
+    )
+}
+
+pub fn set_usd_price_instruction(
+    airdrop: &TestAirdrop,
+    price_usd_cents: u64,
+    pyth_price_account: &Pubkey,
+    max_price_age_slots: u64,
+) -> Instruction {
+    let mut data = vec![25];
+    data.extend_from_slice(&price_usd_cents.to_le_bytes());
+    data.extend_from_slice(pyth_price_account.as_ref());
+    data.extend_from_slice(&max_price_age_slots.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &data,
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+        ],
+    )
+}
+
+pub fn set_user_blocked_instruction(
+    airdrop: &TestAirdrop,
+    user: &Pubkey,
+    fee_payer: &Pubkey,
+    blocked: bool,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[27, blocked as u8],
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+            AccountMeta::new(airdrop.user_data(user), false),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new(*fee_payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn increase_supply_instruction(
+    airdrop: &TestAirdrop,
+    authority: &Pubkey,
+    additional: u64,
+) -> Instruction {
+    let mut data = vec![6];
+    data.extend_from_slice(&additional.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &data,
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+pub fn finalize_instruction(airdrop: &TestAirdrop, lock: bool) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[26, lock as u8],
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+pub fn deactivate_instruction(airdrop: &TestAirdrop) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[24],
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+        ],
+    )
+}
+
+pub fn draw_raffle_instruction(airdrop: &TestAirdrop, winners_count: u64) -> Instruction {
+    let mut data = vec![22];
+    data.extend_from_slice(&winners_count.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &data,
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+            AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        ],
+    )
+}
+
+pub fn close_user_data_instruction(airdrop: &TestAirdrop, user: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[23],
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new(airdrop.user_data(user), false),
+        ],
+    )
+}
+
+pub fn deregister_instruction(airdrop: &TestAirdrop, user: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[28],
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new(airdrop.user_data(user), false),
+        ],
+    )
+}
+
+pub fn batch_init_users_instruction(
+    airdrop: &TestAirdrop,
+    authority: &Pubkey,
+    fee_payer: &Pubkey,
+    users: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(airdrop.config, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(*fee_payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    for user in users {
+        accounts.push(AccountMeta::new(airdrop.user_data(user), false));
+        accounts.push(AccountMeta::new_readonly(*user, false));
+    }
+
+    Instruction::new_with_bytes(premint_airdrop::id(), &[8], accounts)
+}
+
+pub fn recompute_bumps_instruction(
+    airdrop: &TestAirdrop,
+    minted: Option<&Pubkey>,
+    users: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(airdrop.config, false),
+        AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+        AccountMeta::new_readonly(airdrop.mint_authority, false),
+    ];
+
+    if let Some(minted) = minted {
+        accounts.push(AccountMeta::new_readonly(find_metadata(minted), false));
+    }
+
+    for user in users {
+        accounts.push(AccountMeta::new(airdrop.user_data(user), false));
+    }
+
+    Instruction::new_with_bytes(premint_airdrop::id(), &[29], accounts)
+}
+
+pub fn update_window_instruction(airdrop: &TestAirdrop, start_ts: i64, end_ts: i64) -> Instruction {
+    let mut data = vec![9];
+    data.extend_from_slice(&start_ts.to_le_bytes());
+    data.extend_from_slice(&end_ts.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &data,
+        vec![
+            AccountMeta::new(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+        ],
+    )
+}
+
+pub fn sweep_mint_authority_instruction(airdrop: &TestAirdrop) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[11],
+        vec![
+            AccountMeta::new_readonly(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+            AccountMeta::new(airdrop.mint_authority, false),
+            AccountMeta::new(airdrop.revenue_wallet, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Mints token into escrow of next airdrop index, paid by user
+pub fn mint_to_escrow_instruction(
+    airdrop: &TestAirdrop,
+    mint: &Pubkey,
+    user: &Pubkey,
+    payer: &Pubkey,
+    claimant: &Pubkey,
+    purchase_index: u64,
+) -> Instruction {
+    let (escrow, _) = find_escrow(&airdrop.config, purchase_index);
+    let mut instruction = mint_one_instruction(airdrop, mint, user, true, payer);
+    instruction.accounts[4].pubkey =
+        spl_associated_token_account::get_associated_token_address(&escrow, mint);
+    instruction.accounts.push(AccountMeta::new(escrow, false));
+    instruction.data = vec![15];
+    instruction.data.extend_from_slice(claimant.as_ref());
+
+    instruction
+}
+
+pub fn claim_from_escrow_instruction(
+    airdrop: &TestAirdrop,
+    escrow: &TestEscrow,
+    claimant: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[16],
+        vec![
+            AccountMeta::new_readonly(airdrop.config, false),
+            AccountMeta::new(escrow.escrow, false),
+            AccountMeta::new(escrow.token_account(), false),
+            AccountMeta::new(*claimant, true),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(claimant, &escrow.mint),
+                false,
+            ),
+            AccountMeta::new_readonly(escrow.mint, false),
+            AccountMeta::new(escrow.payer, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+    )
+}
+
+pub fn sweep_escrow_instruction(
+    airdrop: &TestAirdrop,
+    escrow: &TestEscrow,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[17],
+        vec![
+            AccountMeta::new_readonly(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+            AccountMeta::new(escrow.escrow, false),
+            AccountMeta::new(escrow.token_account(), false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new(escrow.payer, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+/// Deposits NFT held by airdrop authority in its associated token account
+pub fn deposit_preminted_instruction(
+    airdrop: &TestAirdrop,
+    mint: &Pubkey,
+    fee_payer: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_vault(&airdrop.config);
+
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[18],
+        vec![
+            AccountMeta::new_readonly(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(&vault, mint),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(
+                    &airdrop.authority.pubkey(),
+                    mint,
+                ),
+                false,
+            ),
+            AccountMeta::new(*fee_payer, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+    )
+}
+
+/// Claims queued NFT. User signs and pays the price
+pub fn claim_preminted_instruction(
+    airdrop: &TestAirdrop,
+    mint: &Pubkey,
+    user: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_vault(&airdrop.config);
+
+    let mut accounts = vec![
+        AccountMeta::new(airdrop.config, false),
+        AccountMeta::new(airdrop.user_data(user), false),
+        AccountMeta::new(*user, true),
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(user, mint),
+            false,
+        ),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address(&vault, mint),
+            false,
+        ),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(airdrop.authority.pubkey(), true),
+        AccountMeta::new(airdrop.revenue_wallet, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+    ];
+
+    for split in airdrop.revenue_splits.iter().filter(|s| s.is_used()) {
+        accounts.push(AccountMeta::new(split.wallet, false));
+    }
+
+    Instruction::new_with_bytes(premint_airdrop::id(), &[19], accounts)
+}
+
+/// Withdraws queued NFT to destination token account. Authority receives freed rent
+pub fn withdraw_unclaimed_instruction(
+    airdrop: &TestAirdrop,
+    mint: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_vault(&airdrop.config);
+
+    Instruction::new_with_bytes(
+        premint_airdrop::id(),
+        &[20],
+        vec![
+            AccountMeta::new_readonly(airdrop.config, false),
+            AccountMeta::new_readonly(airdrop.authority.pubkey(), true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address(&vault, mint),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new(airdrop.authority.pubkey(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+/// Mints one token to user. User signs and pays the price. Returns mint address
+pub async fn mint_one(
+    ctx: &mut ProgramTestContext,
+    airdrop: &TestAirdrop,
+    user: &Keypair,
+) -> Result<Pubkey, BanksClientError> {
+    let mint = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    let instruction = mint_one_instruction(airdrop, &mint.pubkey(), &user.pubkey(), true, &payer);
+
+    process(ctx, &[instruction], &[&mint, user, &airdrop.authority]).await?;
+
+    Ok(mint.pubkey())
+}
+
+pub fn system_account(lamports: u64) -> Account {
+    Account {
+        lamports,
+        data: vec![],
+        owner: system_program::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn program_account<T: Pack>(state: T) -> Account {
+    let mut data = vec![0u8; T::LEN];
+    state.pack_into_slice(&mut data);
+
+    Account {
+        lamports: Rent::default().minimum_balance(T::LEN),
+        data,
+        owner: premint_airdrop::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Adds initialized airdrop accounts to program test before start, skipping initialization
+pub fn add_airdrop(program_test: &mut ProgramTest, args: AirdropArgs) -> TestAirdrop {
+    add_airdrop_with_revenue_wallet(program_test, args, Pubkey::new_unique())
+}
+
+pub fn add_airdrop_with_revenue_wallet(
+    program_test: &mut ProgramTest,
+    args: AirdropArgs,
+    revenue_wallet: Pubkey,
+) -> TestAirdrop {
+    let config = Pubkey::new_unique();
+    let authority = Keypair::new();
+    let admin = Pubkey::new_unique();
+    let (mint_authority, mint_authority_bump) = find_mint_authority(&config);
+
+    program_test.add_account(revenue_wallet, system_account(1_000_000_000));
+    program_test.add_account(authority.pubkey(), system_account(1_000_000_000));
+    for split in args.revenue_splits.iter().filter(|s| s.is_used()) {
+        program_test.add_account(split.wallet, system_account(1_000_000_000));
+    }
+
+    program_test.add_account(
+        config,
+        airdrop_config_account(
+            &args,
+            &authority.pubkey(),
+            revenue_wallet,
+            admin,
+            mint_authority_bump,
+        ),
+    );
+
+    TestAirdrop {
+        config,
+        authority,
+        mint_authority,
+        revenue_wallet,
+        admin,
+        revenue_splits: args.revenue_splits,
+        registration_fee: args.registration_fee,
+    }
+}
+
+/// Adds airdrop owned by arbitrary authority, e.g. PDA of another program.
+/// Returns airdrop config address
+pub fn add_airdrop_with_authority(
+    program_test: &mut ProgramTest,
+    args: AirdropArgs,
+    authority: &Pubkey,
+) -> Pubkey {
+    let config = Pubkey::new_unique();
+    let (_, mint_authority_bump) = find_mint_authority(&config);
+
+    program_test.add_account(
+        config,
+        airdrop_config_account(
+            &args,
+            authority,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            mint_authority_bump,
+        ),
+    );
+
+    config
+}
+
+fn airdrop_config_account(
+    args: &AirdropArgs,
+    authority: &Pubkey,
+    revenue_wallet: Pubkey,
+    admin: Pubkey,
+    mint_authority_bump: u8,
+) -> Account {
+    program_account(AirdropConfig {
+        initialized: true,
+        airdrop_authority: *authority,
+        airdrop_index: 0,
+        airdrop_amount: args.airdrop_amount,
+        base_uri_len: args.base_uri.len() as u8,
+        base_uri: padded(&args.base_uri),
+        symbol: args.symbol,
+        airdrop_users: 0,
+        revenues_wallet: revenue_wallet,
+        admin_account: admin,
+        price: args.price,
+        revealed: args.placeholder_uri[0] == 0,
+        placeholder_uri: args.placeholder_uri,
+        auto_lock: args.auto_lock,
+        revenue_splits: args.revenue_splits,
+        charge_payer: args.charge_payer,
+        mint_authority_bump,
+        max_mints_per_tx: args.max_mints_per_tx,
+        nonce: 0,
+        trait_count: args.trait_count,
+        mint_cooldown_secs: args.mint_cooldown_secs,
+        referral_bps: args.referral_bps,
+        bot_tax_lamports: args.bot_tax_lamports,
+        name: args.name,
+        start_ts: 0,
+        end_ts: 0,
+        stage_count: args.stage_count,
+        expected_decimals: args.expected_decimals,
+        escrow_timeout_secs: args.escrow_timeout_secs,
+        mint_quantity: args.mint_quantity,
+        registration_fee: args.registration_fee,
+        registered_count: 0,
+        fairness_bps: args.fairness_bps,
+        raffle: args.raffle,
+        raffle_seed: [0; 32],
+        winners_count: 0,
+        is_active: true,
+        price_usd_cents: 0,
+        pyth_price_account: Pubkey::default(),
+        max_price_age_slots: 0,
+        finalized: false,
+        gifted_count: 0,
+        total_revenue: 0,
+        final_minted: 0,
+        final_gifted: 0,
+        final_registered: 0,
+        final_revenue: 0,
+        require_registration: args.require_registration,
+        deny_cpi_mint: args.deny_cpi_mint,
+        min_payer_balance: args.min_payer_balance,
+    })
+}
+
+/// Stage that is open at any time, with airdrop price and no allowlist or user limit
+pub fn open_stage(airdrop: &TestAirdrop, stage_index: u8) -> AirdropStage {
+    AirdropStage {
+        initialized: true,
+        airdrop: airdrop.config,
+        stage_index,
+        bump: find_stage(&airdrop.config, stage_index).1,
+        price: 100_000_000,
+        supply_cap: 10,
+        minted: 0,
+        start_ts: 0,
+        end_ts: i64::MAX,
+        merkle_root: [0; 32],
+        per_user_limit: 0,
+    }
+}
+
+/// Adds stage account to program test before start. Airdrop must count it in `stage_count`
+pub fn add_stage(program_test: &mut ProgramTest, stage: AirdropStage) -> Pubkey {
+    let (address, _) = find_stage(&stage.airdrop, stage.stage_index);
+    program_test.add_account(address, program_account(stage));
+
+    address
+}
+
+/// Accounts of a token minted into escrow
+pub struct TestEscrow {
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    pub payer: Pubkey,
+    pub purchase_index: u64,
+}
+
+impl TestEscrow {
+    pub fn token_account(&self) -> Pubkey {
+        spl_associated_token_account::get_associated_token_address(&self.escrow, &self.mint)
+    }
+}
+
+/// Token account of program test's built-in token program
+pub fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }
+    .pack_into_slice(&mut data);
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Mint of program test's built-in token program with no mint or freeze authority
+pub fn mint_account(supply: u64, decimals: u8) -> Account {
+    let mut data = vec![0u8; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        supply,
+        decimals,
+        is_initialized: true,
+        ..spl_token::state::Mint::default()
+    }
+    .pack_into_slice(&mut data);
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Token metadata account of `mint` listing `creators` as (address, verified)
+pub fn metadata_account(mint: &Pubkey, creators: &[(Pubkey, bool)]) -> Account {
+    fn push_string(data: &mut Vec<u8>, value: &str) {
+        data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        data.extend_from_slice(value.as_bytes());
+    }
+
+    // Borsh layout of metaplex_token_metadata::state::Metadata
+    let mut data = vec![metaplex_token_metadata::state::Key::MetadataV1 as u8];
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(mint.as_ref());
+    push_string(&mut data, "KATT #0");
+    push_string(&mut data, "KATT");
+    push_string(&mut data, "https://example.com/hidden.json");
+    data.extend_from_slice(&1000u16.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(&(creators.len() as u32).to_le_bytes());
+    for (address, verified) in creators {
+        data.extend_from_slice(address.as_ref());
+        data.push(*verified as u8);
+        data.push(0);
+    }
+    data.extend_from_slice(&[1, 1, 0]);
+    data.resize(metaplex_token_metadata::state::MAX_METADATA_LEN, 0);
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: metaplex_token_metadata::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Pyth price account with aggregate SOL/USD price of `price * 10^expo`, trading unless
+/// `status` says otherwise
+pub fn pyth_price_account(price: i64, expo: i32, status: u32, publish_slot: u64) -> Account {
+    let mut data = vec![0u8; 3312];
+    data[0..4].copy_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+    data[4..8].copy_from_slice(&2u32.to_le_bytes());
+    data[8..12].copy_from_slice(&3u32.to_le_bytes());
+    data[20..24].copy_from_slice(&expo.to_le_bytes());
+    data[208..216].copy_from_slice(&price.to_le_bytes());
+    data[224..228].copy_from_slice(&status.to_le_bytes());
+    data[232..240].copy_from_slice(&publish_slot.to_le_bytes());
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: Pubkey::new_unique(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Adds premint vault queueing given mints, claimed up to cursor. Token accounts are not added
+pub fn add_vault(
+    program_test: &mut ProgramTest,
+    airdrop: &TestAirdrop,
+    mints: &[Pubkey],
+    cursor: u32,
+) -> Pubkey {
+    let (vault, bump) = find_vault(&airdrop.config);
+    let mut data = vec![0u8; PremintVault::space(mints.len() as u32)];
+    PremintVault {
+        initialized: true,
+        airdrop: airdrop.config,
+        bump,
+        cursor,
+        len: mints.len() as u32,
+    }
+    .pack_into_slice(&mut data);
+
+    for (mint, chunk) in mints
+        .iter()
+        .zip(data[PremintVault::LEN..].chunks_exact_mut(32))
+    {
+        chunk.copy_from_slice(mint.as_ref());
+    }
+
+    program_test.add_account(
+        vault,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: premint_airdrop::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    vault
+}
+
+/// Adds locked single token mint sitting in escrow token account, as MintToEscrow leaves it
+pub fn add_escrow(
+    program_test: &mut ProgramTest,
+    airdrop: &TestAirdrop,
+    claimant: &Pubkey,
+    purchase_index: u64,
+    created_ts: i64,
+) -> TestEscrow {
+    let (escrow, bump) = find_escrow(&airdrop.config, purchase_index);
+    let mint = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+
+    program_test.add_account(mint, mint_account(1, 0));
+    program_test.add_account(payer, system_account(1_000_000_000));
+    program_test.add_account(
+        escrow,
+        program_account(AirdropEscrow {
+            initialized: true,
+            airdrop: airdrop.config,
+            purchase_index,
+            bump,
+            claimant: *claimant,
+            mint,
+            payer,
+            created_ts,
+        }),
+    );
+
+    let test_escrow = TestEscrow {
+        escrow,
+        mint,
+        payer,
+        purchase_index,
+    };
+    program_test.add_account(
+        test_escrow.token_account(),
+        token_account(&mint, &escrow, 1),
+    );
+
+    test_escrow
+}
+
+/// Adds funded user wallet registered in airdrop to program test before start
+pub fn add_user(program_test: &mut ProgramTest, airdrop: &TestAirdrop) -> Keypair {
+    add_locked_user(program_test, airdrop, 0)
+}
+
+/// Adds registered user that can not mint until `locked_till` timestamp
+pub fn add_locked_user(
+    program_test: &mut ProgramTest,
+    airdrop: &TestAirdrop,
+    locked_till: u64,
+) -> Keypair {
+    add_registered_user(program_test, airdrop, Keypair::new(), locked_till)
+}
+
+/// Same as [`add_locked_user`] for a given wallet
+pub fn add_registered_user(
+    program_test: &mut ProgramTest,
+    airdrop: &TestAirdrop,
+    user: Keypair,
+    locked_till: u64,
+) -> Keypair {
+    let (user_data, bump) = find_airdrop_user_data(&airdrop.config, &user.pubkey());
+
+    program_test.add_account(user.pubkey(), system_account(10_000_000_000));
+    program_test.add_account(
+        user_data,
+        program_account(AirdropUserData {
+            initialized: true,
+            airdrop: airdrop.config,
+            user: user.pubkey(),
+            mints_amount: 0,
+            locked_till,
+            bump,
+            nonce: 0,
+            registration_index: 0,
+            blocked: false,
+        }),
+    );
+
+    user
+}
+
+/// Creates funded user wallet registered in airdrop. Needs `test-bpf`
+pub async fn create_user(ctx: &mut ProgramTestContext, airdrop: &TestAirdrop) -> Keypair {
+    let user = Keypair::new();
+    fund(ctx, &user.pubkey(), 10_000_000_000).await;
+    register_user(ctx, airdrop, &user.pubkey()).await.unwrap();
+    user
+}
+
+/// Extracts airdrop error code from failed transaction
+pub fn airdrop_error(error: BanksClientError) -> Option<u32> {
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+    match error.unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+        _ => None,
+    }
+}
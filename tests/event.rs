@@ -0,0 +1,49 @@
+use premint_airdrop::event::MintEvent;
+use solana_program::{hash::hash, program_error::ProgramError, pubkey::Pubkey};
+
+fn event() -> MintEvent {
+    MintEvent {
+        user: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        index: 42,
+        price: 1_500_000_000,
+    }
+}
+
+#[test]
+fn mint_event_log_data_decodes_back() {
+    let event = event();
+
+    let data = event.to_log_data();
+
+    assert_eq!(data.len(), 8 + 32 + 32 + 8 + 8);
+    assert_eq!(MintEvent::from_log_data(&data), Ok(event));
+}
+
+#[test]
+fn mint_event_discriminator_follows_anchor_convention() {
+    let discriminator = &hash(b"event:MintEvent").to_bytes()[..8];
+
+    assert_eq!(MintEvent::DISCRIMINATOR, discriminator);
+    assert_eq!(&event().to_log_data()[..8], discriminator);
+}
+
+#[test]
+fn mint_event_rejects_other_event_data() {
+    let mut data = event().to_log_data();
+    data[0] ^= 1;
+    assert_eq!(
+        MintEvent::from_log_data(&data),
+        Err(ProgramError::InvalidAccountData)
+    );
+
+    let data = event().to_log_data();
+    assert_eq!(
+        MintEvent::from_log_data(&data[..data.len() - 1]),
+        Err(ProgramError::InvalidAccountData)
+    );
+    assert_eq!(
+        MintEvent::from_log_data(&[]),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
@@ -0,0 +1,37 @@
+use premint_airdrop::instruction::deserialize_instruction_data;
+use proptest::prelude::*;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2048))]
+
+    #[test]
+    fn random_bytes_never_panic(data in proptest::collection::vec(any::<u8>(), 0..10 * 1024)) {
+        let _ = deserialize_instruction_data(&data);
+    }
+
+    #[test]
+    fn known_instruction_ids_never_panic(
+        id in 0u8..16,
+        body in proptest::collection::vec(any::<u8>(), 0..512),
+    ) {
+        let mut data = vec![id];
+        data.extend_from_slice(&body);
+        let _ = deserialize_instruction_data(&data);
+    }
+}
+
+#[test]
+fn empty_input_is_rejected() {
+    assert!(deserialize_instruction_data(&[]).is_err());
+}
+
+#[test]
+fn truncated_initialize_airdrop_is_rejected() {
+    assert!(deserialize_instruction_data(&[1]).is_err());
+    assert!(deserialize_instruction_data(&[1; 64]).is_err());
+}
+
+#[test]
+fn truncated_increase_supply_is_rejected() {
+    assert!(deserialize_instruction_data(&[6, 1, 2, 3]).is_err());
+}
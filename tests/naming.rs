@@ -0,0 +1,62 @@
+use premint_airdrop::{
+    error::AirdropError,
+    naming::{build_mint_name, build_symbol, build_trait_mint_name, revealed_uri},
+    state::{MAX_AIRDROP_AMOUNT, MAX_URI_LENGTH},
+};
+use solana_program::program_error::ProgramError;
+
+#[test]
+fn symbol_is_trimmed() {
+    assert_eq!(build_symbol(b"KATT\0\0\0\0"), "KATT");
+    assert_eq!(build_symbol(b"KATTKATT"), "KATTKATT");
+    assert_eq!(build_symbol(&[0; 8]), "");
+}
+
+#[test]
+fn mint_name_has_symbol_and_index() {
+    assert_eq!(build_mint_name(b"KATT\0\0\0\0", 0), "KATT #0");
+    assert_eq!(build_mint_name(b"KATT\0\0\0\0", 42), "KATT #42");
+}
+
+#[test]
+fn mint_name_fits_metadata_for_largest_index() {
+    let name = build_mint_name(b"KATTKATT", u64::MAX);
+
+    assert_eq!(name, "KATTKATT #18446744073709551615");
+    assert!(name.len() <= metaplex_token_metadata::state::MAX_NAME_LENGTH);
+}
+
+#[test]
+fn trait_mint_name_has_symbol_index_and_trait() {
+    assert_eq!(build_trait_mint_name(b"KATT\0\0\0\0", 42, 3), "KATT #42 T3");
+}
+
+#[test]
+fn trait_mint_name_fits_metadata_for_largest_supply() {
+    let name = build_trait_mint_name(b"KATTKATT", MAX_AIRDROP_AMOUNT - 1, u8::MAX);
+
+    assert_eq!(name, "KATTKATT #999999 T255");
+    assert!(name.len() <= metaplex_token_metadata::state::MAX_NAME_LENGTH);
+}
+
+#[test]
+fn revealed_uri_joins_base_and_index() {
+    assert_eq!(
+        revealed_uri(b"https://arweave.net/abc", 7).unwrap(),
+        "https://arweave.net/abc/7.json"
+    );
+    assert_eq!(
+        revealed_uri(b"https://example.com/katts/", 7).unwrap(),
+        "https://example.com/katts/7.json"
+    );
+}
+
+#[test]
+fn revealed_uri_rejects_too_long_result() {
+    let base_uri = vec![b'a'; MAX_URI_LENGTH - "/7.json".len()];
+    assert_eq!(revealed_uri(&base_uri, 7).unwrap().len(), MAX_URI_LENGTH);
+    assert_eq!(
+        revealed_uri(&base_uri, 10).unwrap_err(),
+        ProgramError::from(AirdropError::UriTooLong)
+    );
+}
@@ -0,0 +1,115 @@
+use premint_airdrop::pda::{
+    create_airdrop_config, create_airdrop_user_data, create_escrow, create_mint_authority,
+    create_stage, create_vault, find_airdrop_config, find_airdrop_user_data, find_escrow,
+    find_mint_authority, find_stage, find_vault, AIRDROP_CONFIG_SEED, ESCROW_SEED,
+    MINT_AUTHORITY_SEED, STAGE_SEED, USER_DATA_SEED, VAULT_SEED,
+};
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+fn keys() -> Vec<Pubkey> {
+    (0..16u8)
+        .map(|i| Pubkey::new_from_array([i; 32]))
+        .chain((0..16).map(|_| Pubkey::new_unique()))
+        .collect()
+}
+
+#[test]
+fn seeds_are_stable() {
+    assert_eq!(AIRDROP_CONFIG_SEED, b"airdrop_config");
+    assert_eq!(USER_DATA_SEED, b"user_data");
+    assert_eq!(MINT_AUTHORITY_SEED, b"mint_authority");
+    assert_eq!(STAGE_SEED, b"stage");
+    assert_eq!(ESCROW_SEED, b"escrow");
+    assert_eq!(VAULT_SEED, b"vault");
+}
+
+#[test]
+fn find_and_create_airdrop_config_agree() {
+    for (i, authority) in keys().iter().enumerate() {
+        let name = [i as u8; 16];
+        let (address, bump) = find_airdrop_config(authority, &name);
+        assert_eq!(create_airdrop_config(authority, &name, bump), Ok(address));
+    }
+}
+
+#[test]
+fn one_authority_derives_config_per_name() {
+    let authority = Pubkey::new_unique();
+    let configs: Vec<Pubkey> = (0..16u8)
+        .map(|i| find_airdrop_config(&authority, &[i; 16]).0)
+        .collect();
+
+    for (i, config) in configs.iter().enumerate() {
+        assert!(!configs[i + 1..].contains(config));
+    }
+}
+
+#[test]
+fn find_and_create_user_data_agree() {
+    let keys = keys();
+    for (airdrop, user) in keys.iter().zip(keys.iter().rev()) {
+        let (address, bump) = find_airdrop_user_data(airdrop, user);
+        assert_eq!(create_airdrop_user_data(airdrop, user, bump), Ok(address));
+    }
+}
+
+#[test]
+fn find_and_create_mint_authority_agree() {
+    for airdrop in keys() {
+        let (address, bump) = find_mint_authority(&airdrop);
+        assert_eq!(create_mint_authority(&airdrop, bump), Ok(address));
+    }
+}
+
+#[test]
+fn find_and_create_stage_agree() {
+    for (i, airdrop) in keys().iter().enumerate() {
+        let (address, bump) = find_stage(airdrop, i as u8);
+        assert_eq!(create_stage(airdrop, i as u8, bump), Ok(address));
+        assert_ne!(find_stage(airdrop, i as u8 + 1).0, address);
+    }
+}
+
+#[test]
+fn find_and_create_escrow_agree() {
+    for (i, airdrop) in keys().iter().enumerate() {
+        let purchase_index = i as u64 * 1_000;
+        let (address, bump) = find_escrow(airdrop, purchase_index);
+        assert_eq!(create_escrow(airdrop, purchase_index, bump), Ok(address));
+        assert_ne!(find_escrow(airdrop, purchase_index + 1).0, address);
+    }
+}
+
+#[test]
+fn find_and_create_vault_agree() {
+    for airdrop in keys() {
+        let (address, bump) = find_vault(&airdrop);
+        assert_eq!(create_vault(&airdrop, bump), Ok(address));
+        assert_ne!(find_escrow(&airdrop, 0).0, address);
+    }
+}
+
+#[test]
+fn derivations_match_golden_addresses() {
+    let authority = Pubkey::new_from_array([1; 32]);
+    let user = Pubkey::new_from_array([2; 32]);
+    let name = *b"katts\0\0\0\0\0\0\0\0\0\0\0";
+
+    let (airdrop, _) = find_airdrop_config(&authority, &name);
+    let (user_data, _) = find_airdrop_user_data(&airdrop, &user);
+    let (mint_authority, _) = find_mint_authority(&airdrop);
+
+    assert_eq!(
+        airdrop,
+        Pubkey::from_str("2pZzZiLm3vRx8JzZgrLfm3qPh2G2mzPQrVcEhnGTr8B8").unwrap()
+    );
+    assert_eq!(
+        user_data,
+        Pubkey::from_str("6gPv7Hmm44EPBzvtU7QfewUAAT8GH8ro4c7x9R3NuJZ").unwrap()
+    );
+    assert_eq!(
+        mint_authority,
+        Pubkey::from_str("43GEfQqRgV31mgjxtMENYWz7FiBbudUzNnrL2QrxEhbv").unwrap()
+    );
+}
@@ -0,0 +1,341 @@
+use premint_airdrop::error::AirdropError;
+use premint_airdrop::state::{
+    pack_revenue_splits, unpack_revenue_splits, AirdropConfig, AirdropEscrow, AirdropStage,
+    AirdropUserData, PremintVault, RevenueSplit, MAX_REVENUE_SPLITS, REVENUE_SPLITS_LEN,
+    TOKEN_METADATA_PROGRAM_ID,
+};
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+};
+
+fn user_data() -> AirdropUserData {
+    AirdropUserData {
+        initialized: true,
+        airdrop: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+        mints_amount: 2,
+        locked_till: 1_650_000_000,
+        bump: 254,
+        nonce: 7,
+        registration_index: 3,
+        blocked: true,
+    }
+}
+
+#[test]
+fn user_data_round_trips() {
+    let user_data = user_data();
+    let mut data = vec![0; AirdropUserData::LEN];
+    user_data.pack_into_slice(&mut data);
+
+    let unpacked = AirdropUserData::unpack(&data).unwrap();
+    assert!(unpacked.initialized);
+    assert_eq!(unpacked.airdrop, user_data.airdrop);
+    assert_eq!(unpacked.user, user_data.user);
+    assert_eq!(unpacked.mints_amount, 2);
+    assert_eq!(unpacked.locked_till, 1_650_000_000);
+    assert_eq!(unpacked.bump, 254);
+    assert_eq!(unpacked.nonce, 7);
+}
+
+#[test]
+fn user_data_unpacks_from_account() {
+    let user_data = user_data();
+    let mut data = vec![0; AirdropUserData::LEN];
+    user_data.pack_into_slice(&mut data);
+
+    let key = Pubkey::new_unique();
+    let owner = premint_airdrop::id();
+    let mut lamports = 0;
+    let account = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    let unpacked = AirdropUserData::unpack_from_account(&account).unwrap();
+    assert_eq!(unpacked.user, user_data.user);
+    assert_eq!(unpacked.mints_amount, 2);
+}
+
+#[test]
+fn airdrop_config_rejects_undersized_account() {
+    let mut data = vec![0; AirdropConfig::LEN - 1];
+
+    let key = Pubkey::new_unique();
+    let owner = premint_airdrop::id();
+    let mut lamports = 0;
+    let account = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    assert_eq!(
+        AirdropConfig::unpack_from_account(&account).err(),
+        Some(AirdropError::InvalidAccountData.into())
+    );
+}
+
+#[test]
+fn airdrop_index_accessors_agree_with_pack() {
+    let mut config = AirdropConfig::unpack_unchecked(&[0; AirdropConfig::LEN]).unwrap();
+    config.initialized = true;
+    config.airdrop_authority = Pubkey::new_from_array([0xff; 32]);
+    config.airdrop_index = 0x0102_0304_0506_0708;
+    config.airdrop_amount = u64::MAX;
+    config.price = u64::MAX;
+
+    let mut data = vec![0; AirdropConfig::LEN];
+    config.pack_into_slice(&mut data);
+
+    let key = Pubkey::new_unique();
+    let owner = premint_airdrop::id();
+    let mut lamports = 0;
+    let account = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    assert_eq!(
+        AirdropConfig::read_airdrop_index(&account).unwrap(),
+        config.airdrop_index
+    );
+    assert_eq!(
+        AirdropConfig::increment_airdrop_index(&account).unwrap(),
+        config.airdrop_index + 1
+    );
+
+    // Only the index changes, exactly as if the whole config was repacked
+    config.airdrop_index += 1;
+    let mut expected = vec![0; AirdropConfig::LEN];
+    config.pack_into_slice(&mut expected);
+    assert_eq!(*account.data.borrow(), &expected[..]);
+    assert_eq!(
+        AirdropConfig::read_airdrop_index(&account).unwrap(),
+        config.airdrop_index
+    );
+}
+
+#[test]
+fn mint_stats_accessor_agrees_with_pack() {
+    let mut config = AirdropConfig::unpack_unchecked(&[0; AirdropConfig::LEN]).unwrap();
+    config.initialized = true;
+    config.final_minted = u64::MAX;
+    config.gifted_count = 0x0102_0304_0506_0708;
+    config.total_revenue = u64::MAX - 1;
+    config.min_payer_balance = u64::MAX;
+
+    let mut data = vec![0; AirdropConfig::LEN];
+    config.pack_into_slice(&mut data);
+
+    let key = Pubkey::new_unique();
+    let owner = premint_airdrop::id();
+    let mut lamports = 0;
+    let account = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    AirdropConfig::add_mint_stats(&account, 5, true).unwrap();
+
+    // Only the statistics change, revenue saturates as it did when the config was repacked
+    config.gifted_count += 1;
+    config.total_revenue = u64::MAX;
+    let mut expected = vec![0; AirdropConfig::LEN];
+    config.pack_into_slice(&mut expected);
+    assert_eq!(*account.data.borrow(), &expected[..]);
+}
+
+#[test]
+fn user_data_rejects_zeroed_buffer() {
+    let data = vec![0; AirdropUserData::LEN];
+
+    assert_eq!(
+        AirdropUserData::unpack_initialized(&data).unwrap_err(),
+        AirdropError::Uninitialized.into()
+    );
+}
+
+#[test]
+fn user_data_rejects_undersized_buffer() {
+    let data = vec![1; AirdropUserData::LEN - 1];
+
+    assert_eq!(
+        AirdropUserData::unpack_unchecked(&data).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+}
+
+#[test]
+fn user_data_rejects_invalid_initialized_flag() {
+    let mut data = vec![0; AirdropUserData::LEN];
+    data[0] = 2;
+
+    assert_eq!(
+        AirdropUserData::unpack_unchecked(&data).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+}
+
+#[test]
+fn airdrop_config_rejects_undersized_buffer() {
+    let data = vec![1; AirdropConfig::LEN - 1];
+
+    assert_eq!(
+        AirdropConfig::unpack_unchecked(&data).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+}
+
+#[test]
+fn revenue_splits_round_trip() {
+    let mut splits = [RevenueSplit::default(); MAX_REVENUE_SPLITS];
+    splits[0] = RevenueSplit {
+        wallet: Pubkey::new_unique(),
+        basis_points: 7000,
+    };
+    splits[1] = RevenueSplit {
+        wallet: Pubkey::new_unique(),
+        basis_points: 3000,
+    };
+
+    let mut data = [0; REVENUE_SPLITS_LEN];
+    pack_revenue_splits(&splits, &mut data);
+    let unpacked = unpack_revenue_splits(&data);
+
+    for (split, unpacked) in splits.iter().zip(unpacked.iter()) {
+        assert_eq!(split.wallet, unpacked.wallet);
+        assert_eq!(split.basis_points, unpacked.basis_points);
+    }
+}
+
+fn stage(start_ts: i64, end_ts: i64) -> AirdropStage {
+    AirdropStage {
+        initialized: true,
+        airdrop: Pubkey::new_unique(),
+        stage_index: 1,
+        bump: 253,
+        price: 50_000_000,
+        supply_cap: 100,
+        minted: 3,
+        start_ts,
+        end_ts,
+        merkle_root: [7; 32],
+        per_user_limit: 2,
+    }
+}
+
+#[test]
+fn stage_round_trips() {
+    let stage = stage(100, 200);
+    let mut data = vec![0; AirdropStage::LEN];
+    stage.pack_into_slice(&mut data);
+
+    let unpacked = AirdropStage::unpack(&data).unwrap();
+    assert_eq!(unpacked.airdrop, stage.airdrop);
+    assert_eq!(unpacked.stage_index, 1);
+    assert_eq!(unpacked.bump, 253);
+    assert_eq!(unpacked.price, 50_000_000);
+    assert_eq!(unpacked.supply_cap, 100);
+    assert_eq!(unpacked.minted, 3);
+    assert_eq!(unpacked.start_ts, 100);
+    assert_eq!(unpacked.end_ts, 200);
+    assert_eq!(unpacked.merkle_root, [7; 32]);
+    assert_eq!(unpacked.per_user_limit, 2);
+}
+
+#[test]
+fn adjacent_stages_do_not_overlap() {
+    assert!(!stage(100, 200).overlaps(&stage(200, 300)));
+    assert!(!stage(200, 300).overlaps(&stage(100, 200)));
+    assert!(stage(100, 201).overlaps(&stage(200, 300)));
+    assert!(stage(100, 400).overlaps(&stage(200, 300)));
+}
+
+#[test]
+fn escrow_round_trips() {
+    let escrow = AirdropEscrow {
+        initialized: true,
+        airdrop: Pubkey::new_unique(),
+        purchase_index: 42,
+        bump: 252,
+        claimant: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        payer: Pubkey::new_unique(),
+        created_ts: 1_700_000_000,
+    };
+    let mut data = vec![0; AirdropEscrow::LEN];
+    escrow.pack_into_slice(&mut data);
+
+    assert_eq!(AirdropEscrow::unpack(&data).unwrap(), escrow);
+}
+
+#[test]
+fn vault_reads_queued_mints() {
+    let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let vault = PremintVault {
+        initialized: true,
+        airdrop: Pubkey::new_unique(),
+        bump: 251,
+        cursor: 1,
+        len: 2,
+    };
+    let mut data = vec![0; PremintVault::space(2)];
+    vault.pack_into_slice(&mut data);
+    data[PremintVault::LEN..].copy_from_slice(&[mints[0].to_bytes(), mints[1].to_bytes()].concat());
+
+    let key = Pubkey::new_unique();
+    let owner = premint_airdrop::id();
+    let mut lamports = 0;
+    let account = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    let unpacked = PremintVault::unpack_from_account(&account).unwrap();
+    assert_eq!(unpacked, vault);
+    assert_eq!(PremintVault::mint_at(&account, 0), Ok(mints[0]));
+    assert_eq!(unpacked.next_mint(&account), Ok(Some(mints[1])));
+    assert_eq!(
+        PremintVault { cursor: 2, ..vault }.next_mint(&account),
+        Ok(None)
+    );
+    assert_eq!(
+        PremintVault::mint_at(&account, 2),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+fn token_metadata_program_id_matches_metaplex() {
+    assert_eq!(TOKEN_METADATA_PROGRAM_ID, metaplex_token_metadata::id());
+}
@@ -0,0 +1,276 @@
+use premint_airdrop::error::AirdropError;
+use premint_airdrop::util::{
+    allowlist_leaf, calculate_protocol_fee, derive_trait_id, fair_mint_limit, is_raffle_winner,
+    read_pyth_price, str_from_u8_nul_utf8, usd_cents_to_lamports, verify_merkle_proof,
+};
+use solana_program::{
+    account_info::AccountInfo, hash::hashv, program_error::ProgramError, pubkey::Pubkey, sysvar,
+};
+
+#[test]
+fn padded_string_is_trimmed() {
+    assert_eq!(str_from_u8_nul_utf8(b"KATT\0\0\0\0"), Some("KATT"));
+    assert_eq!(str_from_u8_nul_utf8(b"KATTKATT"), Some("KATTKATT"));
+}
+
+#[test]
+fn all_zero_string_is_empty() {
+    assert_eq!(str_from_u8_nul_utf8(&[0; 8]), Some(""));
+}
+
+#[test]
+fn embedded_nul_is_rejected() {
+    assert_eq!(str_from_u8_nul_utf8(b"KA\0TT\0\0\0"), None);
+    assert_eq!(str_from_u8_nul_utf8(b"\0KATT\0\0\0"), None);
+}
+
+#[test]
+fn multi_byte_char_split_at_boundary_is_rejected() {
+    // 'é' is 0xC3 0xA9, only first byte fits
+    let mut src = [b'a'; 8];
+    src[7] = 0xC3;
+    assert_eq!(str_from_u8_nul_utf8(&src), None);
+
+    let mut src = [0; 8];
+    src[..3].copy_from_slice("aé".as_bytes());
+    assert_eq!(str_from_u8_nul_utf8(&src), Some("aé"));
+}
+
+fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    match a <= b {
+        true => hashv(&[&a, &b]).to_bytes(),
+        false => hashv(&[&b, &a]).to_bytes(),
+    }
+}
+
+#[test]
+fn merkle_proof_of_every_leaf_is_accepted() {
+    let leaves: Vec<[u8; 32]> = (0..4)
+        .map(|_| allowlist_leaf(&Pubkey::new_unique()))
+        .collect();
+    let left = hash_pair(leaves[0], leaves[1]);
+    let right = hash_pair(leaves[2], leaves[3]);
+    let root = hash_pair(left, right);
+
+    assert!(verify_merkle_proof(leaves[0], &[leaves[1], right], &root));
+    assert!(verify_merkle_proof(leaves[1], &[leaves[0], right], &root));
+    assert!(verify_merkle_proof(leaves[2], &[leaves[3], left], &root));
+    assert!(verify_merkle_proof(leaves[3], &[leaves[2], left], &root));
+}
+
+#[test]
+fn merkle_proof_of_other_leaf_is_rejected() {
+    let leaves: Vec<[u8; 32]> = (0..2)
+        .map(|_| allowlist_leaf(&Pubkey::new_unique()))
+        .collect();
+    let root = hash_pair(leaves[0], leaves[1]);
+    let outsider = allowlist_leaf(&Pubkey::new_unique());
+
+    assert!(!verify_merkle_proof(outsider, &[leaves[1]], &root));
+    assert!(!verify_merkle_proof(leaves[0], &[], &root));
+}
+
+#[test]
+fn protocol_fee_leaves_rounding_dust_to_project() {
+    // Fee is rounded down, project receives price minus fee
+    for (price, fee) in [
+        (0, 0),
+        (1, 0),
+        (39, 0),
+        (40, 1),
+        (41, 1),
+        (1_000_000_001, 25_000_000),
+    ] {
+        assert_eq!(calculate_protocol_fee(price, 250), Ok(fee));
+    }
+
+    assert_eq!(calculate_protocol_fee(1_000_000_000, 0), Ok(0));
+    assert_eq!(calculate_protocol_fee(u64::MAX, 10_000), Ok(u64::MAX));
+    assert_eq!(calculate_protocol_fee(u64::MAX, 1), Ok(u64::MAX / 10_000));
+}
+
+#[test]
+fn fair_mint_limit_is_share_of_remaining_supply() {
+    assert_eq!(fair_mint_limit(1_000, 1_000), 100);
+    assert_eq!(fair_mint_limit(4, 5_000), 2);
+    assert_eq!(fair_mint_limit(u64::MAX, 10_000), u64::MAX);
+}
+
+#[test]
+fn fair_mint_limit_allows_last_mints() {
+    assert_eq!(fair_mint_limit(3, 1_000), 1);
+    assert_eq!(fair_mint_limit(0, 1_000), 1);
+}
+
+#[test]
+fn raffle_winners_are_deterministic_for_seed() {
+    let seed = [7; 32];
+    let winners = |seed: &[u8; 32]| -> Vec<u64> {
+        (0..100)
+            .filter(|&index| is_raffle_winner(seed, index, 100, 10))
+            .collect()
+    };
+
+    assert_eq!(winners(&seed), winners(&seed));
+    assert!(!winners(&seed).is_empty());
+    assert_ne!(winners(&seed), winners(&[8; 32]));
+}
+
+#[test]
+fn raffle_without_entrants_has_no_winners() {
+    assert!(!is_raffle_winner(&[7; 32], 0, 0, 10));
+}
+
+#[test]
+fn raffle_with_enough_winners_selects_everyone() {
+    assert!((0..50).all(|index| is_raffle_winner(&[7; 32], index, 50, 50)));
+}
+
+#[test]
+fn usd_price_converts_with_pyth_exponent() {
+    // $50 at $25 per SOL
+    assert_eq!(
+        usd_cents_to_lamports(5_000, 2_500_000_000, -8),
+        Some(2_000_000_000)
+    );
+    assert_eq!(usd_cents_to_lamports(5_000, 25, 0), Some(2_000_000_000));
+    assert_eq!(usd_cents_to_lamports(5_000, 5, 9), Some(10));
+}
+
+#[test]
+fn usd_price_rounds_lamports_up() {
+    // 1 cent at $3 per SOL is 3_333_333.33 lamports
+    assert_eq!(usd_cents_to_lamports(1, 300_000_000, -8), Some(3_333_334));
+}
+
+#[test]
+fn usd_price_rejects_non_positive_price_and_overflow() {
+    assert_eq!(usd_cents_to_lamports(5_000, 0, -8), None);
+    assert_eq!(usd_cents_to_lamports(5_000, -2_500_000_000, -8), None);
+    assert_eq!(usd_cents_to_lamports(u64::MAX, 1, -40), None);
+    assert_eq!(usd_cents_to_lamports(u64::MAX, 1, -8), None);
+}
+
+/// Pyth SOL/USD price account serialized field by field in declaration order of version 2
+/// `PriceAccount` of pyth-sdk-solana. Every field holds its own value, so misplaced offset
+/// reads a wrong one
+fn pyth_price_data(version: u32, account_type: u32) -> Vec<u8> {
+    let mut data = vec![];
+    data.extend_from_slice(&0xa1b2_c3d4u32.to_le_bytes()); // magic
+    data.extend_from_slice(&version.to_le_bytes()); // ver
+    data.extend_from_slice(&account_type.to_le_bytes()); // atype
+    data.extend_from_slice(&3312u32.to_le_bytes()); // size
+    data.extend_from_slice(&1u32.to_le_bytes()); // ptype, price
+    data.extend_from_slice(&(-8i32).to_le_bytes()); // expo
+    data.extend_from_slice(&19u32.to_le_bytes()); // num
+    data.extend_from_slice(&18u32.to_le_bytes()); // num_qt
+    data.extend_from_slice(&1_001u64.to_le_bytes()); // last_slot
+    data.extend_from_slice(&1_002u64.to_le_bytes()); // valid_slot
+    for value in [2_400_000_000i64, 2_400_000_001, 1] {
+        data.extend_from_slice(&value.to_le_bytes()); // ema_price
+    }
+    for value in [1_200_000i64, 1_200_001, 1] {
+        data.extend_from_slice(&value.to_le_bytes()); // ema_conf
+    }
+    data.extend_from_slice(&1_650_000_000i64.to_le_bytes()); // timestamp
+    data.extend_from_slice(&[3, 4]); // min_pub, drv2
+    data.extend_from_slice(&5u16.to_le_bytes()); // drv3
+    data.extend_from_slice(&6u32.to_le_bytes()); // drv4
+    data.extend_from_slice(&[0x11; 32]); // prod
+    data.extend_from_slice(&[0x22; 32]); // next
+    data.extend_from_slice(&999u64.to_le_bytes()); // prev_slot
+    data.extend_from_slice(&2_450_000_000i64.to_le_bytes()); // prev_price
+    data.extend_from_slice(&1_400_000u64.to_le_bytes()); // prev_conf
+    data.extend_from_slice(&1_649_999_999i64.to_le_bytes()); // prev_timestamp
+    data.extend_from_slice(&2_500_000_000i64.to_le_bytes()); // agg.price
+    data.extend_from_slice(&1_500_000u64.to_le_bytes()); // agg.conf
+    data.extend_from_slice(&1u32.to_le_bytes()); // agg.status, trading
+    data.extend_from_slice(&7u32.to_le_bytes()); // agg.corp_act
+    data.extend_from_slice(&1_000u64.to_le_bytes()); // agg.pub_slot
+    data.extend_from_slice(&[0x33; 32 * 96]); // comp, publisher price components
+    assert_eq!(data.len(), 3312);
+    data
+}
+
+#[test]
+fn pyth_price_is_read_from_aggregate_price() {
+    assert_eq!(
+        read_pyth_price(&pyth_price_data(2, 3), 1_010, 25),
+        Ok((2_500_000_000, -8))
+    );
+}
+
+#[test]
+fn pyth_price_rejects_stale_aggregate_price() {
+    assert_eq!(
+        read_pyth_price(&pyth_price_data(2, 3), 1_026, 25),
+        Err(AirdropError::StaleOracle.into())
+    );
+}
+
+#[test]
+fn pyth_price_rejects_other_accounts() {
+    // Mapping and product accounts, then price account of other version
+    for (version, account_type) in [(2, 1), (2, 2), (1, 3), (3, 3)] {
+        assert_eq!(
+            read_pyth_price(&pyth_price_data(version, account_type), 1_010, 25),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    let mut data = pyth_price_data(2, 3);
+    data[0] ^= 1;
+    assert_eq!(
+        read_pyth_price(&data, 1_010, 25),
+        Err(ProgramError::InvalidAccountData)
+    );
+
+    let data = pyth_price_data(2, 3);
+    assert_eq!(
+        read_pyth_price(&data[..3311], 1_010, 25),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
+
+/// Slot hashes sysvar data: entry count, then (slot, hash) entries, most recent first
+fn slot_hashes_data(hashes: &[[u8; 32]]) -> Vec<u8> {
+    let mut data = (hashes.len() as u64).to_le_bytes().to_vec();
+    for (age, hash) in hashes.iter().enumerate() {
+        data.extend_from_slice(&(1_000 - age as u64).to_le_bytes());
+        data.extend_from_slice(hash);
+    }
+    data
+}
+
+fn trait_ids(mut data: Vec<u8>, trait_count: u8) -> Result<Vec<u8>, ProgramError> {
+    let key = sysvar::slot_hashes::id();
+    let mut lamports = 0;
+    let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &key, false, 0);
+
+    (0..8)
+        .map(|index| derive_trait_id(&account, index, trait_count))
+        .collect()
+}
+
+#[test]
+fn trait_id_is_derived_from_most_recent_slot_hash() {
+    let trait_ids_of_recent = trait_ids(slot_hashes_data(&[[7; 32], [1; 32]]), 5).unwrap();
+
+    assert_eq!(trait_ids_of_recent, vec![2, 0, 0, 3, 2, 1, 4, 3]);
+    assert_eq!(
+        trait_ids(slot_hashes_data(&[[7; 32], [2; 32]]), 5).unwrap(),
+        trait_ids_of_recent
+    );
+    assert_ne!(
+        trait_ids(slot_hashes_data(&[[8; 32], [1; 32]]), 5).unwrap(),
+        trait_ids_of_recent
+    );
+}
+
+#[test]
+fn trait_id_requires_slot_hash() {
+    assert_eq!(
+        trait_ids(slot_hashes_data(&[]), 5).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+}
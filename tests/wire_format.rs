@@ -0,0 +1,178 @@
+use premint_airdrop::{
+    instruction::{deserialize_instruction_data, AirdropInstruction, InitializeAirdropArgs},
+    state::{RevenueSplit, MAX_REVENUE_SPLITS},
+};
+use solana_program::pubkey::Pubkey;
+
+/// Annotated hex fixture, `#` starts a comment
+fn golden_bytes(fixture: &str) -> Vec<u8> {
+    let hex: String = fixture
+        .lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .collect();
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn initialize_airdrop_golden() -> Vec<u8> {
+    golden_bytes(include_str!("fixtures/initialize_airdrop.hex"))
+}
+
+fn initialize_airdrop_args() -> InitializeAirdropArgs {
+    let mut revenue_splits = [RevenueSplit::default(); MAX_REVENUE_SPLITS];
+    revenue_splits[0] = RevenueSplit {
+        wallet: Pubkey::new_from_array([0x22; 32]),
+        basis_points: 2_500,
+    };
+
+    let mut name = [0; 16];
+    name[..11].copy_from_slice(b"Elder Katts");
+
+    InitializeAirdropArgs {
+        airdrop_amount: 0x0102_0304_0506_0708,
+        base_uri: b"ipfs".to_vec(),
+        symbol: *b"KATT\0\0\0\0",
+        price: 1_000_000_000,
+        placeholder_uri: [0x11; 32],
+        auto_lock: true,
+        revenue_splits,
+        charge_payer: false,
+        max_mints_per_tx: 5,
+        name,
+        trait_count: 7,
+        mint_cooldown_secs: 21_600,
+        referral_bps: 250,
+        bot_tax_lamports: 0x1122_3344_5566_7788,
+        expected_decimals: 0,
+        escrow_timeout_secs: 86_400,
+        mint_quantity: 1,
+        registration_fee: 5_000,
+        fairness_bps: 10_000,
+        raffle: true,
+        require_registration: true,
+        deny_cpi_mint: true,
+        min_payer_balance: 0x0102030405060708,
+    }
+}
+
+#[test]
+fn initialize_airdrop_golden_bytes_parse() {
+    let expected = initialize_airdrop_args();
+
+    let parsed = match deserialize_instruction_data(&initialize_airdrop_golden()) {
+        Ok(AirdropInstruction::InitializeAirdrop(args)) => args,
+        _ => panic!("golden bytes must parse as InitializeAirdrop"),
+    };
+
+    assert_eq!(parsed.airdrop_amount, expected.airdrop_amount);
+    assert_eq!(parsed.base_uri, expected.base_uri);
+    assert_eq!(parsed.symbol, expected.symbol);
+    assert_eq!(parsed.price, expected.price);
+    assert_eq!(parsed.placeholder_uri, expected.placeholder_uri);
+    assert_eq!(parsed.auto_lock, expected.auto_lock);
+    for (parsed, expected) in parsed
+        .revenue_splits
+        .iter()
+        .zip(expected.revenue_splits.iter())
+    {
+        assert_eq!(parsed.wallet, expected.wallet);
+        assert_eq!(parsed.basis_points, expected.basis_points);
+    }
+    assert_eq!(parsed.charge_payer, expected.charge_payer);
+    assert_eq!(parsed.max_mints_per_tx, expected.max_mints_per_tx);
+    assert_eq!(parsed.name, expected.name);
+    assert_eq!(parsed.trait_count, expected.trait_count);
+    assert_eq!(parsed.mint_cooldown_secs, expected.mint_cooldown_secs);
+    assert_eq!(parsed.referral_bps, expected.referral_bps);
+    assert_eq!(parsed.bot_tax_lamports, expected.bot_tax_lamports);
+    assert_eq!(parsed.expected_decimals, expected.expected_decimals);
+    assert_eq!(parsed.escrow_timeout_secs, expected.escrow_timeout_secs);
+    assert_eq!(parsed.mint_quantity, expected.mint_quantity);
+    assert_eq!(parsed.registration_fee, expected.registration_fee);
+    assert_eq!(parsed.fairness_bps, expected.fairness_bps);
+    assert_eq!(parsed.raffle, expected.raffle);
+    assert_eq!(parsed.require_registration, expected.require_registration);
+    assert_eq!(parsed.deny_cpi_mint, expected.deny_cpi_mint);
+    assert_eq!(parsed.min_payer_balance, expected.min_payer_balance);
+}
+
+#[test]
+fn initialize_airdrop_integers_are_little_endian() {
+    let golden = initialize_airdrop_golden();
+
+    // Airdrop amount follows instruction id, least significant byte first
+    assert_eq!(golden[0], 10);
+    assert_eq!(&golden[1..9], &[8, 7, 6, 5, 4, 3, 2, 1]);
+
+    // Base URI is length prefixed at the tail
+    assert_eq!(&golden[golden.len() - 5..], &[4, b'i', b'p', b'f', b's']);
+}
+
+#[test]
+fn initialize_airdrop_rejects_golden_bytes_with_trailing_byte() {
+    let mut data = initialize_airdrop_golden();
+    data.push(0);
+
+    assert!(deserialize_instruction_data(&data).is_err());
+}
+
+#[test]
+fn initialize_airdrop_legacy_golden_bytes_parse_with_defaults() {
+    let data = golden_bytes(include_str!("fixtures/initialize_airdrop_legacy.hex"));
+    assert_eq!(data.len(), 1 + 8 + 32 + 86 + 34 * MAX_REVENUE_SPLITS);
+    let expected = initialize_airdrop_args();
+
+    let parsed = match deserialize_instruction_data(&data) {
+        Ok(AirdropInstruction::InitializeAirdrop(args)) => args,
+        _ => panic!("legacy golden bytes must parse as InitializeAirdrop"),
+    };
+
+    assert_eq!(parsed.airdrop_amount, expected.airdrop_amount);
+    assert_eq!(parsed.base_uri, expected.base_uri);
+    assert_eq!(parsed.symbol, expected.symbol);
+    assert_eq!(parsed.price, expected.price);
+    assert_eq!(parsed.placeholder_uri, expected.placeholder_uri);
+    assert_eq!(parsed.auto_lock, expected.auto_lock);
+    assert_eq!(
+        parsed.revenue_splits[0].wallet,
+        expected.revenue_splits[0].wallet
+    );
+    assert_eq!(parsed.revenue_splits[0].basis_points, 2_500);
+    assert_eq!(parsed.charge_payer, expected.charge_payer);
+    assert_eq!(parsed.max_mints_per_tx, expected.max_mints_per_tx);
+    assert_eq!(parsed.name, expected.name);
+    assert_eq!(parsed.trait_count, expected.trait_count);
+    assert_eq!(parsed.mint_cooldown_secs, expected.mint_cooldown_secs);
+    assert_eq!(parsed.referral_bps, expected.referral_bps);
+    assert_eq!(parsed.bot_tax_lamports, expected.bot_tax_lamports);
+
+    // Not part of the frozen layout
+    assert_eq!(parsed.expected_decimals, 0);
+    assert_eq!(parsed.escrow_timeout_secs, 0);
+    assert_eq!(parsed.mint_quantity, 1);
+    assert_eq!(parsed.registration_fee, 0);
+    assert_eq!(parsed.fairness_bps, 0);
+    assert!(!parsed.raffle);
+    assert!(parsed.require_registration);
+    assert!(!parsed.deny_cpi_mint);
+    assert_eq!(parsed.min_payer_balance, 0);
+}
+
+#[test]
+fn initialize_airdrop_legacy_rejects_current_fields() {
+    let mut data = golden_bytes(include_str!("fixtures/initialize_airdrop_legacy.hex"));
+    data.extend_from_slice(&[0; 38]);
+
+    assert!(deserialize_instruction_data(&data).is_err());
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn initialize_airdrop_client_packs_golden_bytes() {
+    let data = premint_airdrop::client::pack_initialize_airdrop_args(&initialize_airdrop_args());
+
+    assert_eq!(data, initialize_airdrop_golden());
+}